@@ -0,0 +1,731 @@
+use super::commands::CommandRequest;
+use super::link_stats::LinkStats;
+use crate::as_str::AsStr;
+use crate::command::Command;
+use crate::xbee::{Address, DeliveryStatus, TxStatus};
+use chrono::{DateTime, Utc};
+use enum_iterator::Sequence;
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// How urgently a queued command should be sent, the most urgent commands are sent first
+#[derive(Sequence, Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Priority {
+    /// Commands sent by hand, which should never wait behind automated commands
+    Urgent,
+    /// The stream of simulated pressure readings
+    Simulation,
+    /// Other automated commands, e.g. from mission scripts
+    Background,
+}
+
+impl AsStr for Priority {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Priority::Urgent => "Urgent",
+            Priority::Simulation => "Simulation",
+            Priority::Background => "Background",
+        }
+    }
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// the different states a command can have
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum CommandStatus {
+    // used if the radio isn't connected, or the command is waiting to be retried
+    Unsent,
+    // sent but no status
+    Sent { frame_id: u8 },
+    // sent and status received
+    SentStatus { status: DeliveryStatus },
+    // sent but no status was received in time
+    TimedOut,
+    // removed from the queue before it was sent
+    Cancelled,
+}
+
+impl CommandStatus {
+    /// Could the command have reached the CanSat, or might it still once it is sent?
+    /// False once the radio has reported it undelivered or it was cancelled.
+    pub fn maybe_delivered(&self) -> bool {
+        match self {
+            CommandStatus::Unsent | CommandStatus::Sent { .. } | CommandStatus::TimedOut => true,
+            CommandStatus::SentStatus { status } => *status == DeliveryStatus::Success,
+            CommandStatus::Cancelled => false,
+        }
+    }
+}
+
+// whether the flight software has reported running a command in CMD_ECHO
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ExecutionStatus {
+    // not echoed yet
+    Pending,
+    // the CanSat echoed the command
+    Executed,
+    // the command wasn't echoed in time
+    NotEchoed,
+}
+
+// what happened to a single attempt at sending a command
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum AttemptResult {
+    // waiting for the TX status
+    Pending,
+    // the radio reported the status
+    Status(DeliveryStatus),
+    // no status was received in time
+    TimedOut,
+}
+
+// a single attempt at sending a command
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Attempt {
+    pub frame_id: u8,
+    pub sent_at: Instant,
+    pub result: AttemptResult,
+    // the last CMD_ECHO received before the attempt was sent
+    pub echo_at_send: Option<String>,
+}
+
+// a command in the command history
+#[derive(Debug, Clone)]
+pub struct CommandRecord {
+    // when the command was queued
+    pub time: DateTime<Utc>,
+    // the command sent
+    pub cmd: Command,
+    // where the command was sent
+    pub dst: Address,
+    // what queued the command, if it wasn't sent by hand
+    pub origin: Option<String>,
+    // where the command is in the queue relative to other commands
+    pub priority: Priority,
+    // what has happened to the command so far
+    pub status: CommandStatus,
+    // whether the CanSat has run the command
+    pub execution: ExecutionStatus,
+    // every time the command was sent, oldest first
+    pub attempts: Vec<Attempt>,
+}
+
+/// Tracks the commands sent to the CanSat, allocating frame IDs so that a TX status can only
+/// ever match the command it was for, and retrying commands which weren't delivered
+#[derive(Debug)]
+pub struct CommandTransport {
+    /// How long to wait for a TX status before giving up on an attempt
+    pub timeout: Duration,
+
+    /// How many times a command is resent after a NoAck or CCA failure
+    pub max_retries: u32,
+
+    /// How long after a command is first sent to wait for it to appear in CMD_ECHO
+    pub echo_timeout: Duration,
+
+    /// The minimum time between sending commands, so the radio isn't flooded
+    pub send_interval: Duration,
+
+    /// Every command queued, in the order they were queued
+    commands: Vec<CommandRecord>,
+
+    /// The frame IDs waiting for a TX status, and the index of their command
+    in_flight: HashMap<u8, usize>,
+
+    /// The last frame ID allocated
+    last_frame_id: u8,
+
+    /// The CMD_ECHO of the last telemetry received
+    last_echo: Option<String>,
+
+    /// The outcome of every attempt so far
+    link_stats: LinkStats,
+}
+
+impl Default for CommandTransport {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(2),
+            max_retries: 2,
+            echo_timeout: Duration::from_secs(10),
+            send_interval: Duration::from_millis(100),
+            commands: vec![],
+            in_flight: HashMap::new(),
+            last_frame_id: 0,
+            last_echo: None,
+            link_stats: Default::default(),
+        }
+    }
+}
+
+impl CommandTransport {
+    /// All the commands queued so far, oldest first
+    pub fn commands(&self) -> &[CommandRecord] {
+        &self.commands
+    }
+
+    pub fn link_stats(&self) -> &LinkStats {
+        &self.link_stats
+    }
+
+    /// Queue a command to be sent, to `default_dst` if the request doesn't give a destination
+    pub fn push(&mut self, req: CommandRequest, default_dst: Address) {
+        self.commands.push(CommandRecord {
+            time: Utc::now(),
+            cmd: req.cmd,
+            dst: req.dst.unwrap_or(default_dst),
+            origin: req.origin,
+            priority: req.priority,
+            status: CommandStatus::Unsent,
+            execution: ExecutionStatus::Pending,
+            attempts: vec![],
+        });
+    }
+
+    /// The number of commands with the given priority waiting to be sent
+    pub fn queued(&self, priority: Priority) -> usize {
+        self.commands
+            .iter()
+            .filter(|record| record.status == CommandStatus::Unsent && record.priority == priority)
+            .count()
+    }
+
+    /// Remove a command from the queue, returns false if it has already been sent.
+    /// Commands waiting to be retried can still be cancelled.
+    pub fn cancel(&mut self, idx: usize) -> bool {
+        let Some(record) = self.commands.get_mut(idx) else {
+            return false;
+        };
+        if record.status != CommandStatus::Unsent {
+            return false;
+        }
+
+        tracing::info!("Cancelled command {:?}", record.cmd);
+        record.status = CommandStatus::Cancelled;
+        true
+    }
+
+    /// Cancel every queued command with the given priority, returns how many were cancelled
+    pub fn cancel_queued(&mut self, priority: Priority) -> usize {
+        let queued: Vec<usize> = self
+            .commands
+            .iter()
+            .enumerate()
+            .filter(|(_, record)| record.priority == priority)
+            .map(|(idx, _)| idx)
+            .collect();
+
+        queued.into_iter().filter(|idx| self.cancel(*idx)).count()
+    }
+
+    /// The most urgent command waiting to be sent along with the frame ID to send it with,
+    /// commands with the same priority are sent oldest first.
+    /// Returns `None` if nothing is waiting or every frame ID is in flight.
+    pub fn next_unsent(&self) -> Option<(usize, u8)> {
+        let (idx, _) = self
+            .commands
+            .iter()
+            .enumerate()
+            .filter(|(_, record)| record.status == CommandStatus::Unsent)
+            .min_by_key(|(_, record)| record.priority)?;
+
+        // search every frame ID after the last one, 0 is skipped as it disables the TX status
+        let frame_id = (1..=u8::MAX as u16)
+            .map(|i| ((self.last_frame_id as u16 + i - 1) % u8::MAX as u16 + 1) as u8)
+            .find(|id| !self.in_flight.contains_key(id))?;
+
+        Some((idx, frame_id))
+    }
+
+    /// Record that the command at `idx` was written to the radio with the given frame ID
+    pub fn sent(&mut self, idx: usize, frame_id: u8, now: Instant) {
+        let record = &mut self.commands[idx];
+        record.status = CommandStatus::Sent { frame_id };
+        record.attempts.push(Attempt {
+            frame_id,
+            sent_at: now,
+            result: AttemptResult::Pending,
+            echo_at_send: self.last_echo.clone(),
+        });
+
+        self.in_flight.insert(frame_id, idx);
+        self.last_frame_id = frame_id;
+    }
+
+    /// Handle a TX status, returns the index of the command it was for
+    pub fn recv_status(&mut self, tx_status: TxStatus) -> Option<usize> {
+        let Some(idx) = self.in_flight.remove(&tx_status.frame_id) else {
+            tracing::warn!("Received a TX status for a frame ID not in flight - {tx_status}");
+            return None;
+        };
+
+        let max_attempts = self.max_retries as usize + 1;
+        let record = &mut self.commands[idx];
+        if let Some(attempt) = record.attempts.last_mut() {
+            attempt.result = AttemptResult::Status(tx_status.status);
+        }
+        self.link_stats
+            .record(record.cmd, tx_status.status, Utc::now());
+
+        let retry = matches!(
+            tx_status.status,
+            DeliveryStatus::NoAck | DeliveryStatus::CcaFailure
+        ) && record.attempts.len() < max_attempts;
+
+        record.status = if retry {
+            tracing::info!(
+                "Retrying command {:?} after {} - attempt {}",
+                record.cmd,
+                tx_status.status,
+                record.attempts.len() + 1
+            );
+            CommandStatus::Unsent
+        } else {
+            CommandStatus::SentStatus {
+                status: tx_status.status,
+            }
+        };
+
+        Some(idx)
+    }
+
+    /// Give up on any attempts which have been waiting for a TX status for too long,
+    /// freeing their frame IDs. Returns the indexes of the commands which timed out.
+    pub fn check_timeouts(&mut self, now: Instant) -> Vec<usize> {
+        let timed_out: Vec<(u8, usize)> = self
+            .in_flight
+            .iter()
+            .filter(|(_, idx)| {
+                !matches!(
+                    self.commands[**idx].attempts.last(),
+                    Some(a) if now.duration_since(a.sent_at) < self.timeout
+                )
+            })
+            .map(|(frame_id, idx)| (*frame_id, *idx))
+            .collect();
+
+        timed_out
+            .into_iter()
+            .map(|(frame_id, idx)| {
+                self.in_flight.remove(&frame_id);
+                let record = &mut self.commands[idx];
+                tracing::warn!("Command {:?} timed out waiting for a TX status", record.cmd);
+                record.status = CommandStatus::TimedOut;
+                if let Some(attempt) = record.attempts.last_mut() {
+                    attempt.result = AttemptResult::TimedOut;
+                }
+                self.link_stats.record_timeout();
+                idx
+            })
+            .collect()
+    }
+
+    /// Handle the CMD_ECHO from some telemetry received at `now`, marking the newest pending
+    /// command it matches as executed. Returns the index of that command.
+    ///
+    /// A command only matches if the echo has changed to its echo since it was last sent, so a
+    /// command identical to the last one executed can't be confirmed by the old echo. Commands
+    /// the radio failed to deliver, or already flagged as not echoed, are never matched.
+    pub fn recv_echo(&mut self, echo: &str, now: Instant) -> Option<usize> {
+        self.last_echo = Some(echo.to_string());
+
+        let idx = self.commands.iter().rposition(|record| {
+            let delivered =
+                record.status.maybe_delivered() && record.status != CommandStatus::Unsent;
+            let changed_since_sent = matches!(
+                record.attempts.last(),
+                Some(a) if a.sent_at <= now && a.echo_at_send.as_deref() != Some(echo)
+            );
+
+            record.execution == ExecutionStatus::Pending
+                && delivered
+                && changed_since_sent
+                && record.cmd.echo() == echo
+        })?;
+
+        self.commands[idx].execution = ExecutionStatus::Executed;
+        Some(idx)
+    }
+
+    /// Flag any commands which haven't been echoed within `echo_timeout` of first being sent.
+    /// Returns the indexes of the newly flagged commands.
+    ///
+    /// Commands which can never be confirmed aren't flagged: those the radio failed to deliver
+    /// or which were cancelled, those sent while the CanSat was already echoing them, and those
+    /// which stop the telemetry. Simulated pressures are left to the simulation verifier.
+    pub fn check_echo_timeouts(&mut self, now: Instant) -> Vec<usize> {
+        let mut flagged = vec![];
+        for (idx, record) in self.commands.iter_mut().enumerate() {
+            if record.execution != ExecutionStatus::Pending
+                || !record.status.maybe_delivered()
+                || record.priority == Priority::Simulation
+                || record.cmd.stops_telemetry()
+            {
+                continue;
+            }
+            let (Some(first), Some(last)) = (record.attempts.first(), record.attempts.last())
+            else {
+                continue;
+            };
+            if last.echo_at_send.as_deref() == Some(&*record.cmd.echo()) {
+                continue;
+            }
+
+            if now.duration_since(first.sent_at) >= self.echo_timeout {
+                tracing::warn!("Command {:?} was never echoed by the CanSat", record.cmd);
+                record.execution = ExecutionStatus::NotEchoed;
+                flagged.push(idx);
+            }
+        }
+
+        flagged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DST: Address = Address::Short(0x0001);
+
+    fn request(cmd: &str, priority: Priority) -> CommandRequest {
+        CommandRequest::new(cmd.parse().unwrap(), priority)
+    }
+
+    fn send_next(transport: &mut CommandTransport, now: Instant) -> u8 {
+        let (idx, frame_id) = transport.next_unsent().unwrap();
+        transport.sent(idx, frame_id, now);
+        frame_id
+    }
+
+    #[test]
+    fn test_frame_ids_skip_in_flight() {
+        let mut transport = CommandTransport::default();
+        let now = Instant::now();
+
+        // fill every frame ID
+        for i in 0..255 {
+            let cmd = Command::SimulatedPressure(i as u32);
+            transport.push(CommandRequest::new(cmd, Priority::Urgent), DST);
+            let frame_id = send_next(&mut transport, now);
+            assert_eq!(frame_id as usize, i + 1);
+        }
+
+        // nothing is free so the next command can't be sent
+        transport.push(request("CMD,1047,CAL", Priority::Urgent), DST);
+        assert_eq!(transport.next_unsent(), None);
+
+        // freeing a frame ID lets the command use it
+        transport.recv_status(TxStatus {
+            frame_id: 17,
+            status: DeliveryStatus::Success,
+        });
+        assert_eq!(transport.next_unsent(), Some((255, 17)));
+    }
+
+    #[test]
+    fn test_priority_order() {
+        let mut transport = CommandTransport::default();
+        let now = Instant::now();
+
+        transport.push(request("CMD,1047,SIMP,101325", Priority::Simulation), DST);
+        transport.push(request("CMD,1047,SIMP,101300", Priority::Simulation), DST);
+        transport.push(request("CMD,1047,CX,ON", Priority::Background), DST);
+        transport.push(request("CMD,1047,CAL", Priority::Urgent), DST);
+        assert_eq!(transport.queued(Priority::Simulation), 2);
+
+        // the urgent command jumps the queue, then the rest are sent in order of priority
+        let order: Vec<usize> = (0..4)
+            .map(|_| {
+                let (idx, frame_id) = transport.next_unsent().unwrap();
+                transport.sent(idx, frame_id, now);
+                idx
+            })
+            .collect();
+        assert_eq!(order, vec![3, 0, 1, 2]);
+        assert_eq!(transport.queued(Priority::Simulation), 0);
+    }
+
+    #[test]
+    fn test_cancel() {
+        let mut transport = CommandTransport::default();
+        let now = Instant::now();
+
+        transport.push(request("CMD,1047,CX,ON", Priority::Urgent), DST);
+        transport.push(request("CMD,1047,SIMP,101325", Priority::Simulation), DST);
+        transport.push(request("CMD,1047,SIMP,101300", Priority::Simulation), DST);
+        send_next(&mut transport, now);
+
+        // sent commands can't be cancelled
+        assert!(!transport.cancel(0));
+        assert!(transport.cancel(2));
+        assert!(!transport.cancel(2));
+        assert_eq!(transport.commands()[2].status, CommandStatus::Cancelled);
+
+        assert_eq!(transport.cancel_queued(Priority::Simulation), 1);
+        assert_eq!(transport.next_unsent(), None);
+    }
+
+    #[test]
+    fn test_status_matches_the_right_command() {
+        let mut transport = CommandTransport::default();
+        let now = Instant::now();
+
+        transport.push(request("CMD,1047,CX,ON", Priority::Urgent), DST);
+        transport.push(request("CMD,1047,CAL", Priority::Urgent), DST);
+        transport.push(request("CMD,1047,ST,GPS", Priority::Urgent), DST);
+        let first = send_next(&mut transport, now);
+        let second = send_next(&mut transport, now);
+        assert_ne!(first, second);
+
+        let idx = transport.recv_status(TxStatus {
+            frame_id: second,
+            status: DeliveryStatus::Success,
+        });
+        assert_eq!(idx, Some(1));
+        assert_eq!(
+            transport.commands()[0].status,
+            CommandStatus::Sent { frame_id: first }
+        );
+
+        // a repeated status doesn't match anything
+        let idx = transport.recv_status(TxStatus {
+            frame_id: second,
+            status: DeliveryStatus::Success,
+        });
+        assert_eq!(idx, None);
+
+        // failures which aren't retried are applied straight away
+        let third = send_next(&mut transport, now);
+        transport.recv_status(TxStatus {
+            frame_id: third,
+            status: DeliveryStatus::RouteNotFound,
+        });
+        assert_eq!(
+            transport.commands()[2].status,
+            CommandStatus::SentStatus {
+                status: DeliveryStatus::RouteNotFound
+            }
+        );
+    }
+
+    #[test]
+    fn test_retries_on_no_ack() {
+        let mut transport = CommandTransport {
+            max_retries: 1,
+            ..Default::default()
+        };
+        let now = Instant::now();
+        transport.push(request("CMD,1047,CX,ON", Priority::Urgent), DST);
+
+        // the first failure is retried
+        let frame_id = send_next(&mut transport, now);
+        transport.recv_status(TxStatus {
+            frame_id,
+            status: DeliveryStatus::NoAck,
+        });
+        assert_eq!(transport.commands()[0].status, CommandStatus::Unsent);
+
+        // the second isn't
+        let frame_id = send_next(&mut transport, now);
+        transport.recv_status(TxStatus {
+            frame_id,
+            status: DeliveryStatus::CcaFailure,
+        });
+
+        let record = &transport.commands()[0];
+        assert_eq!(
+            record.status,
+            CommandStatus::SentStatus {
+                status: DeliveryStatus::CcaFailure
+            }
+        );
+        assert_eq!(
+            record.attempts.iter().map(|a| a.result).collect::<Vec<_>>(),
+            vec![
+                AttemptResult::Status(DeliveryStatus::NoAck),
+                AttemptResult::Status(DeliveryStatus::CcaFailure)
+            ]
+        );
+        assert_eq!(transport.link_stats().success_rate(), Some(0.0));
+    }
+
+    #[test]
+    fn test_timeout() {
+        let mut transport = CommandTransport::default();
+        let start = Instant::now();
+        transport.push(request("CMD,1047,CX,ON", Priority::Urgent), DST);
+        let frame_id = send_next(&mut transport, start);
+
+        assert!(transport
+            .check_timeouts(start + Duration::from_millis(500))
+            .is_empty());
+        assert_eq!(transport.check_timeouts(start + transport.timeout), vec![0]);
+        assert_eq!(transport.commands()[0].status, CommandStatus::TimedOut);
+
+        // a late status is ignored and the frame ID is free again
+        let idx = transport.recv_status(TxStatus {
+            frame_id,
+            status: DeliveryStatus::Success,
+        });
+        assert_eq!(idx, None);
+        assert_eq!(transport.commands()[0].status, CommandStatus::TimedOut);
+    }
+
+    #[test]
+    fn test_cmd_echo_confirms_execution() {
+        let mut transport = CommandTransport::default();
+        let start = Instant::now();
+
+        transport.push(request("CMD,1047,CX,ON", Priority::Urgent), DST);
+        transport.push(request("CMD,1047,SIMP,101325", Priority::Urgent), DST);
+        transport.push(request("CMD,1047,CAL", Priority::Urgent), DST);
+        let frame_id = send_next(&mut transport, start);
+        send_next(&mut transport, start);
+
+        // echoes for other commands, or commands not sent yet, don't match
+        assert_eq!(transport.recv_echo("CXOFF", start), None);
+        assert_eq!(transport.recv_echo("CAL", start), None);
+
+        // the echo can arrive before the TX status
+        assert_eq!(transport.recv_echo("SIMP101325", start), Some(1));
+        assert_eq!(transport.commands()[1].execution, ExecutionStatus::Executed);
+        transport.recv_status(TxStatus {
+            frame_id,
+            status: DeliveryStatus::Success,
+        });
+
+        // the first command is never echoed
+        assert!(transport
+            .check_echo_timeouts(start + Duration::from_secs(1))
+            .is_empty());
+        assert_eq!(
+            transport.check_echo_timeouts(start + transport.echo_timeout),
+            vec![0]
+        );
+        assert_eq!(
+            transport.commands()[0].execution,
+            ExecutionStatus::NotEchoed
+        );
+        assert_eq!(transport.commands()[2].execution, ExecutionStatus::Pending);
+    }
+
+    #[test]
+    fn test_cmd_echo_skips_stale_commands() {
+        let mut transport = CommandTransport::default();
+        let start = Instant::now();
+
+        // the first CAL is never echoed
+        transport.push(request("CMD,1047,CAL", Priority::Urgent), DST);
+        send_next(&mut transport, start);
+        transport.check_echo_timeouts(start + transport.echo_timeout);
+
+        // so the echo of the second is credited to it
+        let resent = start + transport.echo_timeout;
+        transport.push(request("CMD,1047,CAL", Priority::Urgent), DST);
+        send_next(&mut transport, resent);
+        assert_eq!(transport.recv_echo("CAL", resent), Some(1));
+        assert_eq!(
+            transport.commands()[0].execution,
+            ExecutionStatus::NotEchoed
+        );
+        assert_eq!(transport.commands()[1].execution, ExecutionStatus::Executed);
+        assert!(transport
+            .check_echo_timeouts(resent + transport.echo_timeout)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_cmd_echo_must_change_after_sending() {
+        let mut transport = CommandTransport::default();
+        let start = Instant::now();
+
+        // the CanSat already echoes CAL from an earlier command
+        transport.push(request("CMD,1047,CAL", Priority::Urgent), DST);
+        send_next(&mut transport, start);
+        assert_eq!(transport.recv_echo("CAL", start), Some(0));
+
+        // so the old echo doesn't confirm the same command sent again
+        transport.push(request("CMD,1047,CAL", Priority::Urgent), DST);
+        send_next(&mut transport, start);
+        assert_eq!(transport.recv_echo("CAL", start), None);
+        assert_eq!(transport.commands()[1].execution, ExecutionStatus::Pending);
+
+        // but it does once the echo changes to it after the command was sent
+        transport.push(request("CMD,1047,CX,ON", Priority::Urgent), DST);
+        send_next(&mut transport, start);
+        assert_eq!(transport.recv_echo("CXON", start), Some(2));
+        transport.push(request("CMD,1047,CAL", Priority::Urgent), DST);
+        send_next(&mut transport, start);
+        assert_eq!(transport.recv_echo("CAL", start), Some(3));
+    }
+
+    #[test]
+    fn test_unconfirmable_commands_are_not_flagged() {
+        let mut transport = CommandTransport::default();
+        let start = Instant::now();
+
+        // the second SIMP repeats the echo of the first, so it can never be confirmed
+        transport.push(request("CMD,1047,SIMP,101325", Priority::Simulation), DST);
+        send_next(&mut transport, start);
+        assert_eq!(transport.recv_echo("SIMP101325", start), Some(0));
+        transport.push(request("CMD,1047,SIMP,101325", Priority::Simulation), DST);
+        send_next(&mut transport, start);
+
+        // the same goes for commands sent by hand
+        transport.push(request("CMD,1047,CAL", Priority::Urgent), DST);
+        send_next(&mut transport, start);
+        assert_eq!(transport.recv_echo("CAL", start), Some(2));
+        transport.push(request("CMD,1047,CAL", Priority::Urgent), DST);
+        send_next(&mut transport, start);
+
+        // no telemetry comes back to echo CX,OFF
+        transport.push(request("CMD,1047,CX,OFF", Priority::Urgent), DST);
+        send_next(&mut transport, start);
+
+        assert!(transport
+            .check_echo_timeouts(start + transport.echo_timeout)
+            .is_empty());
+        assert_eq!(transport.commands()[1].execution, ExecutionStatus::Pending);
+    }
+
+    #[test]
+    fn test_undelivered_commands_are_not_executed() {
+        let mut transport = CommandTransport {
+            max_retries: 1,
+            ..Default::default()
+        };
+        let start = Instant::now();
+        transport.push(request("CMD,1047,CX,ON", Priority::Urgent), DST);
+
+        // waiting to be retried after a NoAck
+        let frame_id = send_next(&mut transport, start);
+        transport.recv_status(TxStatus {
+            frame_id,
+            status: DeliveryStatus::NoAck,
+        });
+        assert_eq!(transport.recv_echo("CXON", start), None);
+
+        // the retry failed too
+        let frame_id = send_next(&mut transport, start);
+        transport.recv_status(TxStatus {
+            frame_id,
+            status: DeliveryStatus::CcaFailure,
+        });
+        assert_eq!(transport.recv_echo("CXOFF", start), None);
+        assert_eq!(transport.recv_echo("CXON", start), None);
+        assert_eq!(transport.commands()[0].execution, ExecutionStatus::Pending);
+
+        // so it isn't flagged as never echoed either, the failed delivery says what happened
+        assert!(transport
+            .check_echo_timeouts(start + transport.echo_timeout)
+            .is_empty());
+        assert_eq!(transport.commands()[0].execution, ExecutionStatus::Pending);
+    }
+}
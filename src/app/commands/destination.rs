@@ -0,0 +1,55 @@
+use crate::as_str::AsStr;
+use crate::constants::{BROADCAST_ADDR, CONTAINER_ADDR, PROBE_ADDR};
+use crate::xbee::Address;
+use enum_iterator::Sequence;
+
+/// Where a command is sent
+#[derive(Sequence, Default, Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Destination {
+    /// The destination address set in the radio window
+    #[default]
+    RadioDefault,
+    Container,
+    Probe,
+    Broadcast,
+    Custom,
+}
+
+impl Destination {
+    /// The address to send to, `None` means the radio window's destination address
+    pub fn address(&self, custom: u16) -> Option<Address> {
+        match self {
+            Destination::RadioDefault => None,
+            Destination::Container => Some(Address::Short(CONTAINER_ADDR)),
+            Destination::Probe => Some(Address::Short(PROBE_ADDR)),
+            Destination::Broadcast => Some(Address::Short(BROADCAST_ADDR)),
+            Destination::Custom => Some(Address::Short(custom)),
+        }
+    }
+
+    /// The destination and custom address which send to `addr`, `None` if it isn't a short address
+    pub fn from_address(addr: Address) -> Option<(Self, u16)> {
+        let Address::Short(short) = addr else {
+            return None;
+        };
+        let dst = match short {
+            CONTAINER_ADDR => Destination::Container,
+            PROBE_ADDR => Destination::Probe,
+            BROADCAST_ADDR => Destination::Broadcast,
+            _ => Destination::Custom,
+        };
+        Some((dst, short))
+    }
+}
+
+impl AsStr for Destination {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Destination::RadioDefault => "Radio Default",
+            Destination::Container => "Container",
+            Destination::Probe => "Probe",
+            Destination::Broadcast => "Broadcast",
+            Destination::Custom => "Custom",
+        }
+    }
+}
@@ -1,40 +1,76 @@
-mod action;
-mod enabled;
-mod hold_release;
-mod open_close;
-mod raise_stop;
-mod sim_mode;
-mod state;
+mod destination;
 mod time;
 
-use action::Action;
-use chrono::Timelike;
+use chrono::{DateTime, Timelike, Utc};
+use destination::Destination;
 use eframe::emath::Align;
-use egui::{DragValue, Layout, Ui, WidgetText};
+use egui::{Align2, Color32, DragValue, Grid, Layout, Ui, WidgetText};
 use egui_notify::Toasts;
-use sim_mode::SimMode;
-use state::Target;
 use std::fmt::Display;
+use std::time::{Duration, Instant};
 use time::Time;
 
-use crate::app::commands::hold_release::HoldRelease;
-use crate::app::commands::open_close::OpenClose;
-use crate::app::commands::raise_stop::RaiseStop;
-use crate::constants::SEALEVEL_PA;
-use crate::{
-    app::commands::{
-        enabled::Enabled,
-        state::{ContainerState, PayloadState},
-    },
-    as_str::AsStr,
-    constants::TEAM_ID,
-    telemetry::GpsTime,
+use crate::app::command_transport::Priority;
+use crate::command::{
+    Action, Command, ContainerState, Enabled, HoldRelease, OpenClose, ParseCommandError,
+    PayloadState, RaiseStop, Safeguard, SafetyPolicy, SetState, SimMode, Target, TimeArg, Verdict,
 };
+use crate::constants::{CONTAINER_ADDR, SEALEVEL_PA};
+use crate::telemetry::Telemetry;
+use crate::{as_str::AsStr, constants::TEAM_ID, telemetry::GpsTime, xbee::Address};
 use enum_iterator::{all, Sequence};
 
+/// A command to send and where to send it
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CommandRequest {
+    pub cmd: Command,
+    /// The address to send the command to, `None` uses the radio window's destination address
+    pub dst: Option<Address>,
+    /// What sent the command, e.g. a mission script, `None` if it was sent by hand
+    pub origin: Option<String>,
+    /// Where the command goes in the outgoing queue
+    pub priority: Priority,
+}
+
+impl CommandRequest {
+    /// A request to send a command to the radio window's destination address
+    pub fn new(cmd: Command, priority: Priority) -> Self {
+        Self {
+            cmd,
+            dst: None,
+            origin: None,
+            priority,
+        }
+    }
+}
+
+/// How long an armed command can be fired for before it is disarmed
+const ARM_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A command waiting for the operator to confirm or fire it
+struct PendingCommand {
+    req: CommandRequest,
+    /// When the command was armed, or the confirmation was asked for
+    since: Instant,
+}
+
+/// A command sent despite the safety policy refusing it, or without the operator confirming it
+pub struct OverrideRecord {
+    pub time: DateTime<Utc>,
+    pub cmd: Command,
+    /// Why the policy refused or warned about the command
+    pub blocked: String,
+    /// Why it was sent anyway
+    pub reason: String,
+    /// What sent the command, `None` if it was sent by hand
+    pub origin: Option<String>,
+}
+
 /// Holds all the state related to sending commands / the command UI
 pub struct CommandPanel {
-    curr_command: Command,
+    curr_command: CommandKind,
+    destination: Destination,
+    custom_dst: u16,
     telem_enable: Enabled,
     time: Time,
     manual_time: GpsTime,
@@ -52,6 +88,11 @@ pub struct CommandPanel {
     flag: RaiseStop,
     probe: HoldRelease,
     custom_cmd: String,
+    safety: SafetyPolicy,
+    pending: Option<PendingCommand>,
+    override_block: bool,
+    override_reason: String,
+    overrides: Vec<OverrideRecord>,
 }
 
 impl Default for CommandPanel {
@@ -59,6 +100,8 @@ impl Default for CommandPanel {
         let utc = chrono::Utc::now();
         Self {
             curr_command: Default::default(),
+            destination: Default::default(),
+            custom_dst: CONTAINER_ADDR,
             telem_enable: Default::default(),
             time: Default::default(),
             // default to the current UTC time
@@ -82,6 +125,11 @@ impl Default for CommandPanel {
             flag: Default::default(),
             // simplest full command, should be nicer to edit from
             custom_cmd: format!("CMD,{TEAM_ID},CAL"),
+            safety: Default::default(),
+            pending: None,
+            override_block: false,
+            override_reason: String::new(),
+            overrides: vec![],
         }
     }
 }
@@ -110,83 +158,329 @@ impl CommandPanel {
         });
     }
 
-    fn build_cmd(&self) -> String {
-        match self.curr_command {
-            Command::TelemetryEnable => format!("CMD,{TEAM_ID},CX,{}", self.telem_enable),
-            Command::SetTime => match self.time {
-                Time::Manual => format!("CMD,{TEAM_ID},ST,{}", self.manual_time),
+    fn build_cmd(&self) -> Result<Command, ParseCommandError> {
+        let cmd = match self.curr_command {
+            CommandKind::TelemetryEnable => Command::TelemetryEnable(self.telem_enable),
+            CommandKind::SetTime => match self.time {
+                Time::Manual => Command::SetTime(TimeArg::Utc(self.manual_time)),
                 Time::CurrUtc => {
                     let utc = chrono::Utc::now();
-                    format!(
-                        "CMD,{TEAM_ID},ST,{:02}:{:02}:{:02}",
-                        utc.hour(),
-                        utc.minute(),
-                        utc.second()
-                    )
+                    Command::SetTime(TimeArg::Utc(GpsTime {
+                        h: utc.hour() as u8,
+                        m: utc.minute() as u8,
+                        s: utc.second() as u8,
+                    }))
                 }
-                Time::Gps => format!("CMD,{TEAM_ID},ST,GPS"),
+                Time::Gps => Command::SetTime(TimeArg::Gps),
             },
-            Command::SimulationMode => format!("CMD,{TEAM_ID},SIM,{}", self.sim_state),
-            Command::SimulatedPressure => format!("CMD,{TEAM_ID},SIMP,{}", self.sim_pressure),
-            Command::Calibrate => format!("CMD,{TEAM_ID},CAL"),
-            Command::Reset => format!("CMD,{TEAM_ID},OPTIONAL,RESET"),
-            Command::Action => format!("CMD,{TEAM_ID},OPTIONAL,ACTION,{}", self.action),
-            Command::SetState => match self.setstate_target {
-                Target::Payload => format!(
-                    "CMD,{TEAM_ID},OPTIONAL,SETSTATE,{},{}",
-                    self.setstate_target, self.payload_state
-                ),
-                Target::Container => format!(
-                    "CMD,{TEAM_ID},OPTIONAL,SETSTATE,{},{}",
-                    self.setstate_target, self.container_state
-                ),
+            CommandKind::SimulationMode => Command::SimulationMode(self.sim_state),
+            CommandKind::SimulatedPressure => Command::SimulatedPressure(self.sim_pressure),
+            CommandKind::Calibrate => Command::Calibrate,
+            CommandKind::Reset => Command::Reset,
+            CommandKind::Action => Command::Action(self.action),
+            CommandKind::SetState => match self.setstate_target {
+                Target::Payload => Command::SetState(SetState::Payload(self.payload_state)),
+                Target::Container => Command::SetState(SetState::Container(self.container_state)),
             },
-            Command::SoundEnable => format!("CMD,{TEAM_ID},OPTIONAL,SOUND.{}", self.sound_enable),
-            Command::CamEnable => format!("CMD,{TEAM_ID},OPTIONAL,CAM.{}", self.cam_enable),
-            Command::Flaps => format!("CMD,{TEAM_ID},OPTIONAL,FLAP.{}", self.flaps),
-            Command::HeatShield => format!("CMD,{TEAM_ID},OPTIONAL,HS.{}", self.heat_shield),
-            Command::Parachute => format!("CMD,{TEAM_ID},OPTIONAL,CHUTE.{}", self.parachute),
-            Command::Probe => format!("CMD,{TEAM_ID},OPTIONAL,PROBE.{}", self.probe),
-            Command::Flag => format!("CMD,{TEAM_ID},OPTIONAL,FLAG.{}", self.flag),
-            Command::Custom => self.custom_cmd.clone(),
+            CommandKind::SoundEnable => Command::SoundEnable(self.sound_enable),
+            CommandKind::CamEnable => Command::CamEnable(self.cam_enable),
+            CommandKind::Flaps => Command::Flaps(self.flaps),
+            CommandKind::HeatShield => Command::HeatShield(self.heat_shield),
+            CommandKind::Parachute => Command::Parachute(self.parachute),
+            CommandKind::Probe => Command::Probe(self.probe),
+            CommandKind::Flag => Command::Flag(self.flag),
+            CommandKind::Custom => self.custom_cmd.parse()?,
+        };
+
+        Ok(cmd)
+    }
+
+    /// Load a command into the panel so it can be sent again through the usual safeguards,
+    /// returns false if its destination can't be chosen in the panel
+    pub fn load(&mut self, cmd: Command, dst: Address) -> bool {
+        let Some((destination, custom_dst)) = Destination::from_address(dst) else {
+            return false;
+        };
+
+        self.destination = destination;
+        self.custom_dst = custom_dst;
+        self.curr_command = CommandKind::Custom;
+        self.custom_cmd = cmd.to_string();
+        self.pending = None;
+        true
+    }
+
+    /// Log a command sent despite the safety policy
+    fn record_override(
+        &mut self,
+        cmd: Command,
+        blocked: String,
+        reason: String,
+        origin: Option<String>,
+    ) {
+        tracing::warn!(
+            "Safety override - sending {cmd} despite \"{blocked}\", reason: \"{reason}\", origin: {}",
+            origin.as_deref().unwrap_or("operator")
+        );
+        self.overrides.push(OverrideRecord {
+            time: Utc::now(),
+            cmd,
+            blocked,
+            reason,
+            origin,
+        });
+    }
+
+    /// Check a command a mission script wants to send, as a script can't ask the operator to
+    /// confirm or arm it. Returns why the command is refused, commands sent despite a warning
+    /// or without confirmation are logged as overrides.
+    pub fn check_script_command(
+        &mut self,
+        cmd: Command,
+        telem: Option<&Telemetry>,
+        origin: &str,
+    ) -> Result<(), String> {
+        let warning = match (self.safety.check(&cmd, telem), self.safety.safeguard(&cmd)) {
+            (Verdict::Block(reason), _) => return Err(reason),
+            (_, Safeguard::ArmThenFire) => {
+                return Err(format!("{cmd} must be armed and fired by hand"))
+            }
+            (Verdict::Warn(warning), _) => warning,
+            (Verdict::Allow, Safeguard::Confirm) => "Needs confirmation".to_string(),
+            (Verdict::Allow, Safeguard::None) => return Ok(()),
+        };
+
+        let reason = "Sent by a mission script without asking".to_string();
+        self.record_override(cmd, warning, reason, Some(origin.to_string()));
+        Ok(())
+    }
+
+    /// Send a command which has passed all its safeguards, logging it if the policy was overridden
+    fn send(
+        &mut self,
+        req: CommandRequest,
+        verdict: &Verdict,
+        notif: &mut Toasts,
+    ) -> CommandRequest {
+        if let Verdict::Block(blocked) = verdict {
+            let reason = self.override_reason.trim().to_string();
+            notif.warning(format!("Safety policy overridden for {}", req.cmd));
+            self.record_override(req.cmd, blocked.clone(), reason, None);
+            self.override_block = false;
+            self.override_reason.clear();
+        }
+
+        match req.dst {
+            Some(addr) => notif.info(format!("Sent to {addr}: {}", req.cmd)),
+            None => notif.info(format!("Sent: {}", req.cmd)),
+        };
+        self.pending = None;
+        req
+    }
+
+    /// Show the verdict of the safety policy, returns true if the command can be sent
+    fn verdict_ui(&mut self, ui: &mut Ui, verdict: &Verdict) -> bool {
+        match verdict {
+            Verdict::Allow => true,
+            Verdict::Warn(warning) => {
+                ui.colored_label(Color32::YELLOW, format!("⚠ {warning}"));
+                true
+            }
+            Verdict::Block(reason) => {
+                ui.colored_label(Color32::RED, format!("⛔ {reason}"));
+                ui.checkbox(&mut self.override_block, "Override the safety policy");
+                if self.override_block {
+                    ui.horizontal(|ui| {
+                        ui.label("Reason:");
+                        ui.text_edit_singleline(&mut self.override_reason);
+                    });
+                }
+                self.override_block && !self.override_reason.trim().is_empty()
+            }
         }
     }
 
-    pub fn show(&mut self, ui: &mut Ui, notif: &mut Toasts) -> Option<String> {
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        notif: &mut Toasts,
+        telem: Option<&Telemetry>,
+    ) -> Option<CommandRequest> {
+        Self::combobox_row(
+            ui,
+            &mut self.destination,
+            "Send to:",
+            "destination_combobox",
+        );
+        if self.destination == Destination::Custom {
+            ui.horizontal(|ui| {
+                ui.label("Address:");
+                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                    ui.add(DragValue::new(&mut self.custom_dst).hexadecimal(4, true, true));
+                });
+            });
+        }
+
         Self::combobox_row(ui, &mut self.curr_command, "Command:", "command_combobox");
 
         match self.curr_command {
-            Command::TelemetryEnable => self.telemetry_enable_view(ui),
-            Command::SetTime => self.set_time_view(ui),
-            Command::SimulationMode => self.simulation_mode_view(ui),
-            Command::SimulatedPressure => self.simulation_pressure_view(ui),
-            Command::Calibrate | Command::Reset => (),
-            Command::Action => self.action_view(ui),
-            Command::SetState => self.setstate_view(ui),
-            Command::CamEnable => self.cam_enable_view(ui),
-            Command::SoundEnable => self.sound_enable_view(ui),
-            Command::Flaps => self.flaps_view(ui),
-            Command::HeatShield => self.heat_shield_view(ui),
-            Command::Parachute => self.parachute_view(ui),
-            Command::Flag => self.flag_view(ui),
-            Command::Probe => self.probe_view(ui),
-            Command::Custom => self.custom_view(ui),
+            CommandKind::TelemetryEnable => self.telemetry_enable_view(ui),
+            CommandKind::SetTime => self.set_time_view(ui),
+            CommandKind::SimulationMode => self.simulation_mode_view(ui),
+            CommandKind::SimulatedPressure => self.simulation_pressure_view(ui),
+            CommandKind::Calibrate | CommandKind::Reset => (),
+            CommandKind::Action => self.action_view(ui),
+            CommandKind::SetState => self.setstate_view(ui),
+            CommandKind::CamEnable => self.cam_enable_view(ui),
+            CommandKind::SoundEnable => self.sound_enable_view(ui),
+            CommandKind::Flaps => self.flaps_view(ui),
+            CommandKind::HeatShield => self.heat_shield_view(ui),
+            CommandKind::Parachute => self.parachute_view(ui),
+            CommandKind::Flag => self.flag_view(ui),
+            CommandKind::Probe => self.probe_view(ui),
+            CommandKind::Custom => self.custom_view(ui),
         };
 
         ui.separator();
         ui.vertical_centered(|ui| {
-            ui.label(self.build_cmd());
+            let cmd = match self.build_cmd() {
+                Ok(cmd) => cmd,
+                Err(e) => {
+                    // only the custom command can fail to build
+                    ui.colored_label(Color32::RED, e.to_string());
+                    ui.separator();
+                    ui.add_enabled(false, egui::Button::new("Send"));
+                    return None;
+                }
+            };
+
+            ui.label(cmd.to_string());
+            let verdict = self.safety.check(&cmd, telem);
+            let allowed = self.verdict_ui(ui, &verdict);
             ui.separator();
-            if ui.button("Send").clicked() {
-                let cmd = self.build_cmd();
-                notif.info(format!("Sent: {cmd}"));
-                return Some(cmd);
+
+            let req = CommandRequest {
+                cmd,
+                dst: self.destination.address(self.custom_dst),
+                origin: None,
+                priority: Priority::Urgent,
+            };
+
+            // drop a pending command if the operator changes what they're sending,
+            // or if it has been left armed for too long
+            let now = Instant::now();
+            let armed = self.safety.safeguard(&cmd) == Safeguard::ArmThenFire;
+            let expired = |p: &PendingCommand| armed && now.duration_since(p.since) >= ARM_TIMEOUT;
+            if let Some(p) = self.pending.as_ref().filter(|p| p.req == req && expired(p)) {
+                tracing::info!("Disarmed {} after {ARM_TIMEOUT:?}", p.req.cmd);
+                notif.info(format!("Disarmed {}", p.req.cmd));
+            }
+            let pending = self
+                .pending
+                .as_ref()
+                .filter(|p| p.req == req && allowed && !expired(p))
+                .is_some();
+            if !pending {
+                self.pending = None;
+            }
+
+            match self.safety.safeguard(&cmd) {
+                Safeguard::None => {
+                    if ui.add_enabled(allowed, egui::Button::new("Send")).clicked() {
+                        return Some(self.send(req, &verdict, notif));
+                    }
+                }
+                Safeguard::Confirm => {
+                    if ui.add_enabled(allowed, egui::Button::new("Send")).clicked() {
+                        self.pending = Some(PendingCommand { req, since: now });
+                    } else if pending {
+                        return self.confirm_dialog(ui, &verdict, notif);
+                    }
+                }
+                Safeguard::ArmThenFire if pending => {
+                    let left = ARM_TIMEOUT.saturating_sub(
+                        now.duration_since(self.pending.as_ref().map_or(now, |p| p.since)),
+                    );
+                    ui.colored_label(
+                        Color32::RED,
+                        format!("ARMED - disarming in {}s", left.as_secs() + 1),
+                    );
+                    let fired = ui
+                        .horizontal(|ui| {
+                            let fire = egui::Button::new("🔥 Fire").fill(Color32::DARK_RED);
+                            let fired = ui.add(fire).clicked();
+                            if ui.button("Disarm").clicked() {
+                                tracing::info!("Disarmed {cmd}");
+                                self.pending = None;
+                            }
+                            fired
+                        })
+                        .inner;
+                    if fired {
+                        return Some(self.send(req, &verdict, notif));
+                    }
+                }
+                Safeguard::ArmThenFire => {
+                    if ui.add_enabled(allowed, egui::Button::new("Arm")).clicked() {
+                        tracing::info!("Armed {cmd}");
+                        self.pending = Some(PendingCommand { req, since: now });
+                    }
+                }
             }
             None
         })
         .inner
     }
 
+    /// Ask the operator to confirm the pending command, returns it if they did
+    fn confirm_dialog(
+        &mut self,
+        ui: &mut Ui,
+        verdict: &Verdict,
+        notif: &mut Toasts,
+    ) -> Option<CommandRequest> {
+        let req = self.pending.as_ref()?.req.clone();
+        let mut confirmed = false;
+
+        egui::Window::new("Confirm command")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ui.ctx(), |ui| {
+                ui.label(format!("Really send {}?", req.cmd));
+                ui.horizontal(|ui| {
+                    confirmed = ui.button("Confirm").clicked();
+                    if ui.button("Cancel").clicked() {
+                        self.pending = None;
+                    }
+                });
+            });
+
+        confirmed.then(|| self.send(req, verdict, notif))
+    }
+
+    /// Show every time the safety policy was overridden
+    pub fn overrides_ui(&self, ui: &mut Ui) {
+        if self.overrides.is_empty() {
+            ui.label("No overrides.");
+            return;
+        }
+
+        Grid::new("safety_overrides_grid")
+            .num_columns(5)
+            .striped(true)
+            .show(ui, |ui| {
+                for record in self.overrides.iter() {
+                    ui.label(record.time.format("%H:%M:%S").to_string());
+                    ui.label(record.origin.as_deref().unwrap_or("operator"));
+                    ui.label(record.cmd.to_string());
+                    ui.colored_label(Color32::RED, &record.blocked);
+                    ui.label(&record.reason);
+                    ui.end_row();
+                }
+            });
+    }
+
     fn telemetry_enable_view(&mut self, ui: &mut Ui) {
         Self::combobox_row(ui, &mut self.telem_enable, "Enable:", "cx_combobox");
     }
@@ -293,7 +587,7 @@ impl CommandPanel {
 type Pascals = u32;
 
 #[derive(Sequence, Default, Debug, Copy, Clone, Eq, PartialEq)]
-enum Command {
+enum CommandKind {
     /// CX - Enable/Disable container telemetry
     TelemetryEnable,
 
@@ -344,25 +638,80 @@ enum Command {
     Custom,
 }
 
-impl AsStr for Command {
+impl AsStr for CommandKind {
     fn as_str(&self) -> &'static str {
         match self {
-            Command::TelemetryEnable => "Telemetry Enable",
-            Command::SetTime => "Set Time",
-            Command::SimulationMode => "Simulation Mode",
-            Command::SimulatedPressure => "Simulated Pressure",
-            Command::Calibrate => "Calibrate",
-            Command::Action => "Action",
-            Command::SetState => "Set State",
-            Command::Reset => "Reset",
-            Command::CamEnable => "Camera",
-            Command::SoundEnable => "Buzzer",
-            Command::Flaps => "Container Door",
-            Command::HeatShield => "Heat Shield",
-            Command::Parachute => "Parachute",
-            Command::Probe => "Payload Release",
-            Command::Flag => "Flag",
-            Command::Custom => "Custom",
+            CommandKind::TelemetryEnable => "Telemetry Enable",
+            CommandKind::SetTime => "Set Time",
+            CommandKind::SimulationMode => "Simulation Mode",
+            CommandKind::SimulatedPressure => "Simulated Pressure",
+            CommandKind::Calibrate => "Calibrate",
+            CommandKind::Action => "Action",
+            CommandKind::SetState => "Set State",
+            CommandKind::Reset => "Reset",
+            CommandKind::CamEnable => "Camera",
+            CommandKind::SoundEnable => "Buzzer",
+            CommandKind::Flaps => "Container Door",
+            CommandKind::HeatShield => "Heat Shield",
+            CommandKind::Parachute => "Parachute",
+            CommandKind::Probe => "Payload Release",
+            CommandKind::Flag => "Flag",
+            CommandKind::Custom => "Custom",
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::telemetry::TelemetryBuilder;
+
+    const ORIGIN: &str = "mission script line 3";
+
+    #[test]
+    fn test_script_commands_skip_no_safeguards() {
+        let mut panel = CommandPanel::default();
+        let telem = TelemetryBuilder::default()
+            .altitude(0.0)
+            .state("IDLE")
+            .build();
+
+        // commands which must be armed are refused outright
+        let release = Command::Probe(HoldRelease::Release);
+        assert!(panel
+            .check_script_command(release, Some(&telem), ORIGIN)
+            .is_err());
+
+        // safe commands go straight through
+        let cx_on = Command::TelemetryEnable(Enabled::On);
+        assert_eq!(
+            panel.check_script_command(cx_on, Some(&telem), ORIGIN),
+            Ok(())
+        );
+        assert!(panel.overrides.is_empty());
+
+        // commands needing confirmation, or sent past a warning, are logged
+        assert_eq!(
+            panel.check_script_command(Command::Calibrate, Some(&telem), ORIGIN),
+            Ok(())
+        );
+        assert_eq!(panel.check_script_command(cx_on, None, ORIGIN), Ok(()));
+        assert_eq!(
+            panel.check_script_command(Command::Calibrate, None, ORIGIN),
+            Ok(())
+        );
+        let logged: Vec<_> = panel
+            .overrides
+            .iter()
+            .map(|r| (r.cmd, r.origin.as_deref()))
+            .collect();
+        assert_eq!(
+            logged,
+            [
+                (Command::Calibrate, Some(ORIGIN)),
+                (Command::Calibrate, Some(ORIGIN))
+            ]
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use crate::command::Command;
+use crate::xbee::DeliveryStatus;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+/// The history of a single kind of delivery failure
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FailureHistory {
+    /// How many times the failure has happened
+    pub count: u32,
+    /// When it last happened
+    pub last_seen: DateTime<Utc>,
+    /// The command which last failed this way
+    pub last_cmd: Command,
+}
+
+/// Statistics about how reliably commands are reaching the CanSat
+#[derive(Debug, Default)]
+pub struct LinkStats {
+    /// The number of attempts the radio reported as delivered
+    successes: u32,
+
+    /// The number of attempts which never received a TX status
+    timeouts: u32,
+
+    /// The failed attempts, by the status the radio reported
+    failures: HashMap<DeliveryStatus, FailureHistory>,
+}
+
+impl LinkStats {
+    /// Record the TX status received for an attempt at sending `cmd`
+    pub fn record(&mut self, cmd: Command, status: DeliveryStatus, now: DateTime<Utc>) {
+        if status == DeliveryStatus::Success {
+            self.successes += 1;
+            return;
+        }
+
+        let history = self
+            .failures
+            .entry(status)
+            .or_insert_with(|| FailureHistory {
+                count: 0,
+                last_seen: now,
+                last_cmd: cmd,
+            });
+        history.count += 1;
+        history.last_seen = now;
+        history.last_cmd = cmd;
+    }
+
+    /// Record an attempt which never received a TX status
+    pub fn record_timeout(&mut self) {
+        self.timeouts += 1;
+    }
+
+    pub fn successes(&self) -> u32 {
+        self.successes
+    }
+
+    pub fn timeouts(&self) -> u32 {
+        self.timeouts
+    }
+
+    /// The total number of attempts with a known outcome
+    pub fn attempts(&self) -> u32 {
+        self.successes + self.timeouts + self.failures.values().map(|h| h.count).sum::<u32>()
+    }
+
+    /// The fraction of attempts which were delivered, `None` if nothing has been sent yet
+    pub fn success_rate(&self) -> Option<f64> {
+        match self.attempts() {
+            0 => None,
+            n => Some(self.successes as f64 / n as f64),
+        }
+    }
+
+    /// The failures seen so far, most common first
+    pub fn common_failures(&self) -> Vec<(DeliveryStatus, &FailureHistory)> {
+        let mut failures: Vec<_> = self.failures.iter().map(|(s, h)| (*s, h)).collect();
+        failures
+            .sort_by(|(_, a), (_, b)| b.count.cmp(&a.count).then(b.last_seen.cmp(&a.last_seen)));
+        failures
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::Enabled;
+
+    #[test]
+    fn test_link_stats() {
+        let mut stats = LinkStats::default();
+        assert_eq!(stats.success_rate(), None);
+
+        let now = Utc::now();
+        let cx_on = Command::TelemetryEnable(Enabled::On);
+        stats.record(cx_on, DeliveryStatus::Success, now);
+        stats.record(cx_on, DeliveryStatus::NoAck, now);
+        stats.record(Command::Calibrate, DeliveryStatus::Purged, now);
+        stats.record(Command::Calibrate, DeliveryStatus::NoAck, now);
+        stats.record_timeout();
+
+        assert_eq!(stats.attempts(), 5);
+        assert_eq!(stats.success_rate(), Some(0.2));
+
+        let failures = stats.common_failures();
+        assert_eq!(failures.len(), 2);
+        assert_eq!(failures[0].0, DeliveryStatus::NoAck);
+        assert_eq!(failures[0].1.count, 2);
+        assert_eq!(failures[0].1.last_cmd, Command::Calibrate);
+        assert_eq!(failures[1].0, DeliveryStatus::Purged);
+    }
+}
@@ -0,0 +1,360 @@
+use crate::as_str::AsStr;
+use crate::command::{Command, Safeguard, SafetyPolicy};
+use crate::constants::TEAM_ID;
+use crate::telemetry::{Telemetry, TelemetryField};
+use anyhow::{bail, Context, Result};
+use enum_iterator::all;
+use std::fmt;
+use std::time::Duration;
+
+/// How a telemetry field is compared in a `wait until` step
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CompareOp {
+    fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "==" | "=" => Self::Eq,
+            "!=" => Self::Ne,
+            "<" => Self::Lt,
+            "<=" => Self::Le,
+            ">" => Self::Gt,
+            ">=" => Self::Ge,
+            _ => return None,
+        })
+    }
+
+    /// Can this operator be used to compare text?
+    const fn is_equality(&self) -> bool {
+        matches!(self, Self::Eq | Self::Ne)
+    }
+}
+
+impl AsStr for CompareOp {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+        }
+    }
+}
+
+/// A condition on the latest telemetry, e.g. `ALTITUDE < 500`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    pub field: TelemetryField,
+    pub op: CompareOp,
+    pub value: String,
+}
+
+impl Condition {
+    /// Does the telemetry satisfy the condition? Numbers are compared numerically and
+    /// anything else is compared as text, ignoring case
+    pub fn holds(&self, telem: &Telemetry) -> bool {
+        let actual = telem.get_field(self.field);
+        match (actual.parse::<f64>(), self.value.parse::<f64>()) {
+            (Ok(a), Ok(b)) => match self.op {
+                CompareOp::Eq => a == b,
+                CompareOp::Ne => a != b,
+                CompareOp::Lt => a < b,
+                CompareOp::Le => a <= b,
+                CompareOp::Gt => a > b,
+                CompareOp::Ge => a >= b,
+            },
+            _ => match self.op {
+                CompareOp::Eq => actual.eq_ignore_ascii_case(&self.value),
+                CompareOp::Ne => !actual.eq_ignore_ascii_case(&self.value),
+                _ => false,
+            },
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.field, self.op.as_str(), self.value)
+    }
+}
+
+/// What a step in a mission script does
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepKind {
+    /// Send a command to the CanSat
+    Send(Command),
+    /// Do nothing for a while
+    Delay(Duration),
+    /// Wait for the radio to report the last command was delivered
+    WaitAck { timeout: Option<Duration> },
+    /// Wait for the last command to appear in CMD_ECHO
+    WaitEcho { timeout: Option<Duration> },
+    /// Wait for the latest telemetry to satisfy a condition
+    WaitUntil {
+        condition: Condition,
+        timeout: Option<Duration>,
+    },
+}
+
+impl StepKind {
+    pub fn timeout(&self) -> Option<Duration> {
+        match self {
+            StepKind::Send(_) | StepKind::Delay(_) => None,
+            StepKind::WaitAck { timeout }
+            | StepKind::WaitEcho { timeout }
+            | StepKind::WaitUntil { timeout, .. } => *timeout,
+        }
+    }
+}
+
+impl fmt::Display for StepKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StepKind::Send(cmd) => write!(f, "send {cmd}"),
+            StepKind::Delay(delay) => write!(f, "delay {}", delay.as_secs_f64()),
+            StepKind::WaitAck { .. } => write!(f, "wait ack"),
+            StepKind::WaitEcho { .. } => write!(f, "wait echo"),
+            StepKind::WaitUntil { condition, .. } => write!(f, "wait until {condition}"),
+        }?;
+
+        match self.timeout() {
+            Some(timeout) => write!(f, " timeout {}", timeout.as_secs_f64()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A single step of a mission script and the line it was written on
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub line: usize,
+    pub kind: StepKind,
+}
+
+/// A sequence of commands, delays and waits to run in order.
+///
+/// Scripts have one step per line, blank lines and anything after a `#` are ignored:
+///
+/// ```text
+/// send CX,ON                        # the CMD,<TEAM_ID> prefix is optional
+/// wait ack timeout 5                # wait for the TX status, failing after 5 seconds
+/// send CMD,1047,ST,GPS
+/// wait echo                         # wait for the command to appear in CMD_ECHO
+/// delay 2.5
+/// wait until STATE == YEETED        # any telemetry field can be compared
+/// wait until ALTITUDE < 500 timeout 120
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Script {
+    pub steps: Vec<Step>,
+}
+
+/// Parse a number of seconds
+fn parse_secs(s: &str) -> Result<Duration> {
+    let secs: f64 = s
+        .parse()
+        .with_context(|| format!("Invalid duration {s:?}"))?;
+    if !secs.is_finite() || secs < 0.0 {
+        bail!("Invalid duration {s:?}");
+    }
+
+    Ok(Duration::from_secs_f64(secs))
+}
+
+/// Split an optional `timeout <secs>` off the end of the arguments to a wait step
+fn split_timeout<'a>(args: &'a [&'a str]) -> Result<(&'a [&'a str], Option<Duration>)> {
+    match args {
+        [rest @ .., "timeout", secs] => Ok((rest, Some(parse_secs(secs)?))),
+        _ => Ok((args, None)),
+    }
+}
+
+fn parse_condition(args: &[&str]) -> Result<Condition> {
+    let [field, op, value] = args else {
+        bail!("Expected a condition like `ALTITUDE < 500`");
+    };
+
+    let Some(field) = all::<TelemetryField>().find(|f| f.as_str().eq_ignore_ascii_case(field))
+    else {
+        bail!("Unknown telemetry field {field:?}");
+    };
+    let Some(op) = CompareOp::parse(op) else {
+        bail!("Unknown comparison {op:?}");
+    };
+    if !op.is_equality() && value.parse::<f64>().is_err() {
+        bail!("{value:?} can only be compared with == or !=");
+    }
+
+    Ok(Condition {
+        field,
+        op,
+        value: value.to_string(),
+    })
+}
+
+fn parse_step(line: &str) -> Result<StepKind> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let step = match words.as_slice() {
+        ["send", cmd] => {
+            // allow leaving off the CMD,<TEAM_ID> prefix
+            let cmd = if cmd.starts_with("CMD,") {
+                cmd.to_string()
+            } else {
+                format!("CMD,{TEAM_ID},{cmd}")
+            };
+            let cmd: Command = cmd.parse()?;
+            // nobody is there to arm the command before it fires
+            if SafetyPolicy::default().safeguard(&cmd) == Safeguard::ArmThenFire {
+                bail!("{cmd} must be armed and fired by hand, not from a script");
+            }
+            StepKind::Send(cmd)
+        }
+        ["delay", secs] => StepKind::Delay(parse_secs(secs)?),
+        ["wait", "ack", rest @ ..] => match split_timeout(rest)? {
+            ([], timeout) => StepKind::WaitAck { timeout },
+            _ => bail!("Unexpected arguments to `wait ack`"),
+        },
+        ["wait", "echo", rest @ ..] => match split_timeout(rest)? {
+            ([], timeout) => StepKind::WaitEcho { timeout },
+            _ => bail!("Unexpected arguments to `wait echo`"),
+        },
+        ["wait", "until", rest @ ..] => {
+            let (condition, timeout) = split_timeout(rest)?;
+            StepKind::WaitUntil {
+                condition: parse_condition(condition)?,
+                timeout,
+            }
+        }
+        ["send" | "delay", ..] => bail!("Expected a single argument"),
+        ["wait", ..] => bail!("Expected `wait ack`, `wait echo` or `wait until`"),
+        [other, ..] => bail!("Unknown step {other:?}"),
+        [] => unreachable!("blank lines are skipped"),
+    };
+
+    Ok(step)
+}
+
+impl Script {
+    /// Parse a script, errors include the line number of the first invalid step
+    pub fn parse(src: &str) -> Result<Self> {
+        let mut steps = vec![];
+        let mut sent_command = false;
+
+        for (i, line) in src.lines().enumerate() {
+            let line_no = i + 1;
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let kind = parse_step(line).with_context(|| format!("Line {line_no}"))?;
+            match kind {
+                StepKind::Send(_) => sent_command = true,
+                StepKind::WaitAck { .. } | StepKind::WaitEcho { .. } if !sent_command => {
+                    bail!("Line {line_no}: there is no command to wait for")
+                }
+                _ => (),
+            }
+
+            steps.push(Step {
+                line: line_no,
+                kind,
+            });
+        }
+
+        Ok(Self { steps })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::Enabled;
+    use crate::telemetry::TelemetryBuilder;
+
+    #[test]
+    fn test_script_parse() {
+        let src = "
+            # launch day
+            send CX,ON
+            wait ack timeout 5
+            send CMD,1047,ST,GPS   # set the time
+            wait echo
+            delay 2.5
+            wait until state == YEETED
+            wait until ALTITUDE < 500 timeout 120
+        ";
+        let script = Script::parse(src).unwrap();
+
+        let kinds: Vec<_> = script.steps.iter().map(|s| s.kind.to_string()).collect();
+        assert_eq!(
+            kinds,
+            [
+                "send CMD,1047,CX,ON",
+                "wait ack timeout 5",
+                "send CMD,1047,ST,GPS",
+                "wait echo",
+                "delay 2.5",
+                "wait until STATE == YEETED",
+                "wait until ALTITUDE < 500 timeout 120",
+            ]
+        );
+        assert_eq!(script.steps[0].line, 3);
+        assert_eq!(
+            script.steps[0].kind,
+            StepKind::Send(Command::TelemetryEnable(Enabled::On))
+        );
+    }
+
+    #[test]
+    fn test_script_parse_fails() {
+        let cases = [
+            "send CX,MAYBE",
+            "delay soon",
+            "delay -1",
+            "wait ack",
+            "send CAL\nwait ack forever",
+            "wait until ALTITUDE",
+            "wait until HEIGHT < 5",
+            "wait until STATE < YEETED",
+            "send OPTIONAL,PROBE.RELEASE",
+            "send OPTIONAL,CHUTE.OPEN",
+            "launch",
+        ];
+
+        for src in cases {
+            assert!(Script::parse(src).is_err(), "{src}");
+        }
+
+        let err = Script::parse("send CAL\n\ndelay x").unwrap_err();
+        assert!(format!("{err:#}").starts_with("Line 3"));
+    }
+
+    #[test]
+    fn test_condition_holds() {
+        let telem = TelemetryBuilder::default().build();
+
+        let check = |line: &str| {
+            let StepKind::WaitUntil { condition, .. } = parse_step(line).unwrap() else {
+                panic!("not a condition");
+            };
+            condition.holds(&telem)
+        };
+
+        assert!(check("wait until STATE == yeeted"));
+        assert!(!check("wait until STATE != YEETED"));
+        assert!(check("wait until ALTITUDE < 500"));
+        assert!(!check("wait until ALTITUDE >= 500"));
+        assert!(check("wait until PACKET_COUNT == 0"));
+        assert!(check("wait until CMD_ECHO == CXON"));
+    }
+}
@@ -0,0 +1,261 @@
+mod format;
+mod runner;
+
+pub use runner::ScriptCommand;
+
+use format::Script;
+use runner::{RunState, ScriptRunner, StepOutcome};
+
+use super::command_transport::CommandRecord;
+use crate::telemetry::Telemetry;
+use egui::{Color32, Grid, Layout, ScrollArea, TextEdit, Ui};
+use egui_notify::Toasts;
+use std::time::Instant;
+
+const EXAMPLE_SCRIPT: &str = "\
+# launch day checks
+send CX,ON
+wait ack timeout 5
+send ST,GPS
+wait echo timeout 10
+send CAL
+wait echo timeout 10
+";
+
+/// Holds all the state related to loading and running mission scripts
+pub struct ScriptPanel {
+    /// The file to load the script from
+    path: String,
+
+    /// The script being edited
+    source: String,
+
+    /// Why the script failed to parse, if it did
+    error: Option<String>,
+
+    /// Runs the last script which parsed successfully
+    runner: ScriptRunner,
+}
+
+impl Default for ScriptPanel {
+    fn default() -> Self {
+        let mut panel = Self {
+            path: "mission.txt".to_string(),
+            source: EXAMPLE_SCRIPT.to_string(),
+            error: None,
+            runner: Default::default(),
+        };
+        panel.reparse();
+        panel
+    }
+}
+
+impl ScriptPanel {
+    /// Is a script running, or paused part way through?
+    fn active(&self) -> bool {
+        matches!(self.runner.state(), RunState::Running | RunState::Paused)
+    }
+
+    /// Parse the source into a new runner
+    fn reparse(&mut self) {
+        match Script::parse(&self.source) {
+            Ok(script) => {
+                self.runner = ScriptRunner::new(script);
+                self.error = None;
+            }
+            Err(e) => self.error = Some(format!("{e:#}")),
+        }
+    }
+
+    /// Run the script with the latest telemetry and when it was received, returns any command
+    /// it wants sent
+    pub fn poll(
+        &mut self,
+        history: &[CommandRecord],
+        telem: Option<(&Telemetry, Instant)>,
+        notif: &mut Toasts,
+    ) -> Option<ScriptCommand> {
+        let before = self.runner.state();
+        let cmd = self.runner.poll(Instant::now(), history, telem);
+
+        match (before, self.runner.state()) {
+            (RunState::Running, RunState::Finished) => {
+                notif.success("Mission script finished");
+            }
+            (RunState::Running, RunState::Failed) => {
+                let reason = match self.runner.log().last().map(|r| &r.outcome) {
+                    Some(StepOutcome::Failed(reason)) => reason.as_str(),
+                    _ => "unknown error",
+                };
+                notif.error(format!("Mission script failed: {reason}"));
+            }
+            _ => (),
+        }
+
+        cmd
+    }
+
+    /// Stop the running script because of a problem outside the runner
+    pub fn abort(&mut self, reason: &str, notif: &mut Toasts) {
+        if self.active() {
+            self.runner.abort();
+            notif.error(format!("Mission script aborted: {reason}"));
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut Ui, notif: &mut Toasts) {
+        let active = self.active();
+
+        ui.add_enabled_ui(!active, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("File: ");
+                ui.with_layout(Layout::right_to_left(eframe::emath::Align::Center), |ui| {
+                    if ui.button("Load").clicked() {
+                        match std::fs::read_to_string(&self.path) {
+                            Ok(source) => {
+                                self.source = source;
+                                self.reparse();
+                                notif.info(format!("Loaded {}", self.path));
+                            }
+                            Err(e) => {
+                                tracing::warn!("Failed to read script {:?} - {e:?}", self.path);
+                                notif.error(format!("Failed to read {}: {e}", self.path));
+                            }
+                        }
+                    }
+                    ui.text_edit_singleline(&mut self.path);
+                });
+            });
+
+            ui.collapsing("Edit script", |ui| {
+                let resp = ui.add(
+                    TextEdit::multiline(&mut self.source)
+                        .code_editor()
+                        .desired_rows(8)
+                        .desired_width(f32::INFINITY),
+                );
+                if resp.changed() {
+                    self.reparse();
+                }
+            });
+        });
+
+        if let Some(error) = &self.error {
+            ui.colored_label(Color32::RED, error);
+        }
+
+        let now = Instant::now();
+        ui.horizontal(|ui| {
+            let can_start =
+                !active && self.error.is_none() && !self.runner.script().steps.is_empty();
+            if ui
+                .add_enabled(can_start, egui::Button::new("Start"))
+                .clicked()
+            {
+                tracing::info!("Starting mission script");
+                self.runner.start(now);
+            }
+
+            match self.runner.state() {
+                RunState::Paused => {
+                    if ui.button("Resume").clicked() {
+                        self.runner.resume(now);
+                    }
+                }
+                state => {
+                    let running = state == RunState::Running;
+                    if ui
+                        .add_enabled(running, egui::Button::new("Pause"))
+                        .clicked()
+                    {
+                        self.runner.pause(now);
+                    }
+                }
+            }
+
+            if ui.add_enabled(active, egui::Button::new("Abort")).clicked() {
+                tracing::info!("Aborting mission script");
+                self.runner.abort();
+                notif.warning("Mission script aborted");
+            }
+
+            ui.label(format!("{:?}", self.runner.state()));
+        });
+
+        ui.separator();
+        self.steps_ui(ui, now);
+
+        ui.collapsing("Step log", |ui| self.log_ui(ui));
+    }
+
+    /// Show every step of the script and how far through it the runner is
+    fn steps_ui(&self, ui: &mut Ui, now: Instant) {
+        let log = self.runner.log();
+        let started = self.runner.state() != RunState::Idle;
+
+        ScrollArea::vertical()
+            .id_source("script_steps")
+            .max_height(250.0)
+            .show(ui, |ui| {
+                Grid::new("script_steps_grid")
+                    .num_columns(3)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (i, step) in self.runner.script().steps.iter().enumerate() {
+                            let outcome =
+                                log.iter().rev().find(|r| r.step == i).map(|r| &r.outcome);
+                            let current = started && i == self.runner.current() && self.active();
+
+                            let (color, status) = match outcome {
+                                _ if current => (
+                                    Color32::YELLOW,
+                                    format!("{:.1}s", self.runner.step_elapsed(now).as_secs_f64()),
+                                ),
+                                Some(StepOutcome::Done) => (Color32::GREEN, "Done".to_string()),
+                                Some(StepOutcome::Failed(reason)) => (Color32::RED, reason.clone()),
+                                Some(StepOutcome::Aborted) => (Color32::RED, "Aborted".to_string()),
+                                _ => (Color32::GRAY, String::new()),
+                            };
+
+                            ui.label(step.line.to_string());
+                            ui.colored_label(color, step.kind.to_string());
+                            ui.colored_label(color, status);
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+
+    /// Show a record of every step run so far
+    fn log_ui(&self, ui: &mut Ui) {
+        let steps = &self.runner.script().steps;
+
+        ScrollArea::vertical()
+            .id_source("script_log")
+            .max_height(150.0)
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                Grid::new("script_log_grid")
+                    .num_columns(3)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for record in self.runner.log() {
+                            let outcome = match &record.outcome {
+                                StepOutcome::Done => "Done".to_string(),
+                                StepOutcome::Failed(reason) => format!("Failed: {reason}"),
+                                StepOutcome::Aborted => "Aborted".to_string(),
+                            };
+
+                            ui.label(format!(
+                                "{} - {}",
+                                record.started.format("%H:%M:%S"),
+                                record.finished.format("%H:%M:%S")
+                            ));
+                            ui.label(steps[record.step].kind.to_string());
+                            ui.label(outcome);
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+}
@@ -0,0 +1,450 @@
+use super::format::{Script, StepKind};
+use crate::app::command_transport::{CommandRecord, CommandStatus, ExecutionStatus};
+use crate::command::Command;
+use crate::telemetry::Telemetry;
+use crate::xbee::DeliveryStatus;
+use chrono::{DateTime, Utc};
+use std::time::{Duration, Instant};
+
+// the different states the script runner can be in
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RunState {
+    // loaded but not started
+    Idle,
+    Running,
+    Paused,
+    // every step completed
+    Finished,
+    // stopped by the user
+    Aborted,
+    // a step failed
+    Failed,
+}
+
+// how a step finished
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum StepOutcome {
+    Done,
+    Failed(String),
+    Aborted,
+}
+
+/// A record of a step which has been run
+#[derive(Debug, Clone)]
+pub struct StepRecord {
+    /// The index of the step in the script
+    pub step: usize,
+    pub started: DateTime<Utc>,
+    pub finished: DateTime<Utc>,
+    pub outcome: StepOutcome,
+}
+
+/// A command the runner wants sent
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ScriptCommand {
+    pub cmd: Command,
+    /// The line of the script the command is on
+    pub line: usize,
+}
+
+/// Runs a mission script one step at a time, it is polled every frame with the current
+/// command history and telemetry and returns any commands to send
+#[derive(Debug)]
+pub struct ScriptRunner {
+    script: Script,
+    state: RunState,
+
+    /// The index of the current step
+    current: usize,
+
+    /// When the current step started, shifted forward while paused so delays and timeouts
+    /// don't count the time spent paused
+    step_started: Instant,
+    step_started_utc: DateTime<Utc>,
+
+    /// When the current step began, telemetry received before this is too old to satisfy it
+    step_began: Instant,
+
+    /// When the runner was paused
+    paused_at: Option<Instant>,
+
+    /// The last command sent and the length of the command history when it was sent,
+    /// the command is at or after that index once it is queued
+    last_sent: Option<(Command, usize)>,
+
+    /// Every step run so far
+    log: Vec<StepRecord>,
+}
+
+impl ScriptRunner {
+    pub fn new(script: Script) -> Self {
+        Self {
+            script,
+            state: RunState::Idle,
+            current: 0,
+            step_started: Instant::now(),
+            step_started_utc: Utc::now(),
+            step_began: Instant::now(),
+            paused_at: None,
+            last_sent: None,
+            log: vec![],
+        }
+    }
+
+    pub fn script(&self) -> &Script {
+        &self.script
+    }
+
+    pub fn state(&self) -> RunState {
+        self.state
+    }
+
+    /// The index of the step being run
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    pub fn log(&self) -> &[StepRecord] {
+        &self.log
+    }
+
+    /// How long the current step has been running, ignoring time spent paused
+    pub fn step_elapsed(&self, now: Instant) -> Duration {
+        let now = self.paused_at.unwrap_or(now);
+        now.saturating_duration_since(self.step_started)
+    }
+
+    /// Run the script from the start
+    pub fn start(&mut self, now: Instant) {
+        self.current = 0;
+        self.last_sent = None;
+        self.paused_at = None;
+        self.log.clear();
+        self.state = RunState::Running;
+        self.begin_step(now);
+    }
+
+    pub fn pause(&mut self, now: Instant) {
+        if self.state == RunState::Running {
+            self.state = RunState::Paused;
+            self.paused_at = Some(now);
+        }
+    }
+
+    pub fn resume(&mut self, now: Instant) {
+        if let (RunState::Paused, Some(paused_at)) = (self.state, self.paused_at.take()) {
+            self.step_started += now.duration_since(paused_at);
+            self.state = RunState::Running;
+        }
+    }
+
+    pub fn abort(&mut self) {
+        if matches!(self.state, RunState::Running | RunState::Paused) {
+            self.finish_step(StepOutcome::Aborted);
+            self.state = RunState::Aborted;
+        }
+    }
+
+    fn begin_step(&mut self, now: Instant) {
+        self.step_started = now;
+        self.step_began = now;
+        self.step_started_utc = Utc::now();
+    }
+
+    fn finish_step(&mut self, outcome: StepOutcome) {
+        tracing::info!(
+            "Script step {} finished - {outcome:?}",
+            self.script.steps[self.current].kind
+        );
+        self.log.push(StepRecord {
+            step: self.current,
+            started: self.step_started_utc,
+            finished: Utc::now(),
+            outcome,
+        });
+    }
+
+    /// The record of the last command sent, if it has been queued yet
+    fn last_record<'a>(&self, history: &'a [CommandRecord]) -> Option<&'a CommandRecord> {
+        let (cmd, from) = self.last_sent?;
+        history.get(from..)?.iter().find(|r| r.cmd == cmd)
+    }
+
+    /// Check whether the current step has finished, `None` means it is still waiting
+    fn check_step(
+        &self,
+        now: Instant,
+        history: &[CommandRecord],
+        telem: Option<(&Telemetry, Instant)>,
+    ) -> Option<StepOutcome> {
+        let step = &self.script.steps[self.current].kind;
+        let elapsed = now.saturating_duration_since(self.step_started);
+
+        let outcome = match step {
+            StepKind::Send(_) => Some(StepOutcome::Done),
+            StepKind::Delay(delay) => (elapsed >= *delay).then_some(StepOutcome::Done),
+            StepKind::WaitAck { .. } => {
+                self.last_record(history)
+                    .and_then(|record| match record.status {
+                        CommandStatus::SentStatus {
+                            status: DeliveryStatus::Success,
+                        } => Some(StepOutcome::Done),
+                        CommandStatus::SentStatus { status } => Some(StepOutcome::Failed(format!(
+                            "{} was not delivered: {status}",
+                            record.cmd
+                        ))),
+                        CommandStatus::TimedOut => Some(StepOutcome::Failed(format!(
+                            "No TX status received for {}",
+                            record.cmd
+                        ))),
+                        CommandStatus::Cancelled => {
+                            Some(StepOutcome::Failed(format!("{} was cancelled", record.cmd)))
+                        }
+                        _ => None,
+                    })
+            }
+            StepKind::WaitEcho { .. } => self.last_record(history).and_then(|record| match record
+                .execution
+            {
+                _ if record.status == CommandStatus::Cancelled => {
+                    Some(StepOutcome::Failed(format!("{} was cancelled", record.cmd)))
+                }
+                // it can never be echoed
+                ExecutionStatus::Pending if !record.status.maybe_delivered() => Some(
+                    StepOutcome::Failed(format!("{} was not delivered", record.cmd)),
+                ),
+                ExecutionStatus::Executed => Some(StepOutcome::Done),
+                ExecutionStatus::NotEchoed => Some(StepOutcome::Failed(format!(
+                    "{} was never echoed",
+                    record.cmd
+                ))),
+                ExecutionStatus::Pending => None,
+            }),
+            // telemetry from before the step could be from before the command it waits for
+            StepKind::WaitUntil { condition, .. } => telem
+                .filter(|(telem, received)| *received > self.step_began && condition.holds(telem))
+                .map(|_| StepOutcome::Done),
+        };
+
+        outcome.or_else(|| match step.timeout() {
+            Some(timeout) if elapsed >= timeout => Some(StepOutcome::Failed(format!(
+                "Timed out after {}s",
+                timeout.as_secs_f64()
+            ))),
+            _ => None,
+        })
+    }
+
+    /// Run as many steps as possible, returns a command to send if the script reached one.
+    /// At most one command is returned per call so each is queued before the next is sent.
+    ///
+    /// `telem` is the latest telemetry and when it was received.
+    pub fn poll(
+        &mut self,
+        now: Instant,
+        history: &[CommandRecord],
+        telem: Option<(&Telemetry, Instant)>,
+    ) -> Option<ScriptCommand> {
+        while self.state == RunState::Running {
+            let Some(step) = self.script.steps.get(self.current) else {
+                self.state = RunState::Finished;
+                break;
+            };
+
+            let to_send = match step.kind {
+                StepKind::Send(cmd) => Some(ScriptCommand {
+                    cmd,
+                    line: step.line,
+                }),
+                _ => None,
+            };
+
+            let Some(outcome) = self.check_step(now, history, telem) else {
+                break;
+            };
+
+            let failed = outcome != StepOutcome::Done;
+            self.finish_step(outcome);
+            if failed {
+                self.state = RunState::Failed;
+                break;
+            }
+
+            self.current += 1;
+            self.begin_step(now);
+            if let Some(to_send) = to_send {
+                self.last_sent = Some((to_send.cmd, history.len()));
+                return Some(to_send);
+            }
+        }
+
+        None
+    }
+}
+
+impl Default for ScriptRunner {
+    fn default() -> Self {
+        Self::new(Script::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::command_transport::{CommandTransport, Priority};
+    use crate::app::commands::CommandRequest;
+    use crate::telemetry::TelemetryBuilder;
+    use crate::xbee::{Address, TxStatus};
+
+    const DST: Address = Address::Short(1);
+
+    const SCRIPT: &str = "
+        send CX,ON
+        wait ack
+        delay 2
+        send CAL
+        wait echo timeout 10
+    ";
+
+    #[test]
+    fn test_runner() {
+        let mut transport = CommandTransport::default();
+        let mut runner = ScriptRunner::new(Script::parse(SCRIPT).unwrap());
+        let start = Instant::now();
+        runner.start(start);
+
+        // the first command is sent straight away and the runner waits for its TX status
+        let sent = runner.poll(start, transport.commands(), None).unwrap();
+        assert_eq!(sent.cmd.to_string(), "CMD,1047,CX,ON");
+        assert_eq!(runner.poll(start, transport.commands(), None), None);
+
+        transport.push(CommandRequest::new(sent.cmd, Priority::Background), DST);
+        let (idx, frame_id) = transport.next_unsent().unwrap();
+        transport.sent(idx, frame_id, start);
+        assert_eq!(runner.poll(start, transport.commands(), None), None);
+        transport.recv_status(TxStatus {
+            frame_id,
+            status: DeliveryStatus::Success,
+        });
+
+        // then the delay
+        assert_eq!(runner.poll(start, transport.commands(), None), None);
+        assert_eq!(runner.current(), 2);
+
+        // pausing doesn't count towards the delay
+        runner.pause(start + Duration::from_secs(1));
+        assert_eq!(
+            runner.poll(start + Duration::from_secs(5), transport.commands(), None),
+            None
+        );
+        runner.resume(start + Duration::from_secs(5));
+        assert_eq!(
+            runner.poll(start + Duration::from_secs(5), transport.commands(), None),
+            None
+        );
+
+        let now = start + Duration::from_secs(6);
+        let sent = runner.poll(now, transport.commands(), None).unwrap();
+        assert_eq!(sent.cmd, Command::Calibrate);
+        assert_eq!(sent.line, 5);
+
+        // the echo never arrives
+        let now = now + Duration::from_secs(10);
+        assert_eq!(runner.poll(now, transport.commands(), None), None);
+        assert_eq!(runner.state(), RunState::Failed);
+
+        let outcomes: Vec<_> = runner.log().iter().map(|r| r.outcome.clone()).collect();
+        assert_eq!(outcomes.len(), 5);
+        assert_eq!(outcomes[..4], vec![StepOutcome::Done; 4]);
+        assert_eq!(
+            outcomes[4],
+            StepOutcome::Failed("Timed out after 10s".to_string())
+        );
+    }
+
+    #[test]
+    fn test_runner_fails_on_failed_delivery() {
+        let mut transport = CommandTransport::default();
+        transport.max_retries = 0;
+        let mut runner = ScriptRunner::new(Script::parse(SCRIPT).unwrap());
+        let start = Instant::now();
+        runner.start(start);
+
+        // an identical command sent before the script doesn't count
+        let cx_on = Command::TelemetryEnable(crate::command::Enabled::On);
+        transport.push(CommandRequest::new(cx_on, Priority::Urgent), DST);
+        let (idx, frame_id) = transport.next_unsent().unwrap();
+        transport.sent(idx, frame_id, start);
+        transport.recv_status(TxStatus {
+            frame_id,
+            status: DeliveryStatus::Success,
+        });
+
+        let sent = runner.poll(start, transport.commands(), None).unwrap();
+        assert_eq!(runner.poll(start, transport.commands(), None), None);
+        transport.push(CommandRequest::new(sent.cmd, Priority::Background), DST);
+        let (idx, frame_id) = transport.next_unsent().unwrap();
+        transport.sent(idx, frame_id, start);
+        transport.recv_status(TxStatus {
+            frame_id,
+            status: DeliveryStatus::NoAck,
+        });
+
+        assert_eq!(runner.poll(start, transport.commands(), None), None);
+        assert_eq!(runner.state(), RunState::Failed);
+
+        // aborting after failing does nothing
+        runner.abort();
+        assert_eq!(runner.state(), RunState::Failed);
+    }
+
+    #[test]
+    fn test_wait_echo_fails_on_failed_delivery() {
+        let mut transport = CommandTransport::default();
+        transport.max_retries = 0;
+        let mut runner = ScriptRunner::new(Script::parse("send CAL\nwait echo").unwrap());
+        let start = Instant::now();
+        runner.start(start);
+
+        let sent = runner.poll(start, transport.commands(), None).unwrap();
+        transport.push(CommandRequest::new(sent.cmd, Priority::Background), DST);
+        let (idx, frame_id) = transport.next_unsent().unwrap();
+        transport.sent(idx, frame_id, start);
+        transport.recv_status(TxStatus {
+            frame_id,
+            status: DeliveryStatus::NoAck,
+        });
+
+        // the failed delivery is reported rather than waiting for an echo which can't come
+        assert_eq!(runner.poll(start, transport.commands(), None), None);
+        assert_eq!(runner.state(), RunState::Failed);
+        assert_eq!(
+            runner.log().last().map(|r| r.outcome.clone()),
+            Some(StepOutcome::Failed(
+                "CMD,1047,CAL was not delivered".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_wait_until_ignores_old_telemetry() {
+        let script = "
+            send OPTIONAL,SETSTATE,C,WAIT_PARA
+            wait until STATE == WAIT_PARA timeout 10
+        ";
+        let mut runner = ScriptRunner::new(Script::parse(script).unwrap());
+        let start = Instant::now();
+        runner.start(start);
+
+        // the state is already WAIT_PARA, but from before the command was sent
+        let telem = TelemetryBuilder::default().state("WAIT_PARA").build();
+        let now = start + Duration::from_secs(1);
+        assert!(runner.poll(now, &[], Some((&telem, start))).is_some());
+        assert_eq!(runner.poll(now, &[], Some((&telem, start))), None);
+        assert_eq!(runner.state(), RunState::Running);
+
+        let received = now + Duration::from_secs(1);
+        assert_eq!(runner.poll(received, &[], Some((&telem, received))), None);
+        assert_eq!(runner.state(), RunState::Finished);
+    }
+}
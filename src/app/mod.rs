@@ -1,24 +1,49 @@
+mod command_transport;
 mod commands;
 mod graphable;
+mod link_stats;
+mod mission_script;
+mod profile_builder;
+mod radio_config;
 mod received_packet;
+mod remote_config;
+mod telemetry_stream;
 pub use received_packet::ReceivedPacket;
 
+use command_transport::{
+    CommandRecord, CommandStatus, CommandTransport, ExecutionStatus, Priority,
+};
 use graphable::Graphable;
+use mission_script::{ScriptCommand, ScriptPanel};
+use profile_builder::ProfileBuilderPanel;
+use radio_config::{ApplySettings, RadioConfigPanel};
+use remote_config::RemoteConfigPanel;
+use telemetry_stream::{
+    latest_telemetry, latest_telemetry_received, LongAddresses, TelemetrySource, TelemetryStream,
+};
 
 use crate::geodesic::WorldPosition;
 use crate::{
-    app::commands::CommandPanel,
+    app::commands::{CommandPanel, CommandRequest},
     as_str::AsStr,
-    constants::{BAUD_RATES, BROADCAST_ADDR, SEALEVEL_HPA, TEAM_ID, TEAM_ID_STR, TELEMETRY_FILE},
-    telemetry::{MissionTime, Telemetry, TelemetryField},
-    xbee::{DeliveryStatus, TxRequest, TxStatus, XbeePacket},
+    command::Command,
+    constants::{BAUD_RATES, BROADCAST_ADDR, TEAM_ID_STR},
+    simulation::{
+        pressure_to_altitude, Discrepancy, SimProfile, SimState, SimVerifier, SimulationController,
+    },
+    telemetry::{MissionTime, Mode, Telemetry, TelemetryField},
+    xbee::{
+        Address, ApiMode, AtCommand, AtCommandResponse, AtCommandStatus, AtValue, DeliveryStatus,
+        FrameDecoder, ModemStatus, RemoteAtCommand, Tx64Request, TxFrame, TxRequest, TxStatus,
+        XbeePacket,
+    },
 };
-use chrono::{DateTime, Utc};
 use eframe::{egui, emath::Align};
 use egui::{
     plot::{Line, Plot, PlotPoint, PlotPoints},
     text::LayoutJob,
-    Color32, DragValue, FontFamily, FontId, Grid, Layout, ScrollArea, Sense, Ui, Vec2, Widget,
+    Color32, DragValue, FontFamily, FontId, Grid, Layout, RichText, ScrollArea, Sense, Ui, Vec2,
+    Widget,
 };
 use egui_extras::{Column, TableBuilder};
 use egui_notify::Toasts;
@@ -27,13 +52,13 @@ use parking_lot::FairMutex;
 use serialport::{SerialPort, SerialPortType};
 use std::sync::mpsc::{sync_channel, TryRecvError};
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, VecDeque},
     fmt,
     fs::OpenOptions,
     io::{self, ErrorKind, Read, Write},
     path::PathBuf,
     sync::{
-        atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering},
+        atomic::{AtomicUsize, Ordering},
         mpsc::{channel, Receiver, Sender},
         Arc,
     },
@@ -46,25 +71,15 @@ static CURR_RADIO: AtomicUsize = AtomicUsize::new(0);
 // use the strongest ordering for all atomic operations
 const ORDER: Ordering = Ordering::SeqCst;
 
-// static atomic state for sharing with the sending thread
-// have we started the sending thread? - prevent starting two threads
-static SEND_THREAD_STARTED: AtomicBool = AtomicBool::new(false);
-// have we paused sending the SIMP packets
-static SEND_THREAD_PAUSED: AtomicBool = AtomicBool::new(false);
-// are we cancelling the sending thread
-static SEND_THREAD_CANCEL: AtomicBool = AtomicBool::new(false);
-// how many pressure values have been sent?
-static SENT_SIMPS: AtomicUsize = AtomicUsize::new(0);
+// how long to wait for the radio to respond to AC before following its new settings anyway
+const APPLY_TIMEOUT: Duration = Duration::from_secs(1);
 
 pub struct GroundStationGui {
-    /// The collected telemetry from the current run
-    telemetry: Vec<Telemetry>,
+    /// The collected telemetry from the current run, kept separately for each source
+    streams: BTreeMap<TelemetrySource, TelemetryStream>,
 
-    /// The values for displaying in the graphs
-    graph_values: HashMap<Graphable, Vec<PlotPoint>>,
-
-    /// The number of missed telemetry packets
-    missed_packets: u32,
+    /// Which source the graphs and table show, `None` overlays all of them
+    shown_source: Option<TelemetrySource>,
 
     /// How many telemetry points does the one graph view show?
     one_graph_points: usize,
@@ -103,9 +118,24 @@ pub struct GroundStationGui {
     /// Show the simulation window?
     show_sim_window: bool,
 
+    /// Show the mission script window?
+    show_script_window: bool,
+
     // ===== simulation mode values =====
-    /// The simulation pressure values
-    simp_values: Option<Vec<u32>>,
+    /// The loaded simulation profile
+    sim_profile: Option<SimProfile>,
+
+    /// Plays back the simulation pressure values
+    simulation: Option<SimulationController>,
+
+    /// Builds profiles from flight parameters
+    profile_builder: ProfileBuilderPanel,
+
+    /// Checks the CanSat is using the simulated pressure values, reset each time playback starts
+    sim_verifier: Option<SimVerifier>,
+
+    /// Plot the verification in altitude rather than pressure?
+    sim_plot_altitude: bool,
 
     /// The graph values for each SIMP value
     simp_graph_values: Option<Vec<PlotPoint>>,
@@ -114,13 +144,15 @@ pub struct GroundStationGui {
     /// The command center
     command_center: CommandPanel,
 
+    /// Loads and runs mission scripts
+    script_panel: ScriptPanel,
+
     /// The channel over which to send and receive commands
-    cmd_sender: Sender<String>,
-    cmd_receiver: Receiver<String>,
+    cmd_sender: Sender<CommandRequest>,
+    cmd_receiver: Receiver<CommandRequest>,
 
-    /// A mapping from the time a command was state, to the command and it's status
-    /// allows iterating in sent order due to BTreeMap's inherent ordering
-    command_history: BTreeMap<DateTime<Utc>, (String, CommandStatus)>,
+    /// Every command sent, along with its destination, status and the attempts to send it
+    commands: CommandTransport,
 
     /// The radio's serial port name
     radio_port: String,
@@ -128,15 +160,30 @@ pub struct GroundStationGui {
     /// The radio's baud rate
     radio_baud: u32,
 
-    /// The address to send packets to, 0xFFFF for broadcast
+    /// The API mode the radio is configured in, AP=1 or AP=2
+    radio_api_mode: ApiMode,
+
+    /// The address to send commands to if they don't choose one, 0xFFFF for broadcast
     dst_addr: u16,
 
+    /// The 64-bit addresses of the CanSat's radios, used to sort their telemetry
+    long_addrs: LongAddresses,
+
+    /// The local radio's settings
+    radio_config: RadioConfigPanel,
+
+    /// The settings of the CanSat's radios, changed over the air
+    remote_config: RemoteConfigPanel,
+
     /// The XBee radio serial port connection
     radio: Option<Arc<FairMutex<Box<dyn SerialPort>>>>,
 
     /// The instant the radio last sent a command
     radio_last_sent: Instant,
 
+    /// AT commands waiting to be written to the radio
+    at_queue: VecDeque<QueuedAt>,
+
     /// The channel down which to receive packets
     packet_rx: Option<Receiver<ReceivedPacket>>,
 
@@ -146,6 +193,12 @@ pub struct GroundStationGui {
     /// The RSSI of the previous received packet.
     last_packet_rssi: Option<i8>,
 
+    /// The last event reported by the local radio
+    last_modem_status: Option<ModemStatus>,
+
+    /// Append received telemetry to each source's CSV file
+    save_telemetry: bool,
+
     /// The world position of the cansat from the last telemetry
     last_telem_world_pos: Option<WorldPosition>,
 
@@ -177,9 +230,8 @@ impl Default for GroundStationGui {
         let (tx, rx) = channel();
 
         Self {
-            telemetry: vec![],
-            graph_values: Default::default(),
-            missed_packets: 0,
+            streams: Default::default(),
+            shown_source: None,
             one_graph_points: 40,
             all_graphs_points: 40,
             one_graph_shows_all: false,
@@ -192,20 +244,33 @@ impl Default for GroundStationGui {
             show_radio_window: false,
             show_gps_window: false,
             show_sim_window: false,
-            simp_values: None,
+            show_script_window: false,
+            sim_profile: None,
+            simulation: None,
+            profile_builder: Default::default(),
+            sim_verifier: None,
+            sim_plot_altitude: false,
             simp_graph_values: None,
             command_center: Default::default(),
+            script_panel: Default::default(),
             cmd_sender: tx,
             cmd_receiver: rx,
-            command_history: Default::default(),
+            commands: Default::default(),
             radio_port: "".to_string(),
             radio_baud: 230400,
+            radio_api_mode: Default::default(),
             dst_addr: BROADCAST_ADDR,
+            long_addrs: Default::default(),
+            radio_config: Default::default(),
+            remote_config: Default::default(),
             radio: None,
             radio_last_sent: Instant::now(),
+            at_queue: VecDeque::new(),
             packet_rx: None,
             packet_log: vec![],
             last_packet_rssi: None,
+            last_modem_status: None,
+            save_telemetry: true,
             last_telem_world_pos: None,
             ground_station_world_pos: Default::default(),
             file_receiver: None,
@@ -255,14 +320,45 @@ impl GroundStationGui {
                         let mut attempt_recovery = false;
                         match &packet {
                             ReceivedPacket::Telemetry { telem, frame, .. } => {
-                                self.add_telem(telem.clone());
-                                self.last_packet_rssi = Some(frame.rssi);
+                                let source =
+                                    TelemetrySource::from_address(frame.src(), &self.long_addrs);
+                                self.add_telem(telem.clone(), source);
+                                self.last_packet_rssi = frame.rssi().or(self.last_packet_rssi);
                             }
                             ReceivedPacket::Status { tx_status, .. } => {
                                 self.recv_ack(*tx_status);
                             }
+                            ReceivedPacket::ExtendedStatus { tx_status, .. } => {
+                                self.recv_ack((*tx_status).into());
+                            }
+                            ReceivedPacket::AtResponse { response, .. } => {
+                                self.recv_apply_response(response);
+                                let settings = self
+                                    .radio_config
+                                    .recv_response(response, &mut self.notifications);
+                                if let Some(settings) = settings {
+                                    self.apply_radio_settings(settings);
+                                }
+
+                                // warn if the radio isn't in the API mode we're decoding
+                                let expected = self.radio_api_mode.ap_value();
+                                if let Some(AtValue::ApiMode(ap)) = response.value() {
+                                    if ap != expected {
+                                        self.notifications.warning(format!(
+                                            "radio is in AP={ap} but the ground station expects AP={expected}"
+                                        ));
+                                    }
+                                }
+                            }
+                            ReceivedPacket::RemoteAtResponse { response, .. } => {
+                                self.remote_config
+                                    .recv_response(response, &mut self.notifications);
+                            }
+                            ReceivedPacket::ModemStatus { status, .. } => {
+                                self.recv_modem_status(*status);
+                            }
                             ReceivedPacket::Received { frame, .. } => {
-                                self.last_packet_rssi = Some(frame.rssi);
+                                self.last_packet_rssi = frame.rssi().or(self.last_packet_rssi);
                                 attempt_recovery = true;
                             }
                             _ => {
@@ -272,11 +368,12 @@ impl GroundStationGui {
 
                         // attempt to recover telemetry from the raw bytes
                         if attempt_recovery {
-                            for telem in Self::recover_telemetry(&packet) {
+                            let (source, recovered) = self.recover_telemetry(&packet);
+                            for telem in recovered {
                                 tracing::info!(
                                     "Recovered some telemetry from an invalid packet - {telem}"
                                 );
-                                self.add_telem(telem);
+                                self.add_telem(telem, source);
                             }
                         }
                     }
@@ -299,39 +396,34 @@ impl GroundStationGui {
     }
 
     /// handles all the logic / state that must be kept in sync when adding telemetry
-    fn add_telem(&mut self, telem: Telemetry) {
-        // calculate how many packets we missed if any
-        if let Some(prev) = self.telemetry.last() {
-            self.missed_packets += telem.packet_count.saturating_sub(1 + prev.packet_count);
+    fn add_telem(&mut self, telem: Telemetry, source: TelemetrySource) {
+        tracing::debug!("source={source} - {telem:?}");
+        // the container is the part of the CanSat which runs simulation mode
+        if source != TelemetrySource::Probe {
+            self.verify_simulation(&telem);
         }
-
-        tracing::debug!("{:?}", telem);
-        self.telemetry.push(telem.clone());
-
-        let time = telem.mission_time.as_seconds();
-        for field in all::<Graphable>() {
-            self.graph_values
-                .entry(field)
-                .or_default()
-                .push(PlotPoint::new(time, field.extract_telemetry_value(&telem)));
+        if let Some(idx) = self.commands.recv_echo(&telem.cmd_echo, Instant::now()) {
+            let cmd = &self.commands.commands()[idx].cmd;
+            tracing::info!("CanSat executed command {cmd:?}");
         }
+        self.streams.entry(source).or_default().push(telem.clone());
 
-        // save the telemetry out to the telemetry file
-        let handle = OpenOptions::new()
-            .append(true)
-            .create(true)
-            .open(TELEMETRY_FILE);
+        // save the telemetry out to the source's telemetry file
+        if self.save_telemetry {
+            let file_name = source.file_name();
+            let handle = OpenOptions::new().append(true).create(true).open(file_name);
 
-        let result = match handle {
-            Ok(mut file) => writeln!(file, "{telem}"),
-            Err(e) => {
-                tracing::warn!("Failed to open `{TELEMETRY_FILE}` - {e}.");
-                Ok(())
-            }
-        };
+            let result = match handle {
+                Ok(mut file) => writeln!(file, "{telem}"),
+                Err(e) => {
+                    tracing::warn!("Failed to open `{file_name}` - {e}.");
+                    Ok(())
+                }
+            };
 
-        if let Err(e) = result {
-            tracing::warn!("Encountered error while writing to file: {e}");
+            if let Err(e) = result {
+                tracing::warn!("Encountered error while writing to file: {e}");
+            }
         }
 
         // save the last world position
@@ -340,31 +432,62 @@ impl GroundStationGui {
 
     /// Handle an ack for a packet
     fn recv_ack(&mut self, tx_status: TxStatus) {
-        // if the delivery was a success mark it as acknowledged
-        if tx_status.status == DeliveryStatus::Success {
-            // mark the command as acknowledged
-            for (_, (cmd, status)) in self.command_history.iter_mut().rev() {
-                match status {
-                    CommandStatus::Sent { frame_id } if *frame_id == tx_status.frame_id => {
-                        tracing::info!("Received acknowledgement for command - {cmd:?}");
-                        *status = CommandStatus::SentStatus {
-                            status: tx_status.status,
-                        };
-                        break;
-                    }
-                    _ => (),
-                }
+        let Some(idx) = self.commands.recv_status(tx_status) else {
+            return;
+        };
+
+        let CommandRecord {
+            cmd, dst, status, ..
+        } = &self.commands.commands()[idx];
+        tracing::info!("Received {tx_status} for command - {cmd:?} to {dst}");
+        match status {
+            CommandStatus::SentStatus {
+                status: DeliveryStatus::Success,
+            } => {}
+            CommandStatus::SentStatus { status } => {
+                tracing::warn!("Failed to deliver command {cmd:?} to {dst} - {status}");
+                self.notifications
+                    .error(format!("Failed to deliver {cmd}: {status}"));
+            }
+            _ => {}
+        }
+    }
+
+    /// Handle an event reported by the local radio
+    fn recv_modem_status(&mut self, status: ModemStatus) {
+        tracing::info!("Radio modem status - {status}");
+        if status.is_fault() {
+            self.notifications.warning(format!("Radio: {status}"));
+        } else {
+            self.notifications.info(format!("Radio: {status}"));
+        }
+
+        // settings which weren't saved with WR are lost if the radio reset, so read them again
+        if matches!(
+            status,
+            ModemStatus::HardwareReset | ModemStatus::WatchdogReset
+        ) {
+            for cmd in self.radio_config.read() {
+                self.send_at_command(cmd);
             }
         }
+
+        self.last_modem_status = Some(status);
     }
 
     /// Sometimes invalid packets contain data that we can actually salvage
-    fn recover_telemetry(packet: &ReceivedPacket) -> Vec<Telemetry> {
+    fn recover_telemetry(&self, packet: &ReceivedPacket) -> (TelemetrySource, Vec<Telemetry>) {
         // a sorted alphabet of valid characters
         const ALPHABET: &[u8] =
-            b",.0123456789:ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
-        let ReceivedPacket::Invalid(data) = packet else {
-            return vec![];
+            b",-.0123456789:ABCDEFGHIJKLMNOPQRSTUVWXYZ_abcdefghijklmnopqrstuvwxyz";
+        let (source, data) = match packet {
+            ReceivedPacket::Invalid(data) => (TelemetrySource::Other, data.as_slice()),
+            // the frame tells us who sent it even though its data isn't valid telemetry
+            ReceivedPacket::Received { frame, .. } => {
+                let source = TelemetrySource::from_address(frame.src(), &self.long_addrs);
+                (source, frame.data())
+            }
+            _ => return (TelemetrySource::Other, vec![]),
         };
 
         // extract all the ASCII substrings of this data
@@ -384,13 +507,14 @@ impl GroundStationGui {
         tracing::debug!("Found ascii substrings in invalid data: {ascii_substrings:?}");
 
         // collect any substrings which parse as telemetry
-        ascii_substrings
+        let recovered = ascii_substrings
             .into_iter()
             .filter_map(|s| {
                 let start = s.find(TEAM_ID_STR)?;
                 s[start..].parse().ok()
             })
-            .collect()
+            .collect();
+        (source, recovered)
     }
 
     /// Attempts to open a connection to the given radio
@@ -407,6 +531,7 @@ impl GroundStationGui {
                 let radio_num = CURR_RADIO.fetch_add(1, ORDER) + 1;
                 let radio = Arc::new(FairMutex::new(port));
                 let (tx, rx) = channel();
+                let mode = self.radio_api_mode;
                 self.radio = Some(radio.clone());
                 // sending command immediately after opening seems to not work well
                 self.radio_last_sent = Instant::now();
@@ -415,7 +540,7 @@ impl GroundStationGui {
                 // start a new thread :D
                 if let Err(e) = thread::Builder::new()
                     .name(format!("radio_{radio_num}"))
-                    .spawn(move || Self::radio_thread(radio_num, radio, tx))
+                    .spawn(move || Self::radio_thread(radio_num, radio, mode, tx))
                 {
                     tracing::error!("Failed to start radio reader thread - {e:?}");
                     self.notifications.error("failed to start radio thread");
@@ -437,12 +562,13 @@ impl GroundStationGui {
     fn radio_thread(
         radio_num: usize,
         radio_mutex: Arc<FairMutex<Box<dyn SerialPort>>>,
+        mode: ApiMode,
         packet_tx: Sender<ReceivedPacket>,
     ) {
-        // allocate a buffer for receiving packets
+        // allocate a buffer for receiving data, frames are reassembled by the decoder
         const BUFSIZ: usize = 4096;
         let mut buf = [0u8; BUFSIZ];
-        let mut write_idx = 0;
+        let mut decoder = FrameDecoder::new(mode);
 
         // open the radio data log in append mode
         let mut log_file = OpenOptions::new()
@@ -463,22 +589,20 @@ impl GroundStationGui {
                 radio
                     .bytes_to_read()
                     .map_err(io::Error::other)
-                    .and_then(|n| {
-                        radio.read(&mut buf[write_idx..usize::min(write_idx + n as usize, BUFSIZ)])
-                    })
+                    .and_then(|n| radio.read(&mut buf[..usize::min(n as usize, BUFSIZ)]))
             };
 
             match read_res {
                 Ok(bytes_read) => {
+                    let data = &buf[..bytes_read];
                     tracing::debug!(
-                        "Read {bytes_read} bytes from the radio - {:?} - {:02X?}",
-                        String::from_utf8_lossy(&buf[..bytes_read]),
-                        &buf[write_idx..write_idx + bytes_read]
+                        "Read {bytes_read} bytes from the radio - {:?} - {data:02X?}",
+                        String::from_utf8_lossy(data),
                     );
 
                     // save any data we receive to a file
                     if let Ok(file) = log_file.as_mut() {
-                        let save_data_res = file.write_all(&buf[write_idx..write_idx + bytes_read]);
+                        let save_data_res = file.write_all(data);
 
                         // log any errors
                         if let Err(e) = save_data_res {
@@ -486,8 +610,16 @@ impl GroundStationGui {
                         }
                     }
 
-                    // bump the write index
-                    write_idx += bytes_read;
+                    for event in decoder.feed(data) {
+                        let received = ReceivedPacket::from(event);
+                        tracing::info!("Received: {received:02X?}");
+
+                        // if this fails then this thread should die
+                        if let Err(e) = packet_tx.send(received) {
+                            tracing::error!("Encountered error sending packet over channel - {e:?} - ending radio thread.");
+                            return;
+                        }
+                    }
                 }
 
                 Err(e) => {
@@ -502,248 +634,370 @@ impl GroundStationGui {
                         }
                         ErrorKind::BrokenPipe => {
                             tracing::info!("Radio disconnected - stopping receiver thread");
-                            return;
+                            break;
                         }
                         _ => {
                             tracing::warn!("Received unrecognised error while reading from radio - {e:?} - stopping receiver thread");
-                            return;
+                            break;
                         }
                     }
                 }
             };
 
-            // find packets in the sent data by looking for the start byte
-            let candidates = buf[..write_idx]
-                .iter()
-                .enumerate()
-                .filter_map(|(idx, b)| (*b == 0x7E).then_some(idx));
-
-            // keep track of where we have parsed upto
-            let mut parsed_upto = 0;
-            for start in candidates {
-                tracing::debug!("start = {start}, parsed_upto = {parsed_upto}");
-
-                let potential_packet = &buf[start..write_idx];
-                let received: ReceivedPacket = potential_packet.into();
-
-                match &received {
-                    ReceivedPacket::Telemetry { packet, .. }
-                    | ReceivedPacket::Received { packet, .. }
-                    | ReceivedPacket::Status { packet, .. }
-                    | ReceivedPacket::InvalidFrame(packet)
-                    | ReceivedPacket::Unrecognised(packet) => {
-                        // as good as we're going to get from this one, so send it over
-                        tracing::info!("Received: {received:02X?}");
+            // we want to check the radio very often so only sleep for a millisecond
+            thread::sleep(Duration::from_millis(1));
+        }
 
-                        // if our start is further than `parsed_upto` then output
-                        // whatever came before as an invalid packet.
-                        if start != parsed_upto {
-                            // we don't really care if this fails
-                            let _ = packet_tx
-                                .send(ReceivedPacket::Invalid(buf[parsed_upto..start].to_vec()));
-                        }
+        // output whatever is left in the decoder as Invalid([..]) before exiting
+        if let Some(event) = decoder.flush() {
+            packet_tx.send(event.into()).ok();
+        }
+    }
 
-                        // calculate the packet length while we still borrow the packet
-                        let packet_len = packet.data.len() + 5;
+    /// Send an AT command to the local radio, these aren't sent over the air so the
+    /// command rate limit doesn't apply
+    fn send_at_command(&mut self, cmd: AtCommand) {
+        self.at_queue.push_back(QueuedAt::Local(cmd));
+    }
 
-                        // if this fails then this thread should die
-                        if let Err(e) = packet_tx.send(received) {
-                            tracing::error!("Encountered error sending packet over channel - {e:?} - ending radio thread.");
-                            return;
-                        }
+    /// Send an AT command to one of the CanSat's radios over the air
+    fn send_remote_at_command(&mut self, cmd: RemoteAtCommand) {
+        self.at_queue.push_back(QueuedAt::Remote(cmd));
+    }
 
-                        // now update parsed_upto
-                        // packet_len = data_len + 1 (checksum) + 1 (frame type) + 2 (length) + 1 (start byte)
-                        parsed_upto = start + packet_len;
-                    }
-                    // parse failed so try again later
-                    ReceivedPacket::Invalid(_) => {}
-                }
-            }
+    /// Apply the settings the radio has accepted, following any change to its API mode or baud
+    /// rate before they are saved
+    fn apply_radio_settings(&mut self, settings: ApplySettings) {
+        let apply_id = settings.apply.frame_id;
+        self.send_at_command(settings.apply);
+        if settings.api_mode.is_some() || settings.baud.is_some() {
+            self.at_queue.push_back(QueuedAt::Follow {
+                apply_id,
+                api_mode: settings.api_mode,
+                baud: settings.baud,
+                sent_at: None,
+                applied: false,
+            });
+        }
+        self.send_at_command(settings.write);
+    }
 
-            // if we are at the end of the buffer then attempt to find the start byte of the
-            // last packet sent and make that the new start of the buffer
-            if write_idx == buf.len() {
-                // only search in the last 256 bytes because that is the maximum size of a packet
-                match buf[buf.len() - 256..].iter().rposition(|x| *x == 0x7E) {
-                    // simply set parsed_upto and let the later code handle the buffer logic
-                    Some(back_pos) => parsed_upto = back_pos,
-                    None => parsed_upto = write_idx,
-                }
-            }
+    /// Let a queued follow know the radio has responded to its AC command
+    fn recv_apply_response(&mut self, resp: &AtCommandResponse) {
+        let Some(idx) = self.at_queue.iter().position(
+            |queued| matches!(queued, QueuedAt::Follow { apply_id, .. } if *apply_id == resp.frame_id),
+        ) else {
+            return;
+        };
 
-            // if we have parsed any data then move unparsed data to the start
-            if parsed_upto > 0 {
-                buf.copy_within(parsed_upto..write_idx, 0);
-                write_idx -= parsed_upto;
+        if resp.status == AtCommandStatus::Ok {
+            if let QueuedAt::Follow { applied, .. } = &mut self.at_queue[idx] {
+                *applied = true;
             }
+        } else {
+            // the radio is still using its old settings
+            tracing::warn!("Not following radio settings which failed to apply");
+            self.at_queue.remove(idx);
+        }
+    }
 
-            // we want to check the radio very often so only sleep for a millisecond
-            thread::sleep(Duration::from_millis(1));
+    /// Reopen the radio with the API mode and baud rate just applied to it
+    fn follow_radio_settings(&mut self, api_mode: Option<ApiMode>, baud: Option<u32>) {
+        let api_mode = api_mode.unwrap_or(self.radio_api_mode);
+        let baud = baud.unwrap_or(self.radio_baud);
+        if self.radio.is_none() || (api_mode, baud) == (self.radio_api_mode, self.radio_baud) {
+            return;
+        }
+
+        tracing::info!("Reopening the radio in {api_mode} at {baud} baud");
+        self.notifications
+            .info(format!("Radio changed to {api_mode} at {baud} baud"));
+        self.radio_api_mode = api_mode;
+        self.radio_baud = baud;
+        self.close_radio();
+        self.open_radio_connection();
+
+        // closing the radio forgot its settings
+        for cmd in self.radio_config.read() {
+            self.send_at_command(cmd);
         }
+    }
+
+    /// Write any queued AT commands to the radio, waiting for the next frame if the radio thread
+    /// is using it
+    fn send_at_commands(&mut self) {
+        while let Some(mut queued) = self.at_queue.pop_front() {
+            let packet = match &mut queued {
+                QueuedAt::Local(cmd) => XbeePacket::try_from(cmd.clone()),
+                QueuedAt::Remote(cmd) => XbeePacket::try_from(cmd.clone()),
+                QueuedAt::Follow {
+                    api_mode,
+                    baud,
+                    sent_at,
+                    applied,
+                    ..
+                } => {
+                    let (api_mode, baud, applied) = (*api_mode, *baud, *applied);
+
+                    // the AC before this has just been written, wait for the radio to respond
+                    // so it isn't lost by closing the port or overtaken by the commands after
+                    let sent_at = *sent_at.get_or_insert_with(Instant::now);
+                    let timed_out = Instant::now().duration_since(sent_at) >= APPLY_TIMEOUT;
+                    if !applied && !timed_out && self.radio.is_some() {
+                        self.at_queue.push_front(queued);
+                        return;
+                    }
+                    if !applied && timed_out {
+                        tracing::warn!("No response to AC, following the radio settings anyway");
+                    }
+
+                    self.follow_radio_settings(api_mode, baud);
+                    continue;
+                }
+            };
+
+            let Some(radio_mutex) = self.radio.as_ref() else {
+                tracing::warn!("Tried to send an AT command without a radio - {queued}");
+                continue;
+            };
+            let data = match packet.and_then(|packet| packet.serialise(self.radio_api_mode)) {
+                Ok(data) => data,
+                Err(e) => {
+                    tracing::error!("Failed to build a packet for {queued} - {e:?}");
+                    continue;
+                }
+            };
+            let Some(mut radio) = radio_mutex.try_lock() else {
+                self.at_queue.push_front(queued);
+                return;
+            };
+            let res = radio.write_all(&data);
+            drop(radio);
 
-        // if the write index is not zero, output whatever is in the buffer as Invalid([..]) before exiting
-        if write_idx != 0 {
-            packet_tx
-                .send(ReceivedPacket::Invalid(buf[..write_idx].to_vec()))
-                .ok();
+            match (res, queued) {
+                (Ok(()), QueuedAt::Local(cmd)) => {
+                    tracing::info!("Sent AT command - {cmd}");
+                    self.packet_log.push(Packet::SentAt(cmd));
+                }
+                (Ok(()), QueuedAt::Remote(cmd)) => {
+                    tracing::info!("Sent remote AT command - {cmd}");
+                    self.remote_config.sent(cmd.frame_id);
+                    self.packet_log.push(Packet::SentRemoteAt(cmd));
+                    self.radio_last_sent = Instant::now();
+                }
+                (Err(e), queued) => {
+                    tracing::error!("Failed to send {queued} - {e:?}");
+                    self.notifications.error("failed to send AT command");
+                }
+                (Ok(()), QueuedAt::Follow { .. }) => unreachable!("Follow isn't written"),
+            }
         }
     }
 
     /// Close the current radio
     fn close_radio(&mut self) {
         self.radio = None;
+        self.radio_config.clear();
+        self.last_modem_status = None;
         tracing::debug!("Closed connection - CURR_RADIO={}", CURR_RADIO.load(ORDER));
         CURR_RADIO.fetch_add(1, ORDER);
 
         // receive any packets remaining
         while let Some(packet) = self.packet_rx.as_mut().and_then(|rx| rx.try_recv().ok()) {
             self.packet_log.push(Packet::Received(packet.clone()));
-            if let ReceivedPacket::Telemetry { telem, .. } = packet {
-                self.add_telem(telem);
+            if let ReceivedPacket::Telemetry { telem, frame, .. } = packet {
+                let source = TelemetrySource::from_address(frame.src(), &self.long_addrs);
+                self.add_telem(telem, source);
             } else {
-                for telem in Self::recover_telemetry(&packet) {
+                let (source, recovered) = self.recover_telemetry(&packet);
+                for telem in recovered {
                     tracing::info!("Recovered some telemetry from an invalid packet - {telem}");
-                    self.add_telem(telem);
+                    self.add_telem(telem, source);
                 }
             }
         }
     }
 
+    /// Read any waiting commands from the channel into the command history, marking them unsent
+    fn recv_commands(&mut self) {
+        while let Ok(req) = self.cmd_receiver.try_recv() {
+            tracing::debug!("Received command from channel - {req:?}");
+            // commands without a destination go to the one set in the radio window
+            self.commands.push(req, Address::Short(self.dst_addr));
+        }
+    }
+
     /// Handle reading commands from the channel and sending them down the radio
     fn handle_commands(&mut self) {
-        // read any waiting commands into the command history, marking then unsent
-        while let Ok(cmd) = self.cmd_receiver.try_recv() {
-            tracing::debug!("Received command from channel - cmd={cmd:?}");
-            self.command_history
-                .insert(Utc::now(), (cmd, CommandStatus::Unsent));
-        }
+        self.recv_commands();
 
-        // wrapping counter for the frame IDs
-        static FRAME_ID_COUNTER: AtomicU8 = AtomicU8::new(1);
+        // free the frame IDs of any commands the radio never replied to
+        for idx in self.commands.check_timeouts(Instant::now()) {
+            let cmd = &self.commands.commands()[idx].cmd;
+            self.notifications
+                .warning(format!("No TX status received for {cmd}"));
+        }
+        for idx in self.commands.check_echo_timeouts(Instant::now()) {
+            let cmd = &self.commands.commands()[idx].cmd;
+            self.notifications
+                .warning(format!("{cmd} was never echoed by the CanSat"));
+        }
 
         let Some(radio_mutex) = self.radio.as_mut() else { return };
 
-        // attempt to send any unsent commands
-        for (_, (ref cmd, status)) in self.command_history.iter_mut() {
-            if *status != CommandStatus::Unsent {
-                continue;
-            }
+        // limit the rate commands are sent at
+        if Instant::now().duration_since(self.radio_last_sent) < self.commands.send_interval {
+            return;
+        }
 
-            let mut frame_id = FRAME_ID_COUNTER.fetch_add(1, Ordering::SeqCst);
-            // frame ID == 0 means no ack :(
-            while frame_id == 0 {
-                frame_id = FRAME_ID_COUNTER.fetch_add(1, Ordering::SeqCst);
-            }
-            // ADDMEHERE
-            let req = TxRequest::new(frame_id, BROADCAST_ADDR, cmd);
-            let Ok(packet): io::Result<XbeePacket> = req.clone().try_into() else {
-                tracing::error!("Failed to build a packet for cmd={cmd:?}");
-                continue;
-            };
-            match packet.clone().serialise() {
-                Ok(data) => {
-                    let Some(mut radio) = radio_mutex.try_lock() else {
-                        continue;
-                    };
-
-                    // send packets at a max rate of 1 every 100ms
-                    if Instant::now().duration_since(self.radio_last_sent)
-                        < Duration::from_millis(100)
-                    {
-                        break;
-                    }
+        // attempt to send the most urgent unsent command
+        let Some((idx, frame_id)) = self.commands.next_unsent() else {
+            return;
+        };
+        let CommandRecord { cmd, dst, .. } = &self.commands.commands()[idx];
 
-                    if let Err(e) = radio.write(&data) {
-                        tracing::error!("Failure sending packet - {data:02X?} - {e:?}");
-                    } else {
-                        tracing::info!("Sent command {cmd:?} with frame_id={frame_id:02X}");
-                        *status = CommandStatus::Sent { frame_id };
-                        self.packet_log.push(Packet::Sent(req));
-                        self.radio_last_sent = Instant::now();
-                        break;
+        let req = match *dst {
+            Address::Short(addr) => TxFrame::Tx16(TxRequest::new(frame_id, addr, cmd.to_string())),
+            Address::Long(addr) => TxFrame::Tx64(Tx64Request::new(frame_id, addr, cmd.to_string())),
+        };
+        let Ok(packet): io::Result<XbeePacket> = req.clone().try_into() else {
+            tracing::error!("Failed to build a packet for cmd={cmd:?}");
+            return;
+        };
+        match packet.serialise(self.radio_api_mode) {
+            Ok(data) => {
+                let Some(mut radio) = radio_mutex.try_lock() else {
+                    return;
+                };
+
+                if let Err(e) = radio.write(&data) {
+                    tracing::error!("Failure sending packet - {data:02X?} - {e:?}");
+                } else {
+                    tracing::info!("Sent command {cmd:?} to {dst} with frame_id={frame_id:02X}");
+                    self.commands.sent(idx, frame_id, Instant::now());
+
+                    // the verifier times simulated pressure values from when they're first sent
+                    let record = &self.commands.commands()[idx];
+                    if let (Command::SimulatedPressure(pressure), Priority::Simulation, 1) =
+                        (record.cmd, record.priority, record.attempts.len())
+                    {
+                        if let Some(verifier) = self.sim_verifier.as_mut() {
+                            verifier.record_sent(pressure, Instant::now());
+                        }
                     }
+                    self.packet_log.push(Packet::Sent(req));
+                    self.radio_last_sent = Instant::now();
                 }
-                Err(e) => {
-                    tracing::error!("Failure serialising packet with data - {cmd:?} - {e:?}")
-                }
             }
+            Err(e) => {
+                tracing::error!("Failure serialising packet with data - {cmd:?} - {e:?}")
+            }
+        }
+    }
+
+    /// Write a command straight to the radio instead of queueing it, for when the app is
+    /// closing and won't get another chance to send queued commands
+    fn send_command_now(&mut self, cmd: Command) {
+        let Some(radio_mutex) = self.radio.as_ref() else {
+            tracing::warn!("Couldn't send {cmd:?} as the radio is disconnected");
+            return;
+        };
+
+        // nothing will be around to read the TX status
+        let req = TxFrame::Tx16(TxRequest::new(0, self.dst_addr, cmd.to_string()));
+        let data = XbeePacket::try_from(req).and_then(|p| p.serialise(self.radio_api_mode));
+        let Ok(data) = data else {
+            tracing::error!("Failed to build a packet for cmd={cmd:?}");
+            return;
+        };
+
+        let Some(mut radio) = radio_mutex.try_lock_for(Duration::from_secs(1)) else {
+            tracing::error!("Couldn't send {cmd:?} as the radio is busy");
+            return;
+        };
+        match radio.write_all(&data) {
+            Ok(()) => tracing::info!("Sent command {cmd:?} to {:04X}", self.dst_addr),
+            Err(e) => tracing::error!("Failure sending packet - {data:02X?} - {e:?}"),
         }
     }
 
     fn load_sim_file(&mut self, path: PathBuf) -> anyhow::Result<()> {
-        // first read the lines of the file
-        let file_data = std::fs::read_to_string(path)?;
-        let lines: Vec<_> = file_data.split_ascii_whitespace().collect();
-
-        // pre-allocate a vector with enough capacity to hold one pressure value for each line
-        let mut pressure_data: Vec<u32> = Vec::with_capacity(lines.len());
-
-        for line in lines {
-            // try to parse the line as u32, log the error if it failed
-            let s = line.trim();
-            if let Ok(pressure) = s.parse::<u32>() {
-                pressure_data.push(pressure);
-            } else if let Ok(telem) = s.parse::<Telemetry>() {
-                pressure_data.push(Self::altitude_to_pressure(telem.altitude));
-            } else {
-                tracing::warn!("Failed to parse line as pressure value - line={s:?}")
-            }
+        let file_data = std::fs::read_to_string(&path)?;
+        let profile = SimProfile::parse(&file_data);
+        if profile.pressures.is_empty() {
+            anyhow::bail!("no pressure values found in {}", path.display());
+        }
+        if !profile.errors.is_empty() {
+            self.notifications.warning(format!(
+                "{} lines of {} couldn't be parsed",
+                profile.errors.len(),
+                path.display()
+            ));
         }
 
+        self.load_sim_profile(profile);
+        Ok(())
+    }
+
+    fn load_sim_profile(&mut self, profile: SimProfile) {
         // create the graph values
-        let plot_points: Vec<PlotPoint> = pressure_data
+        let plot_points: Vec<PlotPoint> = profile
+            .pressures
             .iter()
             .enumerate()
-            .map(|(i, simp)| PlotPoint::new(i as f64, Self::pressure_to_altitude(*simp)))
+            .map(|(i, simp)| PlotPoint::new(i as f64, pressure_to_altitude(*simp)))
             .collect();
 
-        self.simp_values = Some(pressure_data);
+        // stop any playback of the old file before replacing it
+        self.stop_simulation();
+        self.simulation = Some(SimulationController::new(profile.pressures.clone()));
+        self.sim_profile = Some(profile);
 
         self.simp_graph_values = Some(plot_points);
-        Ok(())
-    }
-
-    fn pressure_to_altitude(pressure: u32) -> f64 {
-        // Adapted from readAltitude
-        // Equation taken from BMP180 datasheet (page 16):
-        //  http://www.adafruit.com/datasheets/BST-BMP180-DS000-09.pdf
-
-        // Note that using the equation from wikipedia can give bad results
-        // at high altitude. See this thread for more information:
-        //  http://forums.adafruit.com/viewtopic.php?f=22&t=58064
-        let simp_hpa = (pressure as f64) / 100.0;
-        44330.0 * (1.0 - (simp_hpa / SEALEVEL_HPA).powf(0.1903))
-    }
-
-    fn altitude_to_pressure(altitude: f64) -> u32 {
-        // inverted form of pressure_to_altitude
-        let presssure_hpa = SEALEVEL_HPA * (1.0 - altitude / 44330.0).powf(1.0 / 0.1903);
-        (presssure_hpa * 100.0) as u32
     }
 }
 
 /// GUI components
 impl GroundStationGui {
-    fn graph(&mut self, ui: &mut Ui, id_source: &str, field: Graphable, to_show: usize) {
-        let to_skip = self.telemetry.len().saturating_sub(to_show);
-        let points: Vec<PlotPoint> = self
-            .graph_values
-            .entry(field)
-            .or_default()
+    /// The telemetry streams selected to be shown
+    fn shown_streams(&self) -> impl Iterator<Item = (TelemetrySource, &TelemetryStream)> {
+        let shown = self.shown_source;
+        self.streams
             .iter()
-            .skip(to_skip)
-            .copied()
-            .collect();
-        let (min_val, max_val) = points.iter().fold(
-            (field.min_value(), field.max_value()),
-            |(cmin, cmax), point| {
-                let new_min = cmin.map_or(point.y, |y| y.min(point.y));
-                let new_max = cmax.map_or(point.y, |y| y.max(point.y));
-                (Some(new_min), Some(new_max))
-            },
-        );
-        let line = Line::new(PlotPoints::Owned(points)).name(field.as_str());
+            .filter(move |(source, _)| shown.is_none() || shown == Some(**source))
+            .map(|(source, stream)| (*source, stream))
+    }
+
+    fn graph(&mut self, ui: &mut Ui, id_source: &str, field: Graphable, to_show: usize) {
+        let overlaid = self.shown_source.is_none();
+        let mut lines = vec![];
+        let (mut min_val, mut max_val) = (field.min_value(), field.max_value());
+        for (source, stream) in self.shown_streams() {
+            let to_skip = stream.telemetry.len().saturating_sub(to_show);
+            let points: Vec<PlotPoint> = stream
+                .graph_values
+                .get(&field)
+                .into_iter()
+                .flatten()
+                .skip(to_skip)
+                .copied()
+                .collect();
+            (min_val, max_val) = points
+                .iter()
+                .fold((min_val, max_val), |(cmin, cmax), point| {
+                    let new_min = cmin.map_or(point.y, |y| y.min(point.y));
+                    let new_max = cmax.map_or(point.y, |y| y.max(point.y));
+                    (Some(new_min), Some(new_max))
+                });
+
+            // name the lines by source when they are overlaid so they can be told apart
+            let line = Line::new(PlotPoints::Owned(points));
+            lines.push(if overlaid {
+                line.name(source.as_str()).color(source.color())
+            } else {
+                line.name(field.as_str())
+            });
+        }
         let mut plot = Plot::new(id_source)
             .x_axis_formatter(|x, _range| {
                 let mt = MissionTime::from_seconds(x);
@@ -772,7 +1026,11 @@ impl GroundStationGui {
             plot = plot.include_y(min_y);
         }
 
-        plot.show(ui, |plot_ui| plot_ui.line(line));
+        plot.show(ui, |plot_ui| {
+            for line in lines {
+                plot_ui.line(line);
+            }
+        });
     }
 
     fn one_graph_view(&mut self, ui: &mut Ui) {
@@ -798,6 +1056,7 @@ impl GroundStationGui {
             ui.label("Show all: ");
             ui.add(egui::Checkbox::new(&mut self.one_graph_shows_all, ""));
 
+            self.telemetry_source_widget(ui);
             self.missed_packets_widget(ui);
         });
 
@@ -824,6 +1083,8 @@ impl GroundStationGui {
             ui.label("Enable Scrollbar: ");
             ui.add(egui::Checkbox::new(&mut self.all_graphs_show_scrollbar, ""));
 
+            self.telemetry_source_widget(ui);
+
             // show the missed packets
             self.missed_packets_widget(ui);
         });
@@ -860,17 +1121,34 @@ impl GroundStationGui {
         });
     }
 
-    fn data_table_view(&self, ui: &mut Ui) {
+    fn data_table_view(&mut self, ui: &mut Ui) {
         const HEADER_FONT_HEIGHT: f32 = 18.0;
         const MAIN_FONT_HEIGHT: f32 = 14.0;
         const COL_WIDTH_MULT: f32 = 12.0;
 
+        ui.horizontal(|ui| {
+            self.telemetry_source_widget(ui);
+        });
+
+        // interleave the sources by mission time when showing all of them
+        let mut rows: Vec<(TelemetrySource, &Telemetry)> = self
+            .shown_streams()
+            .flat_map(|(source, stream)| stream.telemetry.iter().map(move |t| (source, t)))
+            .collect();
+        rows.sort_by(|(_, a), (_, b)| {
+            a.mission_time
+                .as_seconds()
+                .total_cmp(&b.mission_time.as_seconds())
+        });
+
         ScrollArea::horizontal()
             .auto_shrink([false, false])
             .max_height(f32::INFINITY)
             .show(ui, |ui| {
                 let mut builder = TableBuilder::new(ui).striped(true).stick_to_bottom(true);
 
+                let source_width = "Source".len() as f32 * COL_WIDTH_MULT;
+                builder = builder.column(Column::initial(source_width).at_least(source_width));
                 for field in all::<TelemetryField>() {
                     let min_width = field.as_str().len() as f32 * COL_WIDTH_MULT;
                     builder = builder.column(
@@ -884,6 +1162,14 @@ impl GroundStationGui {
                     .auto_shrink([false, false])
                     .max_scroll_height(f32::INFINITY)
                     .header(HEADER_FONT_HEIGHT, |mut header| {
+                        header.col(|ui| {
+                            ui.label(LayoutJob::simple(
+                                "Source".to_string(),
+                                FontId::monospace(HEADER_FONT_HEIGHT),
+                                Color32::GRAY,
+                                f32::INFINITY,
+                            ));
+                        });
                         for field in all::<TelemetryField>() {
                             header.col(|ui| {
                                 ui.label(LayoutJob::simple(
@@ -896,24 +1182,29 @@ impl GroundStationGui {
                         }
                     })
                     .body(|body| {
-                        body.rows(
-                            MAIN_FONT_HEIGHT,
-                            self.telemetry.len(),
-                            |row_index, mut row| {
-                                let telem = &self.telemetry[row_index];
+                        body.rows(MAIN_FONT_HEIGHT, rows.len(), |row_index, mut row| {
+                            let (source, telem) = rows[row_index];
 
-                                for field in all::<TelemetryField>() {
-                                    row.col(|ui| {
-                                        ui.label(LayoutJob::simple(
-                                            telem.get_field(field),
-                                            FontId::new(MAIN_FONT_HEIGHT, FontFamily::Monospace),
-                                            Color32::GRAY,
-                                            f32::INFINITY,
-                                        ));
-                                    });
-                                }
-                            },
-                        );
+                            row.col(|ui| {
+                                ui.label(LayoutJob::simple(
+                                    source.to_string(),
+                                    FontId::new(MAIN_FONT_HEIGHT, FontFamily::Monospace),
+                                    source.color(),
+                                    f32::INFINITY,
+                                ));
+                            });
+
+                            for field in all::<TelemetryField>() {
+                                row.col(|ui| {
+                                    ui.label(LayoutJob::simple(
+                                        telem.get_field(field),
+                                        FontId::new(MAIN_FONT_HEIGHT, FontFamily::Monospace),
+                                        Color32::GRAY,
+                                        f32::INFINITY,
+                                    ));
+                                });
+                            }
+                        });
                     });
             });
     }
@@ -948,6 +1239,12 @@ impl GroundStationGui {
         const MAIN_FONT_HEIGHT: f32 = 16.0;
         const COL_WIDTH_MULT: f32 = 13.0;
 
+        self.command_queue_ui(ui);
+        ui.separator();
+
+        // the command to cancel, applied after the table has been drawn
+        let mut cancel = None;
+
         ScrollArea::horizontal()
             .auto_shrink([false, false])
             .max_height(f32::INFINITY)
@@ -958,6 +1255,8 @@ impl GroundStationGui {
                     .auto_shrink([false, false])
                     .max_scroll_height(f32::INFINITY)
                     .column(Column::initial(6.0 * COL_WIDTH_MULT).resizable(true))
+                    .column(Column::initial(8.0 * COL_WIDTH_MULT).resizable(true))
+                    .column(Column::initial(11.0 * COL_WIDTH_MULT).resizable(true))
                     .column(Column::remainder())
                     .header(HEADER_FONT_HEIGHT, |mut row| {
                         row.col(|ui| {
@@ -968,6 +1267,22 @@ impl GroundStationGui {
                                 f32::INFINITY,
                             ));
                         });
+                        row.col(|ui| {
+                            ui.label(LayoutJob::simple(
+                                "Executed".to_owned(),
+                                FontId::monospace(HEADER_FONT_HEIGHT),
+                                Color32::GRAY,
+                                f32::INFINITY,
+                            ));
+                        });
+                        row.col(|ui| {
+                            ui.label(LayoutJob::simple(
+                                "Destination".to_owned(),
+                                FontId::monospace(HEADER_FONT_HEIGHT),
+                                Color32::GRAY,
+                                f32::INFINITY,
+                            ));
+                        });
                         row.col(|ui| {
                             ui.label(LayoutJob::simple(
                                 "Command".to_owned(),
@@ -980,18 +1295,27 @@ impl GroundStationGui {
                     .body(|body| {
                         body.rows(
                             MAIN_FONT_HEIGHT,
-                            self.command_history.len(),
+                            self.commands.commands().len(),
                             |row_index, mut row| {
-                                let (_, (cmd, status)) = self
-                                    .command_history
-                                    .iter()
-                                    .nth(row_index)
-                                    .expect("Tried to access a command that didn't exist.");
-
-                                let (color, hover_text) = match status {
-                                    CommandStatus::Unsent => {
+                                let CommandRecord {
+                                    cmd,
+                                    dst,
+                                    status,
+                                    execution,
+                                    attempts,
+                                    time,
+                                    origin,
+                                    priority,
+                                } = &self.commands.commands()[row_index];
+
+                                let (color, mut hover_text) = match status {
+                                    CommandStatus::Unsent if attempts.is_empty() => {
                                         (Color32::GRAY, "Command not sent yet.".to_string())
                                     }
+                                    CommandStatus::Unsent => (
+                                        Color32::YELLOW,
+                                        "Command not delivered, waiting to retry.".to_string(),
+                                    ),
                                     CommandStatus::Sent { .. } => (
                                         Color32::YELLOW,
                                         "Command sent but not acknowledged.".to_string(),
@@ -1006,9 +1330,39 @@ impl GroundStationGui {
                                     CommandStatus::SentStatus { status } => {
                                         (Color32::RED, format!("Command sent, status = {status:?}"))
                                     }
+                                    CommandStatus::TimedOut => (
+                                        Color32::RED,
+                                        "Command sent but no status was received.".to_string(),
+                                    ),
+                                    CommandStatus::Cancelled => (
+                                        Color32::DARK_GRAY,
+                                        "Command cancelled before it was sent.".to_string(),
+                                    ),
+                                };
+                                if attempts.len() > 1 {
+                                    hover_text += &format!("\nSent {} times.", attempts.len());
+                                }
+                                hover_text += &format!(
+                                    "\nQueued at {} with {priority} priority.",
+                                    time.format("%H:%M:%S")
+                                );
+                                if let Some(origin) = origin {
+                                    hover_text += &format!("\nSent by {origin}.");
+                                }
+
+                                let (exec_color, exec_text) = match execution {
+                                    ExecutionStatus::Pending
+                                        if attempts.is_empty() || !status.maybe_delivered() =>
+                                    {
+                                        (Color32::GRAY, "-")
+                                    }
+                                    ExecutionStatus::Pending => (Color32::YELLOW, "Waiting"),
+                                    ExecutionStatus::Executed => (Color32::GREEN, "Yes"),
+                                    ExecutionStatus::NotEchoed => (Color32::RED, "Not echoed"),
                                 };
 
-                                // show the status in the first column and the command in the second
+                                // show the radio status, whether the CanSat ran the command,
+                                // then the destination, then the command
                                 row.col(|ui| {
                                     let r = (MAIN_FONT_HEIGHT - 4.0) / 2.0;
                                     ui.painter().circle_filled(ui.max_rect().center(), r, color);
@@ -1017,30 +1371,133 @@ impl GroundStationGui {
                                 .on_hover_text_at_pointer(hover_text);
 
                                 row.col(|ui| {
-                                    ui.horizontal(|ui| {
-                                        ui.label(LayoutJob::simple(
-                                            cmd.to_string(),
-                                            FontId::monospace(MAIN_FONT_HEIGHT),
-                                            Color32::GRAY,
+                                    ui.label(LayoutJob::simple(
+                                        exec_text.to_string(),
+                                        FontId::monospace(MAIN_FONT_HEIGHT),
+                                        exec_color,
+                                        f32::INFINITY,
+                                    ));
+                                });
+
+                                row.col(|ui| {
+                                    ui.label(LayoutJob::simple(
+                                        dst.to_string(),
+                                        FontId::monospace(MAIN_FONT_HEIGHT),
+                                        Color32::GRAY,
+                                        f32::INFINITY,
+                                    ));
+                                });
+
+                                row.col(|ui| {
+                                    ui.horizontal(|ui| {
+                                        ui.label(LayoutJob::simple(
+                                            cmd.to_string(),
+                                            FontId::monospace(MAIN_FONT_HEIGHT),
+                                            Color32::GRAY,
                                             f32::INFINITY,
                                         ));
                                     });
                                 })
                                 .1
                                 .context_menu(|ui| {
+                                    if *status == CommandStatus::Unsent
+                                        && ui.button("Cancel").clicked()
+                                    {
+                                        cancel = Some(row_index);
+                                        ui.close_menu();
+                                    }
+                                    // resent commands go through the command window so the
+                                    // safety policy is applied to them again
                                     if ui.button("Resend").clicked() {
-                                        if let Err(e) = self.cmd_sender.send(cmd.clone()) {
-                                            tracing::warn!("Failed to resend cmd={cmd:?} - {e:?}");
-                                            self.notifications.error("failed to resend command");
+                                        if self.command_center.load(*cmd, *dst) {
+                                            self.show_command_window = true;
+                                            self.notifications
+                                                .info("loaded command into the command window");
                                         } else {
-                                            self.notifications.info("resent command");
+                                            self.notifications
+                                                .error(format!("can't resend to {dst}"));
                                         }
+                                        ui.close_menu();
                                     }
                                 });
                             },
                         );
                     });
             });
+
+        if let Some(idx) = cancel {
+            if self.commands.cancel(idx) {
+                self.notifications.info("cancelled command");
+            }
+        }
+    }
+
+    fn command_queue_ui(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Queued:");
+            for priority in all::<Priority>() {
+                let queued = self.commands.queued(priority);
+                ui.separator();
+                ui.label(format!("{priority} {queued}"));
+                if ui
+                    .add_enabled(queued > 0, egui::Button::new("✖").small())
+                    .on_hover_text(format!("Cancel every queued {priority} command"))
+                    .clicked()
+                {
+                    let cancelled = self.commands.cancel_queued(priority);
+                    self.notifications
+                        .info(format!("Cancelled {cancelled} {priority} commands"));
+                }
+            }
+        });
+    }
+
+    fn link_stats_ui(&self, ui: &mut Ui) {
+        let stats = self.commands.link_stats();
+        let Some(rate) = stats.success_rate() else {
+            ui.label("No commands sent yet.");
+            return;
+        };
+
+        Grid::new("link_stats_grid").num_columns(2).show(ui, |ui| {
+            ui.label("Success rate: ");
+            let color = if rate >= 0.9 {
+                Color32::GREEN
+            } else if rate >= 0.5 {
+                Color32::YELLOW
+            } else {
+                Color32::RED
+            };
+            ui.colored_label(color, format!("{:.1}%", rate * 100.0));
+            ui.end_row();
+
+            ui.label("Delivered: ");
+            ui.label(format!("{} / {}", stats.successes(), stats.attempts()));
+            ui.end_row();
+
+            ui.label("Timed out: ");
+            ui.label(stats.timeouts().to_string());
+            ui.end_row();
+        });
+
+        let failures = stats.common_failures();
+        if failures.is_empty() {
+            return;
+        }
+
+        ui.label("Failures: ");
+        Grid::new("link_failures_grid")
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                for (status, history) in failures {
+                    ui.label(status.as_str())
+                        .on_hover_text(format!("Last failed command: {}", history.last_cmd));
+                    ui.label(history.count.to_string());
+                    ui.label(history.last_seen.format("%H:%M:%S").to_string());
+                    ui.end_row();
+                }
+            });
     }
 
     fn radio_window(&mut self, ui: &mut Ui) {
@@ -1075,6 +1532,17 @@ impl GroundStationGui {
             });
         });
 
+        // ports which aren't USB devices, e.g. the emulator's pty, can be entered manually
+        ui.horizontal(|ui| {
+            ui.label("Port path: ");
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                let resp = ui.text_edit_singleline(&mut self.radio_port);
+                if resp.changed() && self.radio.is_some() {
+                    self.close_radio();
+                }
+            });
+        });
+
         ui.horizontal(|ui| {
             ui.label("Baud rate: ");
             ui.vertical_centered(|ui| {
@@ -1104,6 +1572,31 @@ impl GroundStationGui {
             });
         });
 
+        ui.horizontal(|ui| {
+            ui.label("API mode: ");
+            ui.vertical_centered(|ui| {
+                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                    egui::ComboBox::from_id_source("radio_api_mode_combobox")
+                        .selected_text(self.radio_api_mode.as_str())
+                        .show_ui(ui, |ui| {
+                            for mode in all::<ApiMode>() {
+                                let value = ui.selectable_value(
+                                    &mut self.radio_api_mode,
+                                    mode,
+                                    mode.as_str(),
+                                );
+
+                                // the radio thread is started with the mode so reconnect
+                                if value.changed() {
+                                    tracing::info!("Set radio API mode to {mode}");
+                                    self.close_radio();
+                                }
+                            }
+                        });
+                });
+            });
+        });
+
         let width = 100.0;
         let height = 18.0;
         ui.horizontal(|ui| {
@@ -1126,6 +1619,60 @@ impl GroundStationGui {
             });
         });
 
+        ui.horizontal(|ui| {
+            ui.label("Ack timeout: ");
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                let mut secs = self.commands.timeout.as_secs_f64();
+                let resp = DragValue::new(&mut secs)
+                    .clamp_range(0.1..=30.0)
+                    .speed(0.1)
+                    .suffix("s")
+                    .ui(ui);
+                if resp.changed() {
+                    self.commands.timeout = Duration::from_secs_f64(secs);
+                }
+            });
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Echo timeout: ");
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                let mut secs = self.commands.echo_timeout.as_secs_f64();
+                let resp = DragValue::new(&mut secs)
+                    .clamp_range(1.0..=120.0)
+                    .speed(0.5)
+                    .suffix("s")
+                    .ui(ui);
+                if resp.changed() {
+                    self.commands.echo_timeout = Duration::from_secs_f64(secs);
+                }
+            });
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Rate limit: ");
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                let mut per_sec = 1.0 / self.commands.send_interval.as_secs_f64();
+                let resp = DragValue::new(&mut per_sec)
+                    .clamp_range(0.5..=50.0)
+                    .speed(0.1)
+                    .suffix(" cmd/s")
+                    .ui(ui);
+                if resp.changed() {
+                    self.commands.send_interval = Duration::from_secs_f64(1.0 / per_sec);
+                }
+            });
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Max retries: ");
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                DragValue::new(&mut self.commands.max_retries)
+                    .clamp_range(0..=10)
+                    .ui(ui);
+            });
+        });
+
         ui.with_layout(Layout::top_down(Align::Center), |ui| {
             // if we don't have a radio show an open button
             if self.radio.is_none() {
@@ -1146,6 +1693,26 @@ impl GroundStationGui {
                 ui.colored_label(Color32::RED, "Disconnected");
             }
         });
+
+        ui.separator();
+        let connected = self.radio.is_some();
+        let resp = ui.collapsing("Module configuration", |ui| {
+            self.radio_config.show(ui, connected)
+        });
+        for cmd in resp.body_returned.unwrap_or_default() {
+            self.send_at_command(cmd);
+        }
+
+        ui.collapsing("Link statistics", |ui| self.link_stats_ui(ui));
+
+        ui.collapsing("64-bit source addresses", |ui| self.long_addrs.show(ui));
+
+        let resp = ui.collapsing("Remote configuration", |ui| {
+            self.remote_config.show(ui, connected)
+        });
+        for cmd in resp.body_returned.unwrap_or_default() {
+            self.send_remote_at_command(cmd);
+        }
     }
 
     fn gps_window(&mut self, ui: &mut Ui) {
@@ -1206,12 +1773,346 @@ impl GroundStationGui {
 
         if let Err(e) = self.load_sim_file(path) {
             tracing::warn!("Failed to load sim file - {e:?}");
-            self.notifications.error("failed to load the sim file");
+            self.notifications
+                .error(format!("failed to load the sim file: {e}"));
         } else {
             self.notifications.info("loaded sim file");
         }
     }
 
+    /// Poll the mission script, sending any command it reaches
+    fn run_script(&mut self) {
+        let latest = latest_telemetry_received(&self.streams);
+        let telem = latest.map(|(telem, _)| telem);
+
+        let Some(ScriptCommand { cmd, line }) =
+            self.script_panel
+                .poll(self.commands.commands(), latest, &mut self.notifications)
+        else {
+            return;
+        };
+
+        // scripts can't be asked for confirmation, so refuse anything the safety policy blocks
+        // or which must be armed
+        let origin = format!("mission script line {line}");
+        let checked = self
+            .command_center
+            .check_script_command(cmd, telem, &origin);
+        if let Err(reason) = checked {
+            tracing::warn!("Mission script line {line} blocked: {cmd} - {reason}");
+            self.script_panel.abort(
+                &format!("line {line} blocked: {reason}"),
+                &mut self.notifications,
+            );
+            return;
+        }
+
+        let req = CommandRequest {
+            cmd,
+            dst: None,
+            origin: Some(origin),
+            priority: Priority::Background,
+        };
+        if let Err(e) = self.cmd_sender.send(req) {
+            tracing::warn!("Failed to send script command down channel - {e:?}");
+        }
+    }
+
+    /// Cancel any simulation commands which haven't been sent yet, so nothing from the stream
+    /// goes out after playback is stopped
+    fn cancel_sim_commands(&mut self) {
+        // commands still in the channel haven't been queued yet
+        self.recv_commands();
+        let cancelled = self.commands.cancel_queued(Priority::Simulation);
+        if cancelled > 0 {
+            tracing::info!("Cancelled {cancelled} queued simulation commands");
+        }
+    }
+
+    /// Queue commands from simulation playback
+    fn send_sim_commands(&mut self, cmds: Vec<Command>) {
+        for cmd in cmds {
+            // queue everything in the simulation stream together so it is sent in order
+            let req = CommandRequest::new(cmd, Priority::Simulation);
+            if let Err(e) = self.cmd_sender.send(req) {
+                tracing::error!("Failed to send simulation command down channel - {e:?}");
+            }
+        }
+    }
+
+    fn run_simulation(&mut self) {
+        let Some(sim) = self.simulation.as_mut() else {
+            return;
+        };
+
+        let before = sim.state();
+        let cmds = sim.poll(Instant::now());
+        if before == SimState::Streaming && sim.state() == SimState::Finished {
+            self.notifications
+                .success("simulation mode playback finished");
+        }
+        self.send_sim_commands(cmds);
+
+        let streaming =
+            self.simulation.as_ref().map(|sim| sim.state()) == Some(SimState::Streaming);
+        if let (true, Some(verifier)) = (streaming, self.sim_verifier.as_mut()) {
+            let found = verifier.check_lag(Instant::now());
+            self.notify_discrepancies(found);
+        }
+    }
+
+    /// Check telemetry received while streaming against the simulated pressure values
+    fn verify_simulation(&mut self, telem: &Telemetry) {
+        let streaming =
+            self.simulation.as_ref().map(|sim| sim.state()) == Some(SimState::Streaming);
+        let Some(verifier) = self.sim_verifier.as_mut().filter(|_| streaming) else {
+            return;
+        };
+
+        let found = verifier.recv(telem, Instant::now());
+        self.notify_discrepancies(found);
+    }
+
+    fn notify_discrepancies(&mut self, found: Vec<Discrepancy>) {
+        for discrepancy in found {
+            self.notifications.warning(discrepancy.to_string());
+        }
+    }
+
+    /// Plot what the CanSat reported against the simulated values it was sent
+    fn sim_verification_ui(&mut self, ui: &mut Ui) {
+        let Some(verifier) = self.sim_verifier.as_mut() else {
+            ui.label("Start sending to verify the CanSat uses the simulated values.");
+            return;
+        };
+
+        ui.horizontal(|ui| {
+            ui.label("Max lag: ");
+            let mut secs = verifier.max_lag.as_secs_f64();
+            let resp = DragValue::new(&mut secs)
+                .clamp_range(0.5..=30.0)
+                .speed(0.1)
+                .suffix("s")
+                .ui(ui);
+            if resp.changed() {
+                verifier.max_lag = Duration::from_secs_f64(secs);
+            }
+
+            ui.label("Max pressure error: ");
+            DragValue::new(&mut verifier.max_pressure_error)
+                .clamp_range(0.0..=5000.0)
+                .suffix(" Pa")
+                .ui(ui);
+        });
+
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut self.sim_plot_altitude, false, "Pressure");
+            ui.selectable_value(&mut self.sim_plot_altitude, true, "Altitude");
+        });
+
+        let samples = verifier.samples();
+        let (expected, reported): (Vec<_>, Vec<_>) = if self.sim_plot_altitude {
+            samples
+                .iter()
+                .map(|s| ([s.time, s.expected_altitude()], [s.time, s.altitude]))
+                .unzip()
+        } else {
+            samples
+                .iter()
+                .map(|s| ([s.time, s.expected as f64], [s.time, s.reported]))
+                .unzip()
+        };
+        Plot::new("sim_verification_plot")
+            .view_aspect(2.0)
+            .legend(Default::default())
+            .show(ui, |ui| {
+                ui.line(Line::new(PlotPoints::from(expected)).name("Expected"));
+                ui.line(Line::new(PlotPoints::from(reported)).name("Reported"));
+            });
+
+        Grid::new("sim_verification_grid")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Echoed: ");
+                // repeated values can't be seen in CMD_ECHO so they aren't counted
+                let echoed = verifier.sent().iter().filter(|s| s.lag.is_some()).count();
+                let observable = verifier.sent().iter().filter(|s| !s.repeats_echo).count();
+                ui.label(format!("{echoed} / {observable}"));
+                ui.end_row();
+
+                ui.label("Mean lag: ");
+                match verifier.mean_lag() {
+                    Some(lag) => ui.label(format!("{:.2}s", lag.as_secs_f64())),
+                    None => ui.label("-"),
+                };
+                ui.end_row();
+
+                let in_sim_mode = samples.last().map(|s| s.mode == Mode::Simulation);
+                ui.label("Mode: ");
+                match in_sim_mode {
+                    Some(true) => ui.colored_label(Color32::GREEN, "Simulation"),
+                    Some(false) => ui.colored_label(Color32::RED, "Flight"),
+                    None => ui.label("-"),
+                };
+                ui.end_row();
+            });
+
+        if verifier.log().is_empty() {
+            ui.colored_label(Color32::GREEN, "No discrepancies.");
+        } else {
+            ScrollArea::vertical()
+                .id_source("sim_discrepancies")
+                .max_height(100.0)
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    for (time, discrepancy) in verifier.log() {
+                        ui.colored_label(Color32::YELLOW, format!("{time:.1}s - {discrepancy}"));
+                    }
+                });
+        }
+    }
+
+    /// Cancel simulation playback if it is running
+    fn stop_simulation(&mut self) {
+        if let Some(sim) = self.simulation.as_mut() {
+            let cmds = sim.cancel();
+            self.cancel_sim_commands();
+            self.send_sim_commands(cmds);
+        }
+    }
+
+    /// Preview the loaded profile before it is sent
+    fn sim_profile_ui(&self, ui: &mut Ui) {
+        let (Some(profile), Some(sim)) = (&self.sim_profile, &self.simulation) else {
+            return;
+        };
+
+        Grid::new("sim_profile_grid").num_columns(2).show(ui, |ui| {
+            ui.label("Values: ");
+            ui.label(profile.pressures.len().to_string());
+            ui.end_row();
+
+            let secs = profile.duration(sim.period).as_secs();
+            ui.label("Duration: ");
+            ui.label(format!("{}:{:02}", secs / 60, secs % 60));
+            ui.end_row();
+
+            if let (Some(min), Some(max)) = (profile.min_pressure(), profile.max_pressure()) {
+                ui.label("Pressure: ");
+                ui.label(format!("{min} - {max} Pa"));
+                ui.end_row();
+            }
+
+            if let Some((idx, apogee)) = profile.apogee() {
+                ui.label("Apogee: ");
+                ui.label(format!("{apogee:.1} m at value {idx}"));
+                ui.end_row();
+            }
+        });
+
+        if !profile.errors.is_empty() {
+            let title = format!("⚠ {} lines couldn't be parsed", profile.errors.len());
+            egui::CollapsingHeader::new(RichText::new(title).color(Color32::YELLOW))
+                .id_source("sim_profile_errors")
+                .show(ui, |ui| {
+                    ScrollArea::vertical().max_height(150.0).show(ui, |ui| {
+                        for error in &profile.errors {
+                            ui.label(error.to_string());
+                        }
+                    });
+                });
+        }
+    }
+
+    fn sim_controls_ui(&mut self, ui: &mut Ui) {
+        let Some(sim) = self.simulation.as_mut() else {
+            return;
+        };
+
+        let now = Instant::now();
+        let total = sim.pressures().len();
+        let mut cmds = vec![];
+        let mut stopped = false;
+
+        ui.horizontal(|ui| {
+            ui.label("Period: ");
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                let mut secs = sim.period.as_secs_f64();
+                let resp = DragValue::new(&mut secs)
+                    .clamp_range(0.1..=10.0)
+                    .speed(0.05)
+                    .suffix("s")
+                    .ui(ui);
+                if resp.changed() {
+                    sim.period = Duration::from_secs_f64(secs);
+                }
+            });
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Position: ");
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                let mut position = sim.position();
+                let resp = ui
+                    .add(egui::Slider::new(&mut position, 0..=total).suffix(format!(" / {total}")));
+                if resp.changed() {
+                    sim.seek(position);
+                }
+            });
+        });
+
+        ui.horizontal(|ui| {
+            ui.with_layout(Layout::left_to_right(Align::TOP), |ui| {
+                match sim.state() {
+                    SimState::Enabling | SimState::Streaming => {
+                        if ui.button("pause").clicked() {
+                            self.notifications.info("pausing simulation mode playback");
+                            cmds = sim.pause();
+                            stopped = true;
+                        }
+                    }
+                    SimState::Paused => {
+                        if ui.button("play").clicked() {
+                            self.notifications.info("started simulation mode playback");
+                            cmds = sim.resume(now);
+                        }
+                    }
+                    SimState::Idle | SimState::Finished | SimState::Cancelled => {
+                        if ui.button("Start sending").clicked() {
+                            self.notifications.info("started simulation mode");
+                            // keep the thresholds from the last run
+                            let mut verifier = SimVerifier::new(now);
+                            if let Some(old) = &self.sim_verifier {
+                                verifier.max_lag = old.max_lag;
+                                verifier.max_pressure_error = old.max_pressure_error;
+                            }
+                            self.sim_verifier = Some(verifier);
+                            cmds = sim.start(now);
+                        }
+                    }
+                }
+                ui.label(format!("{:?}", sim.state()));
+            });
+
+            ui.with_layout(Layout::right_to_left(Align::TOP), |ui| {
+                if ui
+                    .add_enabled(sim.is_active(), egui::Button::new("cancel"))
+                    .clicked()
+                {
+                    self.notifications
+                        .info("cancelling simulation mode playback");
+                    cmds = sim.cancel();
+                    stopped = true;
+                }
+            });
+        });
+
+        if stopped {
+            self.cancel_sim_commands();
+        }
+        self.send_sim_commands(cmds);
+    }
+
     fn sim_window(&mut self, ui: &mut Ui) {
         ui.set_min_width(300.0);
 
@@ -1241,10 +2142,17 @@ impl GroundStationGui {
             });
         });
 
+        ui.collapsing("Profile builder", |ui| {
+            if let Some(profile) = self.profile_builder.show(ui, &mut self.notifications) {
+                self.load_sim_profile(profile);
+                self.notifications.info("loaded built profile");
+            }
+        });
+
         // if we have pressure values display a little graph of them
-        if let Some(simps) = &self.simp_graph_values {
+        if let (Some(simps), Some(sim)) = (&self.simp_graph_values, &self.simulation) {
             Plot::new("simp_plot").view_aspect(1.5).show(ui, |ui| {
-                let sent = SENT_SIMPS.load(ORDER);
+                let sent = sim.position();
                 let sent_simps = simps[..sent].to_vec();
                 let unsent_simps = simps[sent..].to_vec();
                 let sent_line = Line::new(PlotPoints::Owned(sent_simps)).color(Color32::GREEN);
@@ -1255,171 +2163,57 @@ impl GroundStationGui {
 
             ui.separator();
 
-            // if the thread has been started add these buttons instead
-            if SEND_THREAD_STARTED.load(ORDER) {
-                ui.horizontal(|ui| {
-                    // pause / play button
-                    ui.with_layout(Layout::left_to_right(Align::TOP), |ui| {
-                        if SEND_THREAD_PAUSED.load(ORDER) {
-                            if ui.button("play").clicked() {
-                                tracing::info!("Playing simulation mode playback");
-                                self.notifications.info("started simulation mode playback");
-                                SEND_THREAD_PAUSED.store(false, ORDER);
-                            }
-                        } else if ui.button("pause").clicked() {
-                            tracing::info!("Pausing simulation mode playback");
-                            self.notifications.info("pausing simulation mode playback");
-                            SEND_THREAD_PAUSED.store(true, ORDER);
-                        }
-                    });
-
-                    // cancel button
-                    ui.with_layout(Layout::right_to_left(Align::TOP), |ui| {
-                        if ui.button("cancel").clicked() {
-                            tracing::info!("Cancelling simulation mode sender thread");
-                            self.notifications
-                                .info("cancelling simulation mode playback");
-                            SEND_THREAD_CANCEL.store(true, ORDER);
-                        }
-                    });
-                });
-            } else {
-                let button = ui
-                    .with_layout(Layout::top_down(Align::Center), |ui| {
-                        ui.button("Start sending")
-                    })
-                    .inner;
-
-                if button.clicked() {
-                    // set the thread as started
-                    SEND_THREAD_STARTED.store(true, ORDER);
-                    tracing::info!("Starting simulation mode sender thread");
-                    self.notifications.info("started simulation mode");
-
-                    // make a copy of the simp data to send to the thread
-                    let Some(simp_data) = self.simp_values.clone() else {
-                        tracing::error!("Encountered invalid state - simp_graph_values is Some, but simp_values is None, resetting both to None.");
-                        self.simp_graph_values = None;
-                        return;
-                    };
-
-                    let cmd_sender = self.cmd_sender.clone();
-                    let thread_res = thread::Builder::new()
-                        .name(String::from("simp"))
-                        .spawn(move || Self::simp_thread(cmd_sender, simp_data));
+            self.sim_profile_ui(ui);
+            ui.separator();
 
-                    if let Err(e) = thread_res {
-                        tracing::error!("Failed to start SIMP command sender thread - {e:?}");
-                        self.notifications
-                            .error("failed to start thread to send commands");
-                    }
-                }
-            }
+            self.sim_controls_ui(ui);
+            ui.collapsing("Verification", |ui| self.sim_verification_ui(ui));
         }
     }
 
-    fn simp_thread(cmd_sender: Sender<String>, simp_data: Vec<u32>) {
-        tracing::info!("simp thread started");
-
-        fn send_start_packets(sender: &Sender<String>) {
-            // send SIM,ENABLE then SIM,ACTIVATE
-            sender
-                .send(String::from("CMD,1047,SIM,ENABLE"))
-                .map_err(|e| {
-                    tracing::error!("Failed to send SIM,ENABLE.");
-                    e
-                })
-                .expect("Failed to send SIM,ENABLE.");
-            sender
-                .send(String::from("CMD,1047,SIM,ACTIVATE"))
-                .map_err(|e| {
-                    tracing::error!("Failed to send SIM,ACTIVATE.");
-                    e
-                })
-                .expect("Failed to send SIM,ACTIVATE.");
-        }
-
-        fn stop_sending(sender: &Sender<String>) {
-            // send SIM,DISABLE
-            sender
-                .send(String::from("CMD,1047,SIM,DISABLE"))
-                .map_err(|e| {
-                    tracing::error!("Failed to send SIM,DISABLE.");
-                    e
-                })
-                .expect("Failed to send SIM,ENABLE.");
-        }
-
-        // start sending
-        send_start_packets(&cmd_sender);
-
-        // iterate through the commands, sleeping for one second before sending the next
-        let mut simp_iter = simp_data.into_iter();
-        let mut paused = false;
-        loop {
-            // if SEND_THREAD_CANCEL is true, replace with false and cancel this thread
-            if let Ok(true) = SEND_THREAD_CANCEL.compare_exchange(true, false, ORDER, ORDER) {
-                tracing::info!("Cancelling simulation mode thread");
-                stop_sending(&cmd_sender);
-                SEND_THREAD_PAUSED.store(false, ORDER);
-                SEND_THREAD_STARTED.store(false, ORDER);
-                SENT_SIMPS.store(0, ORDER);
-                return;
-            }
-
-            // wait until we are unpaused to send the command
-            let thread_paused = SEND_THREAD_PAUSED.load(ORDER);
-            if thread_paused {
-                // newly paused, send SIM,DISABLE
-                if thread_paused && !paused {
-                    stop_sending(&cmd_sender);
-                    paused = true;
-                }
-                thread::sleep(Duration::from_millis(100));
-                continue;
-            } else if paused {
-                // just paused, start sending again
-                send_start_packets(&cmd_sender);
-                paused = false;
-            }
+    fn telemetry_source_widget(&mut self, ui: &mut Ui) {
+        let selected = self.shown_source.map_or("All", |source| source.as_str());
 
-            if let Some(simp) = simp_iter.next() {
-                // send it!
-                let cmd = format!("CMD,{TEAM_ID},SIMP,{simp}");
-                if let Err(e) = cmd_sender.send(cmd) {
-                    tracing::error!("Failed to send command over cmd_sender - {e:?}");
-                } else {
-                    SENT_SIMPS.fetch_add(1, ORDER);
-                    tracing::debug!("SENT_SIMPS={SENT_SIMPS:?}");
+        ui.label("Source: ");
+        egui::ComboBox::from_id_source(ui.id().with("telemetry_source"))
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut self.shown_source, None, "All");
+                for source in all::<TelemetrySource>() {
+                    ui.selectable_value(&mut self.shown_source, Some(source), source.as_str());
                 }
-            } else {
-                // we have reached the end of the iterator, cancel the thread
-                return;
-            }
-
-            // sleep for a second
-            thread::sleep(Duration::from_secs(1));
-        }
+            });
     }
 
     fn missed_packets_widget(&self, ui: &mut Ui) {
-        let color = match self.missed_packets {
+        let missed_packets: u32 = self
+            .shown_streams()
+            .map(|(_, stream)| stream.missed_packets)
+            .sum();
+        let color = match missed_packets {
             0 => Color32::GREEN,
             1..=10 => Color32::YELLOW,
             11.. => Color32::RED,
         };
 
         ui.with_layout(Layout::right_to_left(Align::TOP), |ui| {
-            ui.colored_label(color, self.missed_packets.to_string());
+            ui.colored_label(color, missed_packets.to_string());
             ui.label("Missed Packets: ");
         });
     }
 
     fn radio_status_ui(&self, ui: &mut Ui) {
-        let (color, hover_text) = if self.radio.is_some() {
-            (Color32::GREEN, "Radio is connected.")
-        } else {
-            (Color32::RED, "Radio is disconnected.")
+        let (color, hover_text) = match (&self.radio, self.last_modem_status) {
+            (None, _) => (Color32::RED, "Radio is disconnected.".to_string()),
+            (Some(_), None) => (Color32::GREEN, "Radio is connected.".to_string()),
+            (Some(_), Some(status)) if status.is_fault() => (
+                Color32::YELLOW,
+                format!("Radio is connected, last status: {status}."),
+            ),
+            (Some(_), Some(status)) => (
+                Color32::GREEN,
+                format!("Radio is connected, last status: {status}."),
+            ),
         };
 
         let r = 7.0;
@@ -1427,6 +2221,9 @@ impl GroundStationGui {
         let (rect, resp) = ui.allocate_at_least(area, Sense::hover());
         ui.painter().circle_filled(rect.center(), r, color);
         resp.on_hover_text_at_pointer(hover_text);
+        if let Some(status) = self.last_modem_status {
+            ui.label(status.to_string());
+        }
         if let Some(rssi) = self.last_packet_rssi {
             ui.label(format!("RSSI: {rssi} dBm"));
         } else {
@@ -1438,12 +2235,31 @@ impl GroundStationGui {
 // TODO: Add a 3d graph showing the GPS position data in real time
 // TODO: Add smoothing to the graph?
 impl eframe::App for GroundStationGui {
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        // don't leave the CanSat in simulation mode waiting for pressure values
+        let Some(sim) = self.simulation.as_mut() else {
+            return;
+        };
+        let cmds = sim.cancel();
+        self.cancel_sim_commands();
+        for cmd in cmds {
+            self.send_command_now(cmd);
+        }
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // attempt to receive any telemetry thats availble from the radio
         self.recv_telem();
 
         // handle any command we have left to send
         self.handle_commands();
+        self.send_at_commands();
+
+        // run the mission script if there is one
+        self.run_script();
+
+        // send any simulated pressure values which are due
+        self.run_simulation();
 
         // handle receiving a sim file if a file picker is open
         self.recv_sim_file();
@@ -1475,6 +2291,7 @@ impl eframe::App for GroundStationGui {
                 ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
                     ui.horizontal(|ui| {
                         // rightmost
+                        ui.checkbox(&mut self.show_script_window, "📜 Script");
                         ui.checkbox(&mut self.show_sim_window, "🔁 Simulation");
                         ui.checkbox(&mut self.show_command_window, "🖧 Commands");
                         ui.checkbox(&mut self.show_radio_window, "📻 Radio");
@@ -1505,7 +2322,12 @@ impl eframe::App for GroundStationGui {
             let resp = egui::Window::new("commands")
                 .open(&mut open)
                 .show(ctx, |ui| {
-                    self.command_center.show(ui, &mut self.notifications)
+                    let telem = latest_telemetry(&self.streams);
+                    let cmd = self.command_center.show(ui, &mut self.notifications, telem);
+                    ui.collapsing("Safety overrides", |ui| {
+                        self.command_center.overrides_ui(ui)
+                    });
+                    cmd
                 });
 
             // get the inner response and flatten the nested Options
@@ -1547,6 +2369,16 @@ impl eframe::App for GroundStationGui {
             self.show_sim_window = open;
         }
 
+        if self.show_script_window {
+            open = true;
+            egui::Window::new("mission script")
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    self.script_panel.show(ui, &mut self.notifications)
+                });
+            self.show_script_window = open;
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
             // match on the current view to decide what to draw
             match self.main_view {
@@ -1563,20 +2395,40 @@ impl eframe::App for GroundStationGui {
     }
 }
 
-// the different states a command can have
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
-pub enum CommandStatus {
-    // used if the radio isn't connected
-    Unsent,
-    // sent but no status
-    Sent { frame_id: u8 },
-    // sent and status received
-    SentStatus { status: DeliveryStatus },
+/// An AT command waiting to be written to the radio
+enum QueuedAt {
+    Local(AtCommand),
+    Remote(RemoteAtCommand),
+    /// Reopen the radio once the AT commands before this have applied a new API mode or baud rate
+    Follow {
+        /// The frame ID of the AC command applying the settings
+        apply_id: u8,
+        api_mode: Option<ApiMode>,
+        baud: Option<u32>,
+        /// When the AC command was written
+        sent_at: Option<Instant>,
+        /// Has the radio responded to the AC command?
+        applied: bool,
+    },
+}
+
+impl fmt::Display for QueuedAt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueuedAt::Local(cmd) => write!(f, "{cmd}"),
+            QueuedAt::Remote(cmd) => write!(f, "{cmd}"),
+            QueuedAt::Follow { api_mode, baud, .. } => {
+                write!(f, "Follow {{ api_mode: {api_mode:?}, baud: {baud:?} }}")
+            }
+        }
+    }
 }
 
 // the packets used to store in the packet log
 pub enum Packet {
-    Sent(TxRequest),
+    Sent(TxFrame),
+    SentAt(AtCommand),
+    SentRemoteAt(RemoteAtCommand),
     Received(ReceivedPacket),
 }
 
@@ -1595,6 +2447,22 @@ impl Packet {
                     f32::INFINITY,
                 ));
             }
+            Packet::SentAt(cmd) => {
+                ui.label(LayoutJob::simple(
+                    format!("{cmd}"),
+                    FontId::monospace(20.0),
+                    SENT_COLOR,
+                    f32::INFINITY,
+                ));
+            }
+            Packet::SentRemoteAt(cmd) => {
+                ui.label(LayoutJob::simple(
+                    format!("{cmd}"),
+                    FontId::monospace(20.0),
+                    SENT_COLOR,
+                    f32::INFINITY,
+                ));
+            }
             Packet::Received(packet) => {
                 ui.label(LayoutJob::simple(
                     format!("{packet}"),
@@ -1606,3 +2474,151 @@ impl Packet {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::SimMode;
+    use crate::constants::{CONTAINER_ADDR, PROBE_ADDR};
+    use crate::synth::TelemetrySynth;
+    use crate::xbee::RxPacket;
+
+    /// An RX frame carrying some telemetry, as the radio thread would receive it
+    fn rx_telem(src_addr: u16, telem: &Telemetry) -> ReceivedPacket {
+        let packet: XbeePacket = RxPacket {
+            src_addr,
+            rssi: 40,
+            options: 0,
+            data: telem.to_string().into_bytes(),
+        }
+        .try_into()
+        .unwrap();
+        packet.into()
+    }
+
+    #[test]
+    fn test_interleaved_sources() {
+        let (tx, rx) = channel();
+        let mut gui = GroundStationGui::new_with_receiver(rx);
+        gui.save_telemetry = false;
+
+        // the container and probe count their packets separately
+        let mut container = TelemetrySynth::new(Some(1));
+        let mut probe = TelemetrySynth::new(Some(2));
+        for _ in 0..3 {
+            probe.next_packet();
+        }
+        for _ in 0..5 {
+            tx.send(rx_telem(CONTAINER_ADDR, &container.next_packet().telem))
+                .unwrap();
+            tx.send(rx_telem(PROBE_ADDR, &probe.next_packet().telem))
+                .unwrap();
+        }
+        gui.recv_telem();
+
+        for source in [TelemetrySource::Container, TelemetrySource::Probe] {
+            let stream = &gui.streams[&source];
+            assert_eq!(stream.telemetry.len(), 5, "{source}");
+            assert_eq!(stream.missed_packets, 0, "{source}");
+        }
+        assert!(!gui.streams.contains_key(&TelemetrySource::Other));
+    }
+
+    #[test]
+    fn test_recovered_telemetry_keeps_source() {
+        let (tx, rx) = channel();
+        let mut gui = GroundStationGui::new_with_receiver(rx);
+        gui.save_telemetry = false;
+
+        // invalid UTF-8 before the telemetry stops the frame parsing as telemetry
+        let line = TelemetrySynth::new(Some(1)).next_packet().telem.to_string();
+        let mut data = vec![0xFF, 0xFE];
+        data.extend(line.as_bytes());
+        let packet: XbeePacket = RxPacket {
+            src_addr: PROBE_ADDR,
+            rssi: 40,
+            options: 0,
+            data,
+        }
+        .try_into()
+        .unwrap();
+        let received = ReceivedPacket::from(packet);
+        assert!(matches!(received, ReceivedPacket::Received { .. }));
+
+        tx.send(received).unwrap();
+        gui.recv_telem();
+
+        let telem: Telemetry = line.parse().unwrap();
+        assert_eq!(gui.streams[&TelemetrySource::Probe].telemetry, [telem]);
+        assert!(!gui.streams.contains_key(&TelemetrySource::Other));
+    }
+
+    #[test]
+    fn test_follow_waits_for_apply_response() {
+        let mut gui = GroundStationGui::default();
+        let settings = |apply_id| ApplySettings {
+            apply: AtCommand::apply_changes(apply_id),
+            write: AtCommand::write(apply_id + 1),
+            api_mode: Some(ApiMode::Unescaped),
+            baud: None,
+        };
+        let response = |frame_id, status| AtCommandResponse {
+            frame_id,
+            command: *b"AC",
+            status,
+            data: vec![],
+        };
+        let follow_applied = |gui: &GroundStationGui| {
+            gui.at_queue.iter().find_map(|queued| match queued {
+                QueuedAt::Follow { applied, .. } => Some(*applied),
+                _ => None,
+            })
+        };
+
+        // only the response to its own AC lets the follow go ahead
+        gui.apply_radio_settings(settings(1));
+        assert_eq!(follow_applied(&gui), Some(false));
+        gui.recv_apply_response(&response(3, AtCommandStatus::Ok));
+        assert_eq!(follow_applied(&gui), Some(false));
+        gui.recv_apply_response(&response(1, AtCommandStatus::Ok));
+        assert_eq!(follow_applied(&gui), Some(true));
+
+        // settings which failed to apply aren't followed
+        gui.at_queue.clear();
+        gui.apply_radio_settings(settings(5));
+        gui.recv_apply_response(&response(5, AtCommandStatus::Failure));
+        assert_eq!(follow_applied(&gui), None);
+        assert_eq!(gui.at_queue.len(), 2);
+    }
+
+    #[test]
+    fn test_no_simp_sent_after_cancel() {
+        let mut gui = GroundStationGui::default();
+        gui.load_sim_profile(SimProfile::parse("101325\n101300\n101200\n"));
+        let sim = gui.simulation.as_mut().unwrap();
+        let now = Instant::now();
+        let mut cmds = sim.start(now);
+        cmds.extend(sim.poll(now + sim.period));
+        gui.send_sim_commands(cmds);
+        gui.handle_commands();
+
+        // a backlog of pressure values built up while the radio was disconnected
+        let sim = gui.simulation.as_mut().unwrap();
+        let period = sim.period;
+        let cmds: Vec<Command> = (2..4).flat_map(|i| sim.poll(now + period * i)).collect();
+        assert_eq!(cmds.len(), 2);
+        gui.send_sim_commands(cmds);
+        gui.stop_simulation();
+        gui.handle_commands();
+
+        // only SIM,DISABLE is left to send
+        let unsent: Vec<Command> = gui
+            .commands
+            .commands()
+            .iter()
+            .filter(|record| record.status == CommandStatus::Unsent)
+            .map(|record| record.cmd)
+            .collect();
+        assert_eq!(unsent, [Command::SimulationMode(SimMode::Disable)]);
+    }
+}
@@ -0,0 +1,165 @@
+use crate::simulation::{pressure_to_altitude, FlightProfile, SimProfile};
+use eframe::emath::Align;
+use egui::{
+    plot::{Line, Plot, PlotPoints},
+    Color32, DragValue, Grid, Layout, Ui, Widget,
+};
+use egui_notify::Toasts;
+
+/// A profile built from a set of flight parameters
+struct BuiltProfile {
+    /// The parameters it was built from
+    params: FlightProfile,
+    /// The profile, or why it couldn't be built
+    profile: Result<SimProfile, String>,
+    /// The altitude at each second, for the preview
+    points: Vec<[f64; 2]>,
+}
+
+/// Holds all the state related to building SIMP profiles from flight parameters
+pub struct ProfileBuilderPanel {
+    profile: FlightProfile,
+
+    /// Only rebuilt when the parameters change, not every frame
+    built: Option<BuiltProfile>,
+
+    /// The file to save the built profile to
+    path: String,
+}
+
+impl Default for ProfileBuilderPanel {
+    fn default() -> Self {
+        Self {
+            profile: Default::default(),
+            built: None,
+            path: "simp_profile.txt".to_string(),
+        }
+    }
+}
+
+/// A labelled row with a drag value for one of the flight parameters
+fn param_row(ui: &mut Ui, label: &str, value: &mut f64, suffix: &str, max: f64) {
+    ui.label(label);
+    DragValue::new(value)
+        .clamp_range(0.0..=max)
+        .speed(max / 1000.0)
+        .suffix(suffix)
+        .ui(ui);
+    ui.end_row();
+}
+
+impl ProfileBuilderPanel {
+    /// Show the builder, returns a profile if the user wants to send it
+    pub fn show(&mut self, ui: &mut Ui, notif: &mut Toasts) -> Option<SimProfile> {
+        let profile = &mut self.profile;
+        Grid::new("profile_builder_grid")
+            .num_columns(2)
+            .show(ui, |ui| {
+                param_row(
+                    ui,
+                    "Launch altitude: ",
+                    &mut profile.launch_altitude,
+                    " m",
+                    5000.0,
+                );
+                param_row(ui, "Ascent rate: ", &mut profile.ascent_rate, " m/s", 500.0);
+                param_row(ui, "Apogee: ", &mut profile.apogee, " m", 5000.0);
+                param_row(
+                    ui,
+                    "Descent rate: ",
+                    &mut profile.descent_rate,
+                    " m/s",
+                    200.0,
+                );
+                param_row(
+                    ui,
+                    "Parachute altitude: ",
+                    &mut profile.parachute_altitude,
+                    " m",
+                    5000.0,
+                );
+                param_row(
+                    ui,
+                    "Parachute descent rate: ",
+                    &mut profile.parachute_descent_rate,
+                    " m/s",
+                    200.0,
+                );
+                param_row(
+                    ui,
+                    "Landing dwell: ",
+                    &mut profile.landing_dwell,
+                    " s",
+                    600.0,
+                );
+                param_row(ui, "Noise: ", &mut profile.noise, " m", 100.0);
+
+                ui.label("Seed: ");
+                DragValue::new(&mut profile.seed).ui(ui);
+                ui.end_row();
+            });
+
+        // rebuilt when the parameters change so the preview follows them
+        if !matches!(&self.built, Some(built) if built.params == self.profile) {
+            let profile = self.profile.build().map_err(|e| e.to_string());
+            let points = profile.as_ref().map_or(vec![], |profile| {
+                profile
+                    .pressures
+                    .iter()
+                    .enumerate()
+                    .map(|(t, pressure)| [t as f64, pressure_to_altitude(*pressure)])
+                    .collect()
+            });
+            self.built = Some(BuiltProfile {
+                params: self.profile.clone(),
+                profile,
+                points,
+            });
+        }
+        let BuiltProfile {
+            profile, points, ..
+        } = self.built.as_ref()?;
+        let built = match profile {
+            Ok(built) => built,
+            Err(e) => {
+                ui.colored_label(Color32::RED, e);
+                return None;
+            }
+        };
+
+        let points = PlotPoints::new(points.clone());
+        Plot::new("profile_builder_plot")
+            .view_aspect(2.0)
+            .show(ui, |ui| ui.line(Line::new(points)));
+        ui.label(format!(
+            "{} values over {:.0}s",
+            built.pressures.len(),
+            self.profile.duration()
+        ));
+
+        ui.horizontal(|ui| {
+            ui.label("File: ");
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                if ui.button("Save").clicked() {
+                    match std::fs::write(&self.path, built.to_official_format()) {
+                        Ok(()) => {
+                            notif.info(format!("Saved profile to {}", self.path));
+                        }
+                        Err(e) => {
+                            tracing::warn!("Failed to save profile to {:?} - {e:?}", self.path);
+                            notif.error(format!("Failed to save {}: {e}", self.path));
+                        }
+                    }
+                }
+                ui.text_edit_singleline(&mut self.path);
+            });
+        });
+
+        ui.with_layout(Layout::top_down(Align::Center), |ui| {
+            ui.button("Use for simulation")
+                .clicked()
+                .then(|| built.clone())
+        })
+        .inner
+    }
+}
@@ -0,0 +1,352 @@
+use crate::as_str::AsStr;
+use crate::constants::BAUD_RATES;
+use crate::xbee::{
+    bd_baud_rate, ApiMode, AtCommand, AtCommandResponse, AtCommandStatus, AtParameter, AtValue,
+    BD_LITERAL_MIN,
+};
+use egui::{Button, DragValue, Grid, Ui};
+use egui_notify::Toasts;
+use enum_iterator::all;
+use std::collections::{BTreeMap, HashMap};
+
+/// Settings the radio has accepted, which are applied by sending `apply` then saved by sending
+/// `write`. Any change to the API mode or baud rate takes effect with `apply`, so the connection
+/// must follow it before `write` is sent.
+pub struct ApplySettings {
+    /// AC - apply the queued values
+    pub apply: AtCommand,
+    /// WR - save the values so they survive a power cycle
+    pub write: AtCommand,
+    /// The radio's new API mode, if it changed
+    pub api_mode: Option<ApiMode>,
+    /// The radio's new baud rate, if it changed
+    pub baud: Option<u32>,
+}
+
+/// Holds all the state related to reading and changing the connected radio's settings
+#[derive(Default)]
+pub struct RadioConfigPanel {
+    /// The values last read from the radio
+    current: BTreeMap<AtParameter, AtValue>,
+
+    /// The values shown in the editor, any that differ from `current` are written
+    edited: BTreeMap<AtParameter, AtValue>,
+
+    /// The values queued on the radio which it hasn't accepted yet, keyed by frame ID
+    pending: HashMap<u8, AtValue>,
+
+    /// The queued values the radio has accepted, applied once none are pending
+    accepted: Vec<AtValue>,
+
+    /// The parameters queried which the radio hasn't responded to yet, keyed by frame ID
+    queries: HashMap<u8, AtParameter>,
+
+    /// The frame ID of the last AT command sent
+    frame_id: u8,
+}
+
+impl RadioConfigPanel {
+    /// Get the next frame ID, skipping 0 as that disables the response
+    fn next_frame_id(&mut self) -> u8 {
+        self.frame_id = self.frame_id.checked_add(1).unwrap_or(1);
+        self.frame_id
+    }
+
+    /// Forget everything read from the radio, e.g. after it is disconnected
+    pub fn clear(&mut self) {
+        self.current.clear();
+        self.edited.clear();
+        self.pending.clear();
+        self.accepted.clear();
+        self.queries.clear();
+    }
+
+    /// Query every parameter from the radio
+    pub fn read(&mut self) -> Vec<AtCommand> {
+        all::<AtParameter>()
+            .map(|param| {
+                let frame_id = self.next_frame_id();
+                self.queries.insert(frame_id, param);
+                AtCommand::query(frame_id, param)
+            })
+            .collect()
+    }
+
+    /// Handle a response to one of the AT commands we sent, returns the settings to apply once
+    /// the radio has accepted every queued value
+    pub fn recv_response(
+        &mut self,
+        resp: &AtCommandResponse,
+        notif: &mut Toasts,
+    ) -> Option<ApplySettings> {
+        let command = String::from_utf8_lossy(&resp.command);
+        let pending = self.pending.remove(&resp.frame_id);
+        let query = self.queries.remove(&resp.frame_id);
+
+        if resp.status != AtCommandStatus::Ok {
+            tracing::warn!("AT command {command} failed - {}", resp.status);
+            notif.error(format!("AT command {command} failed: {}", resp.status));
+            if pending.is_some() {
+                // don't apply only some of the values
+                self.pending.clear();
+                self.accepted.clear();
+                notif.error("Radio settings were not applied");
+            }
+            return None;
+        }
+
+        if &resp.command == b"WR" {
+            notif.success("Wrote settings to the radio");
+        } else if let Some(param) = query {
+            // the response to a query, an unset NI is returned with no data
+            match AtValue::decode(param, &resp.data) {
+                Ok(value) => {
+                    self.current.insert(param, value.clone());
+                    self.edited.insert(param, value);
+                }
+                Err(e) => tracing::warn!("Couldn't decode {param} from {:02X?} - {e:?}", resp.data),
+            }
+        } else if let Some(value) = pending {
+            // the response to queueing a value
+            self.accepted.push(value);
+            if self.pending.is_empty() {
+                return Some(self.apply());
+            }
+        }
+
+        None
+    }
+
+    /// Apply the accepted values
+    fn apply(&mut self) -> ApplySettings {
+        let mut settings = ApplySettings {
+            apply: AtCommand::apply_changes(self.next_frame_id()),
+            write: AtCommand::write(self.next_frame_id()),
+            api_mode: None,
+            baud: None,
+        };
+
+        for value in self.accepted.drain(..) {
+            match value {
+                AtValue::ApiMode(ap) => settings.api_mode = ApiMode::from_ap_value(ap),
+                AtValue::BaudRate(v) => settings.baud = bd_baud_rate(v),
+                _ => {}
+            }
+            self.current.insert(value.parameter(), value);
+        }
+
+        settings
+    }
+
+    /// Show the configuration editor, returns any AT commands to send to the radio
+    pub fn show(&mut self, ui: &mut Ui, connected: bool) -> Vec<AtCommand> {
+        let mut cmds = vec![];
+
+        ui.add_enabled_ui(connected, |ui| {
+            Grid::new("radio_config_grid")
+                .num_columns(3)
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label("Parameter");
+                    ui.label("Radio");
+                    ui.label("New");
+                    ui.end_row();
+
+                    for param in all::<AtParameter>() {
+                        ui.label(param.as_str());
+                        match self.current.get(&param) {
+                            Some(value) => ui.label(value.to_string()),
+                            None => ui.label("?"),
+                        };
+                        match self.edited.get_mut(&param) {
+                            Some(value) => value_editor(ui, value),
+                            None => {
+                                ui.label("-");
+                            }
+                        }
+                        ui.end_row();
+                    }
+                });
+
+            // the values which have been edited and need writing to the radio
+            let changed: Vec<AtValue> = self
+                .edited
+                .values()
+                .filter(|value| self.current.get(&value.parameter()) != Some(*value))
+                .cloned()
+                .collect();
+            // transparent mode would stop the ground station talking to the radio for good
+            let transparent = changed.contains(&AtValue::ApiMode(0));
+
+            ui.horizontal(|ui| {
+                if ui.button("Read").clicked() {
+                    cmds.extend(self.read());
+                }
+
+                // the values are queued so changing the API mode or baud rate doesn't break
+                // the link until they have all been accepted
+                let writable = !changed.is_empty() && !transparent && self.pending.is_empty();
+                let mut write = ui.add_enabled(writable, Button::new("Write"));
+                if transparent {
+                    write = write.on_disabled_hover_text("AP=0 (transparent mode) isn't supported");
+                }
+                if write.clicked() {
+                    self.accepted.clear();
+                    for value in changed {
+                        let frame_id = self.next_frame_id();
+                        cmds.push(AtCommand::queue(frame_id, &value));
+                        self.pending.insert(frame_id, value);
+                    }
+                }
+
+                if ui.button("Revert").clicked() {
+                    self.edited = self.current.clone();
+                }
+            });
+        });
+
+        cmds
+    }
+}
+
+/// Show an editor for an AT parameter value
+pub(super) fn value_editor(ui: &mut Ui, value: &mut AtValue) {
+    match value {
+        AtValue::Channel(v) => {
+            ui.add(
+                DragValue::new(v)
+                    .clamp_range(0x0B..=0x1A)
+                    .hexadecimal(2, false, true),
+            );
+        }
+        AtValue::PanId(v) | AtValue::SourceAddress(v) => {
+            ui.add(DragValue::new(v).hexadecimal(4, false, true));
+        }
+        AtValue::DestinationHigh(v) | AtValue::DestinationLow(v) => {
+            ui.add(DragValue::new(v).hexadecimal(8, false, true));
+        }
+        AtValue::PowerLevel(v) => {
+            ui.add(DragValue::new(v).clamp_range(0..=4));
+        }
+        AtValue::ApiMode(v) => {
+            // AP=0 is transparent mode, which the ground station can't talk to
+            ui.add(DragValue::new(v).clamp_range(1..=2));
+        }
+        AtValue::BaudRate(v) => {
+            let selected =
+                bd_baud_rate(*v).map_or_else(|| format!("non-standard ({v})"), |b| b.to_string());
+            egui::ComboBox::from_id_source(ui.id().with("at_baud_combobox"))
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    for (i, baud) in BAUD_RATES.iter().enumerate() {
+                        ui.selectable_value(v, i as u32, baud.to_string());
+                    }
+                });
+            // any other rate is set as the rate itself
+            let mut literal = *v >= BD_LITERAL_MIN;
+            if ui.checkbox(&mut literal, "Custom").changed() {
+                *v = if literal {
+                    bd_baud_rate(*v)
+                        .unwrap_or(BD_LITERAL_MIN)
+                        .max(BD_LITERAL_MIN)
+                } else {
+                    BAUD_RATES.len() as u32 - 1
+                };
+            }
+            if literal {
+                ui.add(DragValue::new(v).clamp_range(BD_LITERAL_MIN..=0x3D090));
+            }
+        }
+        AtValue::NodeIdentifier(s) => {
+            ui.text_edit_singleline(s);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ok(frame_id: u8, command: &[u8; 2]) -> AtCommandResponse {
+        AtCommandResponse {
+            frame_id,
+            command: *command,
+            status: AtCommandStatus::Ok,
+            data: vec![],
+        }
+    }
+
+    #[test]
+    fn test_apply_once_every_value_is_accepted() {
+        let mut panel = RadioConfigPanel::default();
+        let mut notif = Toasts::new();
+        panel.pending.insert(1, AtValue::ApiMode(1));
+        panel.pending.insert(2, AtValue::BaudRate(7));
+
+        assert!(panel.recv_response(&ok(1, b"AP"), &mut notif).is_none());
+        let settings = panel.recv_response(&ok(2, b"BD"), &mut notif).unwrap();
+
+        assert_eq!(settings.apply.command, *b"AC");
+        assert_eq!(settings.write.command, *b"WR");
+        assert_eq!(settings.api_mode, Some(ApiMode::Unescaped));
+        assert_eq!(settings.baud, Some(115200));
+
+        // a non-standard rate is the rate itself
+        panel.pending.insert(3, AtValue::BaudRate(250_000));
+        let settings = panel.recv_response(&ok(3, b"BD"), &mut notif).unwrap();
+        assert_eq!(settings.baud, Some(250_000));
+        assert_eq!(
+            panel.current.get(&AtParameter::ApiMode),
+            Some(&AtValue::ApiMode(1))
+        );
+    }
+
+    #[test]
+    fn test_query_responses_match_by_frame_id() {
+        let mut panel = RadioConfigPanel::default();
+        let mut notif = Toasts::new();
+        let queries = panel.read();
+        let frame_id = |param: AtParameter| {
+            queries
+                .iter()
+                .find(|cmd| cmd.command == param.command())
+                .unwrap()
+                .frame_id
+        };
+
+        // an unset node identifier is returned without any data
+        let ni = ok(frame_id(AtParameter::NodeIdentifier), b"NI");
+        assert!(panel.recv_response(&ni, &mut notif).is_none());
+        let ch = AtCommandResponse {
+            data: vec![0x0C],
+            ..ok(frame_id(AtParameter::Channel), b"CH")
+        };
+        assert!(panel.recv_response(&ch, &mut notif).is_none());
+
+        assert_eq!(
+            panel.current.get(&AtParameter::NodeIdentifier),
+            Some(&AtValue::NodeIdentifier(String::new()))
+        );
+        assert_eq!(
+            panel.edited.get(&AtParameter::Channel),
+            Some(&AtValue::Channel(0x0C))
+        );
+        assert!(panel.accepted.is_empty());
+    }
+
+    #[test]
+    fn test_rejected_value_abandons_changes() {
+        let mut panel = RadioConfigPanel::default();
+        let mut notif = Toasts::new();
+        panel.pending.insert(1, AtValue::PowerLevel(4));
+        panel.pending.insert(2, AtValue::Channel(0x0C));
+
+        let rejected = AtCommandResponse {
+            status: AtCommandStatus::InvalidParameter,
+            ..ok(1, b"PL")
+        };
+        assert!(panel.recv_response(&rejected, &mut notif).is_none());
+        assert!(panel.recv_response(&ok(2, b"CH"), &mut notif).is_none());
+        assert!(panel.current.is_empty());
+    }
+}
@@ -1,5 +1,8 @@
 use crate::telemetry::Telemetry;
-use crate::xbee::{RxPacket, TxStatus, XbeePacket};
+use crate::xbee::{
+    AtCommandResponse, DecodeEvent, ExtendedTxStatus, ModemStatus, RemoteAtCommandResponse,
+    RxFrame, TxStatus, XbeePacket,
+};
 use std::fmt;
 
 #[derive(Debug, Clone)]
@@ -8,8 +11,8 @@ pub enum ReceivedPacket {
     Telemetry {
         // the packet containing the telemetry
         packet: XbeePacket,
-        // the parsed RxPacket
-        frame: RxPacket,
+        // the parsed RX frame
+        frame: RxFrame,
         // the parsed telemetry
         telem: Telemetry,
     },
@@ -17,7 +20,7 @@ pub enum ReceivedPacket {
     // an incoming packet that parsed correctly but couldn't be parsed as telemetry
     Received {
         packet: XbeePacket,
-        frame: RxPacket,
+        frame: RxFrame,
     },
 
     // status information for the packet with the given frame ID
@@ -28,6 +31,38 @@ pub enum ReceivedPacket {
         tx_status: TxStatus,
     },
 
+    // extended status information for the packet with the given frame ID
+    ExtendedStatus {
+        // the packet containing the ExtendedTxStatus
+        packet: XbeePacket,
+        // the parsed ExtendedTxStatus
+        tx_status: ExtendedTxStatus,
+    },
+
+    // the response to an AT command sent to the local radio
+    AtResponse {
+        // the packet containing the AtCommandResponse
+        packet: XbeePacket,
+        // the parsed AtCommandResponse
+        response: AtCommandResponse,
+    },
+
+    // the response to an AT command sent to a remote radio
+    RemoteAtResponse {
+        // the packet containing the RemoteAtCommandResponse
+        packet: XbeePacket,
+        // the parsed RemoteAtCommandResponse
+        response: RemoteAtCommandResponse,
+    },
+
+    // an event reported by the local radio, e.g. a reset
+    ModemStatus {
+        // the packet containing the ModemStatus
+        packet: XbeePacket,
+        // the parsed ModemStatus
+        status: ModemStatus,
+    },
+
     // an incoming packet which had a good frame ID but parsing the inner frame failed
     InvalidFrame(XbeePacket),
 
@@ -38,24 +73,15 @@ pub enum ReceivedPacket {
     Invalid(Vec<u8>),
 }
 
-impl From<&[u8]> for ReceivedPacket {
-    fn from(raw_packet: &[u8]) -> Self {
-        // first try and parse it as an XbeePacket
-        let xbp = match XbeePacket::decode(raw_packet) {
-            Ok(xbp) => xbp,
-            Err(e) => {
-                tracing::warn!("Failed to parse radio data - {e:?}");
-                return Self::Invalid(raw_packet.to_vec());
-            }
-        };
-
-        // then match on the frame type
+impl From<XbeePacket> for ReceivedPacket {
+    fn from(xbp: XbeePacket) -> Self {
+        // match on the frame type
         let received_data = match xbp.frame_type {
-            // RxPacket frame type
-            0x81 => match RxPacket::try_from(xbp.clone()) {
-                Ok(rxp) => rxp,
+            // RX frame types - 64-bit, 16-bit and Zigbee
+            0x80 | 0x81 | 0x90 => match RxFrame::try_from(xbp.clone()) {
+                Ok(frame) => frame,
                 Err(e) => {
-                    tracing::warn!("Failed to parse incoming RxPacket - {e:?}");
+                    tracing::warn!("Failed to parse incoming RX frame - {e:?}");
                     return Self::InvalidFrame(xbp);
                 }
             },
@@ -76,16 +102,68 @@ impl From<&[u8]> for ReceivedPacket {
                     }
                 }
             }
+            // ExtendedTxStatus frame type
+            0x8B => match ExtendedTxStatus::try_from(xbp.clone()) {
+                Ok(status) => {
+                    return Self::ExtendedStatus {
+                        packet: xbp,
+                        tx_status: status,
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to parse incoming ExtendedTxStatus - {e:?}");
+                    return Self::InvalidFrame(xbp);
+                }
+            },
+            // AtCommandResponse frame type
+            0x88 => match AtCommandResponse::try_from(xbp.clone()) {
+                Ok(response) => {
+                    return Self::AtResponse {
+                        packet: xbp,
+                        response,
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to parse incoming AtCommandResponse - {e:?}");
+                    return Self::InvalidFrame(xbp);
+                }
+            },
+            // RemoteAtCommandResponse frame type
+            0x97 => match RemoteAtCommandResponse::try_from(xbp.clone()) {
+                Ok(response) => {
+                    return Self::RemoteAtResponse {
+                        packet: xbp,
+                        response,
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to parse incoming RemoteAtCommandResponse - {e:?}");
+                    return Self::InvalidFrame(xbp);
+                }
+            },
+            // ModemStatus frame type
+            0x8A => match ModemStatus::try_from(xbp.clone()) {
+                Ok(status) => {
+                    return Self::ModemStatus {
+                        packet: xbp,
+                        status,
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to parse incoming ModemStatus - {e:?}");
+                    return Self::InvalidFrame(xbp);
+                }
+            },
             _ => {
                 return Self::Unrecognised(xbp);
             }
         };
 
         // get a UTF8 string from the sent data
-        let string_data = match String::from_utf8(received_data.data.clone()) {
+        let string_data = match String::from_utf8(received_data.data().to_vec()) {
             Ok(s) => s,
             Err(e) => {
-                tracing::warn!("Incoming RxPacket contained invalid UTF8 data - {e:?}");
+                tracing::warn!("Incoming RX frame contained invalid UTF8 data - {e:?}");
                 return Self::Received {
                     packet: xbp,
                     frame: received_data,
@@ -102,7 +180,7 @@ impl From<&[u8]> for ReceivedPacket {
                 telem,
             },
             Err(e) => {
-                tracing::warn!("Failed to parse RxPacket data as telemetry - {e:?}");
+                tracing::warn!("Failed to parse RX frame data as telemetry - {e:?}");
                 Self::Received {
                     packet: xbp,
                     frame: received_data,
@@ -112,6 +190,29 @@ impl From<&[u8]> for ReceivedPacket {
     }
 }
 
+impl From<DecodeEvent> for ReceivedPacket {
+    fn from(event: DecodeEvent) -> Self {
+        // anything that isn't a frame is kept as invalid data, telemetry may be recovered from it
+        let data = match event {
+            DecodeEvent::Frame(xbp) => return xbp.into(),
+            DecodeEvent::Garbage(data) => {
+                tracing::warn!("Skipped radio data outside of a frame - {data:02X?}");
+                data
+            }
+            DecodeEvent::BadChecksum(data) => {
+                tracing::warn!("Frame checksum didn't match - {data:02X?}");
+                data
+            }
+            DecodeEvent::Resync(data) => {
+                tracing::warn!("Abandoned partial frame - {data:02X?}");
+                data
+            }
+        };
+
+        Self::Invalid(data)
+    }
+}
+
 impl fmt::Display for ReceivedPacket {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -124,6 +225,18 @@ impl fmt::Display for ReceivedPacket {
             ReceivedPacket::Status { tx_status, .. } => {
                 write!(f, "{tx_status}")
             }
+            ReceivedPacket::ExtendedStatus { tx_status, .. } => {
+                write!(f, "{tx_status}")
+            }
+            ReceivedPacket::AtResponse { response, .. } => {
+                write!(f, "{response}")
+            }
+            ReceivedPacket::RemoteAtResponse { response, .. } => {
+                write!(f, "{response}")
+            }
+            ReceivedPacket::ModemStatus { status, .. } => {
+                write!(f, "ModemStatus - {status}")
+            }
             ReceivedPacket::InvalidFrame(xbp) => {
                 write!(f, "Invalid RxFrame - {xbp}")
             }
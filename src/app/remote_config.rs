@@ -0,0 +1,253 @@
+use super::radio_config::value_editor;
+use crate::as_str::AsStr;
+use crate::constants::{CONTAINER_ADDR, PROBE_ADDR};
+use crate::xbee::{
+    Address, AtCommandStatus, AtParameter, AtValue, RemoteAtCommand, RemoteAtCommandResponse,
+};
+use egui::{Color32, Grid, ScrollArea, Ui};
+use egui_notify::Toasts;
+use enum_iterator::{all, Sequence};
+use std::fmt;
+
+/// The radio a remote AT command is sent to
+#[derive(Sequence, Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum RemoteTarget {
+    #[default]
+    Container,
+    Probe,
+    Custom,
+}
+
+impl AsStr for RemoteTarget {
+    fn as_str(&self) -> &'static str {
+        match self {
+            RemoteTarget::Container => "Container",
+            RemoteTarget::Probe => "Probe",
+            RemoteTarget::Custom => "Custom (64-bit)",
+        }
+    }
+}
+
+impl fmt::Display for RemoteTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// the different states a remote AT command can have
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RemoteAtStatus {
+    // used if the radio isn't connected or writing to it failed
+    Unsent,
+    // sent but no response
+    Sent { frame_id: u8 },
+    // sent and response received
+    Response { status: AtCommandStatus },
+}
+
+/// A remote AT command and what has happened to it so far
+struct RemoteAtRecord {
+    cmd: RemoteAtCommand,
+    status: RemoteAtStatus,
+    /// The value returned if the command was a query
+    value: Option<AtValue>,
+}
+
+/// Holds all the state related to configuring the CanSat's radios over the air
+pub struct RemoteConfigPanel {
+    /// Which radio to send commands to
+    target: RemoteTarget,
+
+    /// The 64-bit address entered for `RemoteTarget::Custom`, in hex
+    custom_addr: String,
+
+    /// The value to set
+    value: AtValue,
+
+    /// The commands sent so far, oldest first
+    history: Vec<RemoteAtRecord>,
+
+    /// The frame ID of the last remote AT command sent
+    frame_id: u8,
+}
+
+impl Default for RemoteConfigPanel {
+    fn default() -> Self {
+        Self {
+            target: Default::default(),
+            custom_addr: "0013A20000000000".to_string(),
+            value: AtValue::PowerLevel(4),
+            history: vec![],
+            frame_id: 0,
+        }
+    }
+}
+
+impl RemoteConfigPanel {
+    /// Get the next frame ID, skipping 0 as that disables the response
+    fn next_frame_id(&mut self) -> u8 {
+        self.frame_id = self.frame_id.checked_add(1).unwrap_or(1);
+        self.frame_id
+    }
+
+    /// The address of the selected target, if it is valid
+    fn dst(&self) -> Option<Address> {
+        match self.target {
+            RemoteTarget::Container => Some(Address::Short(CONTAINER_ADDR)),
+            RemoteTarget::Probe => Some(Address::Short(PROBE_ADDR)),
+            RemoteTarget::Custom => u64::from_str_radix(self.custom_addr.trim(), 16)
+                .ok()
+                .map(Address::Long),
+        }
+    }
+
+    /// Record that the command with the given frame ID was written to the radio
+    pub fn sent(&mut self, frame_id: u8) {
+        let record = self
+            .history
+            .iter_mut()
+            .rev()
+            .find(|r| r.cmd.frame_id == frame_id && r.status == RemoteAtStatus::Unsent);
+
+        if let Some(record) = record {
+            record.status = RemoteAtStatus::Sent { frame_id };
+        }
+    }
+
+    /// Handle a response to one of the remote AT commands we sent
+    pub fn recv_response(&mut self, resp: &RemoteAtCommandResponse, notif: &mut Toasts) {
+        let command = String::from_utf8_lossy(&resp.command);
+        let record = self.history.iter_mut().rev().find(|r| {
+            r.status
+                == RemoteAtStatus::Sent {
+                    frame_id: resp.frame_id,
+                }
+                && r.cmd.command == resp.command
+        });
+
+        let Some(record) = record else {
+            tracing::warn!("Received a remote AT response we weren't waiting for - {resp}");
+            return;
+        };
+
+        record.status = RemoteAtStatus::Response {
+            status: resp.status,
+        };
+        record.value = resp.value();
+
+        if resp.status == AtCommandStatus::Ok {
+            notif.success(format!("Remote AT command {command} succeeded"));
+        } else {
+            tracing::warn!("Remote AT command {command} failed - {}", resp.status);
+            notif.error(format!(
+                "Remote AT command {command} failed: {}",
+                resp.status
+            ));
+        }
+    }
+
+    /// Show the remote configuration editor, returns any remote AT commands to send
+    pub fn show(&mut self, ui: &mut Ui, connected: bool) -> Vec<RemoteAtCommand> {
+        let mut cmds = vec![];
+
+        ui.horizontal(|ui| {
+            ui.label("Target: ");
+            egui::ComboBox::from_id_source("remote_target_combobox")
+                .selected_text(self.target.as_str())
+                .show_ui(ui, |ui| {
+                    for target in all::<RemoteTarget>() {
+                        ui.selectable_value(&mut self.target, target, target.as_str());
+                    }
+                });
+
+            if self.target == RemoteTarget::Custom {
+                ui.text_edit_singleline(&mut self.custom_addr);
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Parameter: ");
+            let mut param = self.value.parameter();
+            egui::ComboBox::from_id_source("remote_param_combobox")
+                .selected_text(param.as_str())
+                .show_ui(ui, |ui| {
+                    for p in all::<AtParameter>() {
+                        ui.selectable_value(&mut param, p, p.as_str());
+                    }
+                });
+
+            if param != self.value.parameter() {
+                self.value = param.default_value();
+            }
+            value_editor(ui, &mut self.value);
+        });
+
+        let dst = self.dst();
+        if dst.is_none() {
+            ui.colored_label(Color32::RED, "Invalid 64-bit address");
+        }
+
+        ui.add_enabled_ui(connected && dst.is_some(), |ui| {
+            ui.horizontal(|ui| {
+                let Some(dst) = dst else {
+                    return;
+                };
+
+                let param = self.value.parameter();
+                if ui.button("Query").clicked() {
+                    cmds.push(RemoteAtCommand::query(self.next_frame_id(), dst, param));
+                }
+                if ui.button("Set").clicked() {
+                    cmds.push(RemoteAtCommand::set(self.next_frame_id(), dst, &self.value));
+                }
+                if ui.button("Write").clicked() {
+                    cmds.push(RemoteAtCommand::write(self.next_frame_id(), dst));
+                }
+            });
+        });
+
+        for cmd in cmds.iter() {
+            self.history.push(RemoteAtRecord {
+                cmd: cmd.clone(),
+                status: RemoteAtStatus::Unsent,
+                value: None,
+            });
+        }
+
+        ScrollArea::vertical()
+            .max_height(150.0)
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                Grid::new("remote_at_history_grid")
+                    .num_columns(4)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for record in self.history.iter() {
+                            let (color, status) = match record.status {
+                                RemoteAtStatus::Unsent => (Color32::GRAY, "Unsent".to_string()),
+                                RemoteAtStatus::Sent { .. } => {
+                                    (Color32::YELLOW, "Waiting".to_string())
+                                }
+                                RemoteAtStatus::Response {
+                                    status: AtCommandStatus::Ok,
+                                } => (Color32::GREEN, "OK".to_string()),
+                                RemoteAtStatus::Response { status } => {
+                                    (Color32::RED, status.to_string())
+                                }
+                            };
+
+                            ui.label(record.cmd.dst().to_string());
+                            ui.label(String::from_utf8_lossy(&record.cmd.command));
+                            ui.colored_label(color, status);
+                            match &record.value {
+                                Some(value) => ui.label(value.to_string()),
+                                None => ui.label(""),
+                            };
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        cmds
+    }
+}
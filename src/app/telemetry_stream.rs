@@ -0,0 +1,243 @@
+use super::graphable::Graphable;
+use crate::as_str::AsStr;
+use crate::constants::{
+    CONTAINER_ADDR, CONTAINER_TELEMETRY_FILE, PROBE_ADDR, PROBE_TELEMETRY_FILE, TELEMETRY_FILE,
+};
+use crate::telemetry::Telemetry;
+use crate::xbee::Address;
+use egui::{plot::PlotPoint, Color32, Grid, Ui};
+use enum_iterator::{all, Sequence};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::time::Instant;
+
+/// Which part of the CanSat sent some telemetry, decided by the RX frame's source address
+#[derive(Sequence, Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum TelemetrySource {
+    Container,
+    Probe,
+    /// Telemetry from any other address, or recovered from data without an address
+    Other,
+}
+
+impl TelemetrySource {
+    pub fn from_address(addr: Address, long_addrs: &LongAddresses) -> Self {
+        match addr {
+            Address::Short(CONTAINER_ADDR) => TelemetrySource::Container,
+            Address::Short(PROBE_ADDR) => TelemetrySource::Probe,
+            Address::Long(addr) if long_addrs.container == Some(addr) => TelemetrySource::Container,
+            Address::Long(addr) if long_addrs.probe == Some(addr) => TelemetrySource::Probe,
+            _ => TelemetrySource::Other,
+        }
+    }
+
+    /// The CSV file this source's telemetry is saved to
+    pub const fn file_name(&self) -> &'static str {
+        match self {
+            TelemetrySource::Container => CONTAINER_TELEMETRY_FILE,
+            TelemetrySource::Probe => PROBE_TELEMETRY_FILE,
+            TelemetrySource::Other => TELEMETRY_FILE,
+        }
+    }
+
+    /// The colour used for this source's line when graphs are overlaid
+    pub const fn color(&self) -> Color32 {
+        match self {
+            TelemetrySource::Container => Color32::from_rgb(0, 162, 232),
+            TelemetrySource::Probe => Color32::from_rgb(255, 127, 39),
+            TelemetrySource::Other => Color32::GRAY,
+        }
+    }
+}
+
+impl AsStr for TelemetrySource {
+    fn as_str(&self) -> &'static str {
+        match self {
+            TelemetrySource::Container => "Container",
+            TelemetrySource::Probe => "Probe",
+            TelemetrySource::Other => "Other",
+        }
+    }
+}
+
+impl fmt::Display for TelemetrySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The 64-bit addresses of the container and probe radios, which are unique to each radio so
+/// can't be constants. Radios sending 64-bit RX frames are only told apart once these are set.
+#[derive(Debug, Default)]
+pub struct LongAddresses {
+    pub container: Option<u64>,
+    pub probe: Option<u64>,
+
+    /// The addresses being edited, in hex
+    container_text: String,
+    probe_text: String,
+}
+
+impl LongAddresses {
+    /// Show an editor for the addresses, an empty address is unset
+    pub fn show(&mut self, ui: &mut Ui) {
+        Grid::new("long_addresses_grid")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Container: ");
+                long_address_editor(ui, &mut self.container, &mut self.container_text);
+                ui.end_row();
+
+                ui.label("Probe: ");
+                long_address_editor(ui, &mut self.probe, &mut self.probe_text);
+                ui.end_row();
+            });
+    }
+}
+
+/// Edit a 64-bit address as hex, only changing the address once the text is valid
+fn long_address_editor(ui: &mut Ui, addr: &mut Option<u64>, text: &mut String) {
+    let resp = ui.text_edit_singleline(text);
+    let parsed = match text.trim() {
+        "" => Some(None),
+        hex => u64::from_str_radix(hex, 16).ok().map(Some),
+    };
+    match parsed {
+        Some(parsed) if resp.changed() => *addr = parsed,
+        Some(_) => {}
+        None => {
+            ui.colored_label(Color32::RED, "invalid");
+        }
+    }
+}
+
+/// The telemetry received from a single source
+#[derive(Debug, Default)]
+pub struct TelemetryStream {
+    /// The collected telemetry from the current run
+    pub telemetry: Vec<Telemetry>,
+
+    /// The values to graph for each telemetry field
+    pub graph_values: HashMap<Graphable, Vec<PlotPoint>>,
+
+    /// The number of missed telemetry packets
+    pub missed_packets: u32,
+
+    /// When the latest telemetry was received
+    pub last_received: Option<Instant>,
+}
+
+impl TelemetryStream {
+    /// Add some telemetry to the stream, updating the missed packets and graph values
+    pub fn push(&mut self, telem: Telemetry) {
+        // calculate how many packets we missed if any
+        if let Some(prev) = self.telemetry.last() {
+            self.missed_packets += telem.packet_count.saturating_sub(1 + prev.packet_count);
+        }
+
+        let time = telem.mission_time.as_seconds();
+        for field in all::<Graphable>() {
+            self.graph_values
+                .entry(field)
+                .or_default()
+                .push(PlotPoint::new(time, field.extract_telemetry_value(&telem)));
+        }
+
+        self.telemetry.push(telem);
+        self.last_received = Some(Instant::now());
+    }
+}
+
+/// The latest telemetry describing the CanSat's flight, the container runs the mission so its
+/// telemetry is preferred over telemetry from an unknown source
+pub fn latest_telemetry(
+    streams: &BTreeMap<TelemetrySource, TelemetryStream>,
+) -> Option<&Telemetry> {
+    latest_telemetry_received(streams).map(|(telem, _)| telem)
+}
+
+/// The latest telemetry describing the CanSat's flight and when it was received
+pub fn latest_telemetry_received(
+    streams: &BTreeMap<TelemetrySource, TelemetryStream>,
+) -> Option<(&Telemetry, Instant)> {
+    [TelemetrySource::Container, TelemetrySource::Other]
+        .iter()
+        .find_map(|source| {
+            let stream = streams.get(source)?;
+            Some((stream.telemetry.last()?, stream.last_received?))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::TelemetryBuilder;
+
+    #[test]
+    fn test_telemetry_source_from_address() {
+        let long_addrs = LongAddresses::default();
+        assert_eq!(
+            TelemetrySource::from_address(Address::Short(CONTAINER_ADDR), &long_addrs),
+            TelemetrySource::Container
+        );
+        assert_eq!(
+            TelemetrySource::from_address(Address::Short(PROBE_ADDR), &long_addrs),
+            TelemetrySource::Probe
+        );
+        assert_eq!(
+            TelemetrySource::from_address(Address::Long(CONTAINER_ADDR as u64), &long_addrs),
+            TelemetrySource::Other
+        );
+    }
+
+    #[test]
+    fn test_telemetry_source_from_long_address() {
+        let long_addrs = LongAddresses {
+            container: Some(0x0013_A200_4040_1122),
+            probe: Some(0x0013_A200_4040_3344),
+            ..Default::default()
+        };
+        assert_eq!(
+            TelemetrySource::from_address(Address::Long(0x0013_A200_4040_1122), &long_addrs),
+            TelemetrySource::Container
+        );
+        assert_eq!(
+            TelemetrySource::from_address(Address::Long(0x0013_A200_4040_3344), &long_addrs),
+            TelemetrySource::Probe
+        );
+        assert_eq!(
+            TelemetrySource::from_address(Address::Long(0x0013_A200_4040_5566), &long_addrs),
+            TelemetrySource::Other
+        );
+
+        // the 16-bit addresses still work alongside them
+        assert_eq!(
+            TelemetrySource::from_address(Address::Short(PROBE_ADDR), &long_addrs),
+            TelemetrySource::Probe
+        );
+    }
+
+    #[test]
+    fn test_missed_packets_per_stream() {
+        let telem: Vec<Telemetry> = [0, 1, 2, 5]
+            .into_iter()
+            .map(|count| TelemetryBuilder::default().packet_count(count).build())
+            .collect();
+
+        // each stream only counts gaps in its own packet counts
+        let mut container = TelemetryStream::default();
+        let mut probe = TelemetryStream::default();
+        for t in &telem[..3] {
+            container.push(t.clone());
+            probe.push(t.clone());
+        }
+        assert_eq!(container.missed_packets, 0);
+        assert_eq!(probe.missed_packets, 0);
+
+        // skipping packets 3 and 4
+        container.push(telem[3].clone());
+        assert_eq!(container.missed_packets, 2);
+        assert_eq!(container.telemetry.len(), 4);
+        assert_eq!(container.graph_values[&Graphable::PacketCount].len(), 4);
+    }
+}
@@ -0,0 +1,253 @@
+use anyhow::{bail, Context, Result};
+use ground_station::constants::CONTAINER_ADDR;
+use ground_station::synth::{
+    connect_tcp, parse_addr, parse_api_mode, parse_rate, FlightSoftware, RadioLink, RadioPort,
+    SinkSpec,
+};
+use ground_station::telemetry::Telemetry;
+use ground_station::xbee::{ApiMode, DeliveryStatus};
+use std::{
+    io::{self, BufRead, ErrorKind, Write},
+    net::TcpStream,
+    sync::mpsc::{self, Sender},
+    thread,
+    time::{Duration, Instant},
+};
+use tracing::Level;
+
+/// How the fake CanSat talks to the ground station
+#[derive(Debug, Clone)]
+enum LinkConfig {
+    /// Connect to the ground station's telemetry listener and send telemetry as lines of text.
+    /// The listener never sends commands back, so they have to be typed on stdin.
+    Tcp(String),
+    /// XBee frames, as the ground station's radio would send them
+    Radio(RadioPort),
+}
+
+/// How the fake CanSat behaves
+#[derive(Debug, Clone)]
+struct Config {
+    link: LinkConfig,
+    /// The API mode of the XBee frames
+    mode: ApiMode,
+    /// The number of telemetry packets to send per second
+    rate: f64,
+    /// The source address of the telemetry in XBee frames
+    src_addr: u16,
+    /// The seed for the flight's random noise
+    seed: Option<u64>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            link: LinkConfig::Tcp("127.0.0.1:10470".to_string()),
+            mode: ApiMode::default(),
+            rate: 1.0,
+            src_addr: CONTAINER_ADDR,
+            seed: None,
+        }
+    }
+}
+
+impl Config {
+    fn from_args(mut args: impl Iterator<Item = String>) -> Result<Self> {
+        let mut config = Self::default();
+        let mut baud = None;
+
+        while let Some(arg) = args.next() {
+            // every option except --pty takes a value
+            if arg == "--pty" {
+                #[cfg(unix)]
+                {
+                    config.link = LinkConfig::Radio(RadioPort::Pty);
+                    continue;
+                }
+                #[cfg(not(unix))]
+                bail!("--pty is only supported on unix, use --serial or --tcp instead");
+            }
+            let Some(value) = args.next() else {
+                bail!("Missing value for {arg}");
+            };
+
+            match arg.as_str() {
+                "--tcp" => config.link = LinkConfig::Tcp(value),
+                "--serial" => {
+                    config.link = LinkConfig::Radio(RadioPort::Serial {
+                        path: value,
+                        baud: SinkSpec::DEFAULT_BAUD,
+                    })
+                }
+                "--baud" => baud = Some(value.parse().context("Invalid --baud")?),
+                "--mode" => config.mode = parse_api_mode(&value)?,
+                "--rate" => config.rate = parse_rate(&arg, &value)?,
+                "--src" => config.src_addr = parse_addr(&arg, &value)?,
+                "--seed" => config.seed = Some(value.parse().context("Invalid --seed")?),
+                _ => bail!("Unrecognised argument {arg:?}"),
+            }
+        }
+
+        if let Some(baud) = baud {
+            let LinkConfig::Radio(RadioPort::Serial {
+                baud: port_baud, ..
+            }) = &mut config.link
+            else {
+                bail!("--baud can only be used with --serial");
+            };
+            *port_baud = baud;
+        }
+
+        Ok(config)
+    }
+}
+
+const USAGE: &str = "usage: cansat [--tcp HOST:PORT | --serial PORT [--baud BAUD] | --pty] \
+    [--mode 1|2] [--rate HZ] [--src container|probe|ADDR] [--seed N]";
+
+/// A command received from the ground station
+struct Received {
+    line: String,
+    /// The frame ID to acknowledge, 0 if no TX status is wanted
+    frame_id: u8,
+}
+
+/// The RSSI reported with each XBee frame of telemetry, in -dBm
+const RSSI: i8 = 40;
+
+/// The open connection to the ground station
+enum Link {
+    Tcp(TcpStream),
+    Xbee { radio: RadioLink, src_addr: u16 },
+}
+
+impl Link {
+    /// Open the link, spawning a thread to send any commands received on it to `tx`
+    fn open(config: &Config, tx: Sender<Received>) -> Result<Self> {
+        let port = match &config.link {
+            // the ground station may not be listening yet
+            LinkConfig::Tcp(addr) => return Ok(Link::Tcp(connect_tcp(addr))),
+            LinkConfig::Radio(port) => port,
+        };
+
+        let radio = RadioLink::open(port, config.mode)?;
+        #[cfg(unix)]
+        if let Some(path) = radio.pty_path() {
+            println!("CanSat radio listening on {path}");
+        }
+        radio.spawn_reader(move |frame| {
+            let received = Received {
+                line: String::from_utf8_lossy(frame.data()).into_owned(),
+                frame_id: frame.frame_id(),
+            };
+            tx.send(received)
+                .map_err(|_| io::Error::other("The flight software has stopped"))
+        })?;
+
+        Ok(Link::Xbee {
+            radio,
+            src_addr: config.src_addr,
+        })
+    }
+
+    fn send_telemetry(&mut self, telem: &Telemetry) -> io::Result<()> {
+        match self {
+            Link::Tcp(stream) => writeln!(stream, "{telem}"),
+            Link::Xbee { radio, src_addr } => {
+                radio.send_rx(*src_addr, RSSI, telem.to_string().into_bytes())
+            }
+        }
+    }
+
+    /// Acknowledge a command sent in an XBee frame
+    fn send_status(&self, frame_id: u8) -> io::Result<()> {
+        match self {
+            Link::Tcp(_) => Ok(()),
+            Link::Xbee { radio, .. } => radio.send_status(frame_id, DeliveryStatus::Success),
+        }
+    }
+}
+
+/// Has a write failed because the ground station disconnected
+fn disconnected(e: &io::Error) -> bool {
+    matches!(e.kind(), ErrorKind::BrokenPipe | ErrorKind::ConnectionReset)
+}
+
+/// Send each line typed on stdin to the flight software
+fn read_lines(reader: impl BufRead, tx: Sender<Received>) {
+    for line in reader.lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                tracing::warn!("Failed to read command - {e:?}");
+                break;
+            }
+        };
+
+        let received = Received { line, frame_id: 0 };
+        if tx.send(received).is_err() {
+            break;
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    // setup logging
+    tracing_subscriber::fmt()
+        .with_ansi(true)
+        .with_max_level(Level::DEBUG)
+        .with_writer(io::stderr)
+        .init();
+
+    let config = match Config::from_args(std::env::args().skip(1)) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{e}\n{USAGE}");
+            std::process::exit(1);
+        }
+    };
+    tracing::info!("Starting CanSat - {config:?}");
+
+    // commands can also be typed in, to rehearse without the ground station sending them or
+    // when telemetry is sent over TCP
+    let (tx, rx) = mpsc::channel();
+    let mut link = Link::open(&config, tx.clone())?;
+    thread::Builder::new()
+        .name("stdin".to_string())
+        .spawn(move || read_lines(io::stdin().lock(), tx))?;
+
+    let mut fsw = FlightSoftware::new(config.seed, Instant::now());
+    fsw.period = Duration::from_secs_f64(1.0 / config.rate);
+
+    loop {
+        let now = Instant::now();
+        for Received { line, frame_id } in rx.try_iter() {
+            // the radio acknowledges every frame, whether or not the command is understood
+            if frame_id != 0 {
+                if let Err(e) = link.send_status(frame_id) {
+                    if disconnected(&e) {
+                        tracing::info!("Ground station has disconnected, exiting.");
+                        return Ok(());
+                    }
+                    tracing::warn!("Failed to acknowledge command - {e}");
+                }
+            }
+            if let Err(e) = fsw.handle_line(line.trim(), now) {
+                tracing::warn!("Ignoring invalid command {line:?} - {e}");
+            }
+        }
+
+        if let Some(telem) = fsw.poll(now) {
+            tracing::debug!("Sending {telem}");
+            if let Err(e) = link.send_telemetry(&telem) {
+                if disconnected(&e) {
+                    tracing::info!("Ground station has disconnected, exiting.");
+                    return Ok(());
+                }
+                tracing::warn!("Failed to send telemetry - {e}");
+            }
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    }
+}
@@ -0,0 +1,179 @@
+use anyhow::{bail, Context, Result};
+use chrono::{Timelike, Utc};
+use ground_station::command::Command;
+use ground_station::constants::CONTAINER_ADDR;
+use ground_station::synth::{parse_addr, parse_api_mode, parse_rate, RadioLink, TelemetrySynth};
+use ground_station::xbee::{ApiMode, DeliveryStatus, ModemStatus, TxFrame};
+use parking_lot::Mutex;
+use std::{io, sync::Arc, thread, time::Duration};
+use tracing::Level;
+
+/// How the emulated radio behaves
+#[derive(Debug, Clone)]
+struct Config {
+    /// The API mode of the emulated radio
+    mode: ApiMode,
+    /// The number of telemetry packets to emit per second
+    rate: f64,
+    /// The RSSI reported with each RX packet, in -dBm
+    rssi: i8,
+    /// The source address of the emitted telemetry
+    src_addr: u16,
+    /// The delivery status to reply to every TX request with
+    status: DeliveryStatus,
+    /// How long to wait before replying with the TX status
+    latency: Duration,
+    /// Copy received commands into CMD_ECHO?
+    echo: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            mode: ApiMode::default(),
+            rate: 1.0,
+            rssi: 40,
+            src_addr: CONTAINER_ADDR,
+            status: DeliveryStatus::Success,
+            latency: Duration::from_millis(20),
+            echo: false,
+        }
+    }
+}
+
+impl Config {
+    fn from_args(mut args: impl Iterator<Item = String>) -> Result<Self> {
+        let mut config = Self::default();
+
+        while let Some(arg) = args.next() {
+            // every option except --echo takes a value
+            if arg == "--echo" {
+                config.echo = true;
+                continue;
+            }
+            let Some(value) = args.next() else {
+                bail!("Missing value for {arg}");
+            };
+
+            match arg.as_str() {
+                "--mode" => config.mode = parse_api_mode(&value)?,
+                "--rate" => config.rate = parse_rate(&arg, &value)?,
+                "--rssi" => config.rssi = value.parse().context("Invalid --rssi")?,
+                "--src" => config.src_addr = parse_addr(&arg, &value)?,
+                "--ack" => {
+                    config.status = match value.as_str() {
+                        "success" => DeliveryStatus::Success,
+                        "noack" => DeliveryStatus::NoAck,
+                        "cca" => DeliveryStatus::CcaFailure,
+                        _ => bail!("Invalid --ack {value:?}, expected success, noack or cca"),
+                    }
+                }
+                "--latency" => {
+                    config.latency =
+                        Duration::from_millis(value.parse().context("Invalid --latency")?)
+                }
+                _ => bail!("Unrecognised argument {arg:?}"),
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+const USAGE: &str = "usage: emulator [--mode 1|2] [--rate HZ] [--rssi DBM] \
+    [--src container|probe|ADDR] [--ack success|noack|cca] [--latency MS] [--echo]";
+
+/// Reply to a TX request sent by the ground station with its TX status
+fn handle_frame(
+    frame: TxFrame,
+    radio: &RadioLink,
+    echo: &Mutex<String>,
+    config: &Config,
+) -> io::Result<()> {
+    if config.echo && config.status == DeliveryStatus::Success {
+        // the flight software only echoes commands it understands
+        if let Ok(cmd) = String::from_utf8_lossy(frame.data()).parse::<Command>() {
+            *echo.lock() = cmd.echo();
+        }
+    }
+
+    // frame ID 0 means no status is wanted
+    if frame.frame_id() != 0 {
+        thread::sleep(config.latency);
+        radio.send_status(frame.frame_id(), config.status)?;
+    }
+
+    Ok(())
+}
+
+/// Create the pty the ground station opens like a radio, returning its path
+#[cfg(unix)]
+fn open_pty(mode: ApiMode) -> Result<(RadioLink, String)> {
+    use ground_station::synth::RadioPort;
+
+    let radio = RadioLink::open(&RadioPort::Pty, mode)?;
+    let path = radio.pty_path().context("The pty has no name")?;
+    Ok((radio, path))
+}
+
+#[cfg(not(unix))]
+fn open_pty(_mode: ApiMode) -> Result<(RadioLink, String)> {
+    bail!("The emulator creates a pty, which is only supported on unix")
+}
+
+fn main() -> Result<()> {
+    // setup logging
+    tracing_subscriber::fmt()
+        .with_ansi(true)
+        .with_max_level(Level::DEBUG)
+        .with_writer(io::stderr)
+        .init();
+
+    let config = match Config::from_args(std::env::args().skip(1)) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{e}\n{USAGE}");
+            std::process::exit(1);
+        }
+    };
+    tracing::info!("Starting emulator - {config:?}");
+
+    let (radio, path) = open_pty(config.mode)?;
+    let radio = Arc::new(radio);
+    println!("Emulated radio listening on {path}");
+    let echo = Arc::new(Mutex::new(String::from("CXON")));
+
+    // the radio reports a reset when it powers on
+    radio.send(ModemStatus::HardwareReset.into())?;
+
+    let tx_handle = {
+        let replies = radio.clone();
+        let echo = echo.clone();
+        let config = config.clone();
+        radio.spawn_reader(move |frame| handle_frame(frame, &replies, &echo, &config))?
+    };
+
+    // emit telemetry from the same flight model as the generator, at the configured rate
+    let mut synth = TelemetrySynth::new(None);
+    synth.interval = 1.0 / config.rate;
+    synth.jitter = 0.0;
+    synth.failure_rate = 0.0;
+    synth.launch_time = Utc::now().num_seconds_from_midnight() as f64;
+    while !tx_handle.is_finished() {
+        synth.cmd_echo = echo.lock().clone();
+        let packet = synth.next_packet();
+        tracing::debug!("Sending {}", packet.telem);
+        radio.send_rx(
+            config.src_addr,
+            config.rssi,
+            packet.telem.to_string().into_bytes(),
+        )?;
+
+        thread::sleep(packet.delay);
+    }
+
+    match tx_handle.join() {
+        Ok(res) => Ok(res?),
+        Err(_) => bail!("TX thread panicked"),
+    }
+}
@@ -7,6 +7,9 @@ use eframe::{egui, NativeOptions};
 use ground_station::app::GroundStationGui;
 use ground_station::listener::TelemetryListener;
 use ground_station::reader::TelemetryReader;
+use ground_station::replay::RadioReplay;
+use ground_station::synth::parse_api_mode;
+use ground_station::xbee::ApiMode;
 use termcolor::ColorChoice;
 use tracing::Level;
 use tracing_subscriber::fmt::writer::MakeWriterExt;
@@ -51,6 +54,22 @@ fn main() -> Result<()> {
                 .spawn(move || listener.run())?;
             GroundStationGui::new_with_receiver(rx)
         }
+        "replay" => {
+            // replay raw data saved from the radio, in the API mode the radio was in
+            let path = args()
+                .nth(2)
+                .unwrap_or_else(|| String::from("radio_data.raw"));
+            let mode = match args().nth(3) {
+                Some(mode) => parse_api_mode(&mode)?,
+                None => ApiMode::default(),
+            };
+            let (tx, rx) = channel();
+            let mut replay = RadioReplay::new(tx, path, mode);
+            let _handle: JoinHandle<Result<()>> = thread::Builder::new()
+                .name("replay".to_string())
+                .spawn(move || replay.run())?;
+            GroundStationGui::new_with_receiver(rx)
+        }
         _ => {
             if arg != "radio" {
                 tracing::warn!("Unrecognised first argument - {arg:?} - starting in radio mode.");
@@ -1,8 +1,8 @@
 use crate::as_str::AsStr;
 use enum_iterator::Sequence;
-use parse_display::Display;
+use parse_display::{Display, FromStr};
 
-#[derive(Sequence, Display, Default, Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Sequence, Display, FromStr, Default, Debug, Copy, Clone, Eq, PartialEq)]
 #[display(style = "SNAKE_CASE")]
 pub enum Action {
     #[default]
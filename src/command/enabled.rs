@@ -1,10 +1,10 @@
 use crate::as_str::AsStr;
 use enum_iterator::Sequence;
-use parse_display::Display;
+use parse_display::{Display, FromStr};
 use std::default::Default;
 
 /// Represents the argument to the
-#[derive(Default, Sequence, Display, Copy, Clone, Eq, PartialEq)]
+#[derive(Default, Sequence, Display, FromStr, Debug, Copy, Clone, Eq, PartialEq)]
 #[display(style = "UPPERCASE")]
 pub enum Enabled {
     #[default]
@@ -0,0 +1,340 @@
+mod action;
+mod enabled;
+mod hold_release;
+mod open_close;
+mod raise_stop;
+mod safety;
+mod sim_mode;
+mod state;
+
+pub use action::Action;
+pub use enabled::Enabled;
+pub use hold_release::HoldRelease;
+pub use open_close::OpenClose;
+pub use raise_stop::RaiseStop;
+pub use safety::{Safeguard, SafetyPolicy, Verdict};
+pub use sim_mode::SimMode;
+pub use state::{ContainerState, PayloadState, Target};
+
+use crate::constants::TEAM_ID;
+use crate::telemetry::GpsTime;
+use std::fmt;
+use std::str::FromStr;
+
+/// The argument to the ST command
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TimeArg {
+    /// Set the time to the given UTC time
+    Utc(GpsTime),
+    /// Set the time from the time read by the GPS
+    Gps,
+}
+
+impl fmt::Display for TimeArg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeArg::Utc(time) => write!(f, "{time}"),
+            TimeArg::Gps => f.write_str("GPS"),
+        }
+    }
+}
+
+/// The argument to the OPTIONAL,SETSTATE command, the FSM to change and the state to change it to
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SetState {
+    Container(ContainerState),
+    Payload(PayloadState),
+}
+
+impl fmt::Display for SetState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetState::Container(state) => write!(f, "{},{state}", Target::Container),
+            SetState::Payload(state) => write!(f, "{},{state}", Target::Payload),
+        }
+    }
+}
+
+/// A command which can be sent to the CanSat, displayed in the `CMD,<TEAM_ID>,...` format
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Command {
+    /// CX - Enable/Disable container telemetry
+    TelemetryEnable(Enabled),
+
+    /// ST - Set the mission time
+    SetTime(TimeArg),
+
+    /// SIM - Enable, activate or disable simulation mode
+    SimulationMode(SimMode),
+
+    /// SIMP - Simulated pressure in pascals
+    SimulatedPressure(u32),
+
+    /// CAL - calibrate the sensors and reset the EEPROM
+    Calibrate,
+
+    /// OPTIONAL,RESET
+    Reset,
+
+    /// OPTIONAL,ACTION - force the container / payload to perform a certain action
+    Action(Action),
+
+    /// OPTIONAL,SETSTATE - forcibly change the payload/container FSM state
+    SetState(SetState),
+
+    /// OPTIONAL,CAM.ON/OFF
+    CamEnable(Enabled),
+
+    /// OPTIONAL,SOUND.ON/OFF
+    SoundEnable(Enabled),
+
+    /// OPTIONAL,FLAP.OPEN/CLOSE
+    Flaps(OpenClose),
+
+    /// OPTIONAL,HS.OPEN/CLOSE
+    HeatShield(OpenClose),
+
+    /// OPTIONAL,CHUTE.OPEN/CLOSE
+    Parachute(OpenClose),
+
+    /// OPTIONAL,PROBE.HOLD/RELEASE
+    Probe(HoldRelease),
+
+    /// OPTIONAL,FLAG.RAISE/STOP
+    Flag(RaiseStop),
+}
+
+impl Command {
+    /// The CMD_ECHO the CanSat reports after running this command, the fields after the team ID
+    /// with the commas removed, e.g. `CMD,1047,CX,ON` is echoed as `CXON`.
+    pub fn echo(&self) -> String {
+        self.to_string().split(',').skip(2).collect()
+    }
+
+    /// Whether the CanSat stops sending telemetry after running this command, so it can never
+    /// be seen in CMD_ECHO
+    pub fn stops_telemetry(&self) -> bool {
+        matches!(self, Command::TelemetryEnable(Enabled::Off))
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CMD,{TEAM_ID},")?;
+        match self {
+            Command::TelemetryEnable(enabled) => write!(f, "CX,{enabled}"),
+            Command::SetTime(time) => write!(f, "ST,{time}"),
+            Command::SimulationMode(mode) => write!(f, "SIM,{mode}"),
+            Command::SimulatedPressure(pressure) => write!(f, "SIMP,{pressure}"),
+            Command::Calibrate => write!(f, "CAL"),
+            Command::Reset => write!(f, "OPTIONAL,RESET"),
+            Command::Action(action) => write!(f, "OPTIONAL,ACTION,{action}"),
+            Command::SetState(state) => write!(f, "OPTIONAL,SETSTATE,{state}"),
+            Command::CamEnable(enabled) => write!(f, "OPTIONAL,CAM.{enabled}"),
+            Command::SoundEnable(enabled) => write!(f, "OPTIONAL,SOUND.{enabled}"),
+            Command::Flaps(open) => write!(f, "OPTIONAL,FLAP.{open}"),
+            Command::HeatShield(open) => write!(f, "OPTIONAL,HS.{open}"),
+            Command::Parachute(open) => write!(f, "OPTIONAL,CHUTE.{open}"),
+            Command::Probe(hold) => write!(f, "OPTIONAL,PROBE.{hold}"),
+            Command::Flag(raise) => write!(f, "OPTIONAL,FLAG.{raise}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ParseCommandError {
+    // the command didn't start with CMD
+    MissingPrefix,
+    // the command was addressed to another team
+    WrongTeam(String),
+    // there was nothing after the team ID
+    MissingCommand,
+    // the command name wasn't recognised
+    UnknownCommand(String),
+    // the command had the wrong number of arguments
+    WrongArgCount(String),
+    // one of the command's arguments wasn't valid
+    InvalidArgument { command: String, arg: String },
+}
+
+impl fmt::Display for ParseCommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingPrefix => write!(f, "Commands must start with CMD,{TEAM_ID}"),
+            Self::WrongTeam(team) => write!(f, "Team ID {team:?} isn't ours ({TEAM_ID})"),
+            Self::MissingCommand => write!(f, "Missing the command name"),
+            Self::UnknownCommand(cmd) => write!(f, "Unknown command {cmd:?}"),
+            Self::WrongArgCount(cmd) => write!(f, "Wrong number of arguments for {cmd}"),
+            Self::InvalidArgument { command, arg } => {
+                write!(f, "Invalid argument {arg:?} for {command}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseCommandError {}
+
+/// Parse a single argument of the command `command`
+fn parse_arg<T: FromStr>(command: &str, arg: &str) -> Result<T, ParseCommandError> {
+    arg.parse().map_err(|_| ParseCommandError::InvalidArgument {
+        command: command.to_string(),
+        arg: arg.to_string(),
+    })
+}
+
+impl FromStr for Command {
+    type Err = ParseCommandError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use ParseCommandError::*;
+
+        let fields: Vec<&str> = s.trim().split(',').collect();
+        let fields = match fields.as_slice() {
+            ["CMD", team, rest @ ..] if *team == TEAM_ID.to_string() => rest,
+            ["CMD", team, ..] => return Err(WrongTeam(team.to_string())),
+            _ => return Err(MissingPrefix),
+        };
+
+        let cmd = match fields {
+            [] | [""] => return Err(MissingCommand),
+            ["CX", arg] => Command::TelemetryEnable(parse_arg("CX", arg)?),
+            ["ST", "GPS"] => Command::SetTime(TimeArg::Gps),
+            ["ST", time] => Command::SetTime(TimeArg::Utc(parse_arg("ST", time)?)),
+            ["SIM", mode] => Command::SimulationMode(parse_arg("SIM", mode)?),
+            ["SIMP", pressure] => Command::SimulatedPressure(parse_arg("SIMP", pressure)?),
+            ["CAL"] => Command::Calibrate,
+            ["OPTIONAL", "RESET"] => Command::Reset,
+            ["OPTIONAL", "ACTION", action] => Command::Action(parse_arg("ACTION", action)?),
+            ["OPTIONAL", "SETSTATE", target, state] => {
+                let state = match parse_arg("SETSTATE", target)? {
+                    Target::Container => SetState::Container(parse_arg("SETSTATE", state)?),
+                    Target::Payload => SetState::Payload(parse_arg("SETSTATE", state)?),
+                };
+                Command::SetState(state)
+            }
+            ["OPTIONAL", "RESET" | "ACTION" | "SETSTATE", ..] => {
+                return Err(WrongArgCount(fields[1].to_string()))
+            }
+            ["OPTIONAL", sub] => match sub.split_once('.') {
+                Some(("CAM", arg)) => Command::CamEnable(parse_arg("CAM", arg)?),
+                Some(("SOUND", arg)) => Command::SoundEnable(parse_arg("SOUND", arg)?),
+                Some(("FLAP", arg)) => Command::Flaps(parse_arg("FLAP", arg)?),
+                Some(("HS", arg)) => Command::HeatShield(parse_arg("HS", arg)?),
+                Some(("CHUTE", arg)) => Command::Parachute(parse_arg("CHUTE", arg)?),
+                Some(("PROBE", arg)) => Command::Probe(parse_arg("PROBE", arg)?),
+                Some(("FLAG", arg)) => Command::Flag(parse_arg("FLAG", arg)?),
+                _ => return Err(UnknownCommand(format!("OPTIONAL,{sub}"))),
+            },
+            [name @ ("CX" | "ST" | "SIM" | "SIMP" | "CAL" | "OPTIONAL"), ..] => {
+                return Err(WrongArgCount(name.to_string()))
+            }
+            [name, ..] => return Err(UnknownCommand(name.to_string())),
+        };
+
+        Ok(cmd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_round_trip() {
+        let cmds = [
+            "CMD,1047,CX,ON",
+            "CMD,1047,CX,OFF",
+            "CMD,1047,ST,13:35:59",
+            "CMD,1047,ST,GPS",
+            "CMD,1047,SIM,ENABLE",
+            "CMD,1047,SIM,ACTIVATE",
+            "CMD,1047,SIM,DISABLE",
+            "CMD,1047,SIMP,101325",
+            "CMD,1047,CAL",
+            "CMD,1047,OPTIONAL,RESET",
+            "CMD,1047,OPTIONAL,ACTION,BEACON",
+            "CMD,1047,OPTIONAL,SETSTATE,C,WAIT_PARA",
+            "CMD,1047,OPTIONAL,SETSTATE,P,ON_GROUND",
+            "CMD,1047,OPTIONAL,CAM.OFF",
+            "CMD,1047,OPTIONAL,SOUND.ON",
+            "CMD,1047,OPTIONAL,FLAP.CLOSE",
+            "CMD,1047,OPTIONAL,HS.OPEN",
+            "CMD,1047,OPTIONAL,CHUTE.OPEN",
+            "CMD,1047,OPTIONAL,PROBE.HOLD",
+            "CMD,1047,OPTIONAL,FLAG.RAISE",
+        ];
+
+        for s in cmds {
+            let cmd: Command = s.parse().unwrap();
+            assert_eq!(cmd.to_string(), s);
+        }
+    }
+
+    #[test]
+    fn test_command_parse() {
+        assert_eq!(
+            "CMD,1047,SIMP,101325\n".parse(),
+            Ok(Command::SimulatedPressure(101325))
+        );
+        assert_eq!(
+            "CMD,1047,OPTIONAL,SETSTATE,P,WAIT_GND".parse(),
+            Ok(Command::SetState(SetState::Payload(
+                PayloadState::WaitGround
+            )))
+        );
+    }
+
+    #[test]
+    fn test_command_parse_fails() {
+        use ParseCommandError::*;
+
+        let cases = [
+            ("CX,ON", MissingPrefix),
+            ("CMD,1234,CX,ON", WrongTeam("1234".to_string())),
+            ("CMD,1047", MissingCommand),
+            ("CMD,1047,FOO", UnknownCommand("FOO".to_string())),
+            ("CMD,1047,CX", WrongArgCount("CX".to_string())),
+            ("CMD,1047,CAL,NOW", WrongArgCount("CAL".to_string())),
+            (
+                "CMD,1047,OPTIONAL,RESET,NOW",
+                WrongArgCount("RESET".to_string()),
+            ),
+            (
+                "CMD,1047,OPTIONAL,LASER.ON",
+                UnknownCommand("OPTIONAL,LASER.ON".to_string()),
+            ),
+            (
+                "CMD,1047,CX,MAYBE",
+                InvalidArgument {
+                    command: "CX".to_string(),
+                    arg: "MAYBE".to_string(),
+                },
+            ),
+            (
+                "CMD,1047,ST,24:00:00",
+                InvalidArgument {
+                    command: "ST".to_string(),
+                    arg: "24:00:00".to_string(),
+                },
+            ),
+            (
+                "CMD,1047,SIMP,-5",
+                InvalidArgument {
+                    command: "SIMP".to_string(),
+                    arg: "-5".to_string(),
+                },
+            ),
+        ];
+
+        for (s, err) in cases {
+            assert_eq!(s.parse::<Command>(), Err(err), "{s}");
+        }
+    }
+
+    #[test]
+    fn test_command_echo() {
+        assert_eq!(Command::TelemetryEnable(Enabled::On).echo(), "CXON");
+        assert_eq!(Command::SimulatedPressure(101325).echo(), "SIMP101325");
+        assert_eq!(Command::Flaps(OpenClose::Open).echo(), "OPTIONALFLAP.OPEN");
+    }
+}
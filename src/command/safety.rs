@@ -0,0 +1,176 @@
+use super::{Command, HoldRelease, OpenClose};
+use crate::telemetry::Telemetry;
+
+/// How much care is needed before a command is sent
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Safeguard {
+    /// Send straight away
+    None,
+    /// Ask the operator to confirm before sending
+    Confirm,
+    /// The command must be armed then fired as two separate actions
+    ArmThenFire,
+}
+
+/// Whether a command is safe to send given the latest telemetry
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Verdict {
+    Allow,
+    /// Allowed, but the operator should know why it might be a bad idea
+    Warn(String),
+    /// Refused unless the operator overrides it
+    Block(String),
+}
+
+/// The rules deciding which commands need confirming and when they are refused
+#[derive(Debug, Clone)]
+pub struct SafetyPolicy {
+    /// Above this altitude in metres the CanSat is assumed to be flying
+    pub max_ground_altitude: f64,
+
+    /// The flight software states in which the CanSat is waiting on the ground
+    pub ground_states: Vec<String>,
+}
+
+impl Default for SafetyPolicy {
+    fn default() -> Self {
+        Self {
+            max_ground_altitude: 10.0,
+            ground_states: vec!["IDLE".to_string(), "LAUNCH_WAIT".to_string()],
+        }
+    }
+}
+
+impl SafetyPolicy {
+    /// How much care is needed before sending the command
+    pub fn safeguard(&self, cmd: &Command) -> Safeguard {
+        match cmd {
+            // anything which releases part of the CanSat can't be undone
+            Command::Probe(HoldRelease::Release)
+            | Command::HeatShield(OpenClose::Open)
+            | Command::Parachute(OpenClose::Open) => Safeguard::ArmThenFire,
+            Command::Calibrate
+            | Command::Reset
+            | Command::SetState(_)
+            | Command::Probe(_)
+            | Command::HeatShield(_)
+            | Command::Parachute(_) => Safeguard::Confirm,
+            _ => Safeguard::None,
+        }
+    }
+
+    fn on_ground(&self, telem: &Telemetry) -> bool {
+        let state = telem.state.to_string();
+        self.ground_states
+            .iter()
+            .any(|s| s.eq_ignore_ascii_case(&state))
+    }
+
+    /// Check the command against the latest telemetry, if there is any
+    pub fn check(&self, cmd: &Command, telem: Option<&Telemetry>) -> Verdict {
+        let Some(telem) = telem else {
+            return match self.safeguard(cmd) {
+                Safeguard::None => Verdict::Allow,
+                _ => Verdict::Warn("No telemetry received to check against".to_string()),
+            };
+        };
+
+        let flying = telem.altitude > self.max_ground_altitude;
+        match cmd {
+            Command::Calibrate if flying => Verdict::Block(format!(
+                "Altitude is {:.1} m, calibrating now would zero it in flight",
+                telem.altitude
+            )),
+            Command::Calibrate if !self.on_ground(telem) => Verdict::Block(format!(
+                "State is {}, calibration is only allowed while waiting on the ground",
+                telem.state
+            )),
+            Command::Reset if flying => Verdict::Warn(format!(
+                "Altitude is {:.1} m, resetting will restart the flight software mid-flight",
+                telem.altitude
+            )),
+            Command::Probe(HoldRelease::Release)
+            | Command::HeatShield(OpenClose::Open)
+            | Command::Parachute(OpenClose::Open)
+                if !flying =>
+            {
+                Verdict::Warn(format!(
+                    "Altitude is only {:.1} m, the CanSat may still be on the ground",
+                    telem.altitude
+                ))
+            }
+            _ => Verdict::Allow,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::Enabled;
+    use crate::telemetry::TelemetryBuilder;
+
+    fn telem(state: &str, altitude: f64) -> Telemetry {
+        TelemetryBuilder::default()
+            .state(state)
+            .altitude(altitude)
+            .build()
+    }
+
+    #[test]
+    fn test_safeguards() {
+        let policy = SafetyPolicy::default();
+        assert_eq!(
+            policy.safeguard(&Command::TelemetryEnable(Enabled::On)),
+            Safeguard::None
+        );
+        assert_eq!(policy.safeguard(&Command::Calibrate), Safeguard::Confirm);
+        assert_eq!(
+            policy.safeguard(&Command::Probe(HoldRelease::Hold)),
+            Safeguard::Confirm
+        );
+        assert_eq!(
+            policy.safeguard(&Command::Probe(HoldRelease::Release)),
+            Safeguard::ArmThenFire
+        );
+    }
+
+    #[test]
+    fn test_calibrate_blocked_in_flight() {
+        let policy = SafetyPolicy::default();
+        assert_eq!(
+            policy.check(&Command::Calibrate, Some(&telem("IDLE", 0.5))),
+            Verdict::Allow
+        );
+        assert!(matches!(
+            policy.check(&Command::Calibrate, Some(&telem("IDLE", 250.0))),
+            Verdict::Block(_)
+        ));
+        assert!(matches!(
+            policy.check(&Command::Calibrate, Some(&telem("ASCENT", 2.0))),
+            Verdict::Block(_)
+        ));
+        assert!(matches!(
+            policy.check(&Command::Calibrate, None),
+            Verdict::Warn(_)
+        ));
+    }
+
+    #[test]
+    fn test_release_warns_on_ground() {
+        let policy = SafetyPolicy::default();
+        let release = Command::Parachute(OpenClose::Open);
+        assert!(matches!(
+            policy.check(&release, Some(&telem("IDLE", 1.0))),
+            Verdict::Warn(_)
+        ));
+        assert_eq!(
+            policy.check(&release, Some(&telem("YEETED", 400.0))),
+            Verdict::Allow
+        );
+        assert_eq!(
+            policy.check(&Command::TelemetryEnable(Enabled::On), None),
+            Verdict::Allow
+        );
+    }
+}
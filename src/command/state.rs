@@ -1,10 +1,10 @@
 use crate::as_str::AsStr;
 use enum_iterator::Sequence;
-use parse_display::Display;
+use parse_display::{Display, FromStr};
 use std::default::Default;
 
 /// Represents the argument to the
-#[derive(Default, Sequence, Display, Copy, Clone, Eq, PartialEq)]
+#[derive(Default, Sequence, Display, FromStr, Debug, Copy, Clone, Eq, PartialEq)]
 pub enum Target {
     #[default]
     #[display("C")]
@@ -25,7 +25,7 @@ impl AsStr for Target {
 }
 
 /// The various states the payload's FSM can be in
-#[derive(Sequence, Display, Default, Copy, Clone, Eq, PartialEq)]
+#[derive(Sequence, Display, FromStr, Default, Debug, Copy, Clone, Eq, PartialEq)]
 #[display(style = "SNAKE_CASE")]
 pub enum ContainerState {
     #[default]
@@ -51,7 +51,7 @@ impl AsStr for ContainerState {
 }
 
 /// The various states the payload's FSM can be in
-#[derive(Sequence, Display, Default, Copy, Clone, Eq, PartialEq)]
+#[derive(Sequence, Display, FromStr, Default, Debug, Copy, Clone, Eq, PartialEq)]
 #[display(style = "SNAKE_CASE")]
 pub enum PayloadState {
     #[default]
@@ -20,3 +20,9 @@ pub const BAUD_RATES: [u32; 9] = [1200, 2400, 4800, 9600, 19200, 38400, 57600, 1
 
 /// The file to save the telemetry to
 pub const TELEMETRY_FILE: &str = "Flight_1047.csv";
+
+/// The file to save the container's telemetry to
+pub const CONTAINER_TELEMETRY_FILE: &str = "Flight_1047_C.csv";
+
+/// The file to save the probe's telemetry to
+pub const PROBE_TELEMETRY_FILE: &str = "Flight_1047_P.csv";
@@ -2,9 +2,13 @@
 
 pub mod app;
 pub mod as_str;
+pub mod command;
 pub mod constants;
 pub mod geodesic;
 pub mod listener;
 pub mod reader;
+pub mod replay;
+pub mod simulation;
+pub mod synth;
 pub mod telemetry;
 pub mod xbee;
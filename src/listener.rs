@@ -3,10 +3,14 @@ use std::net::TcpListener;
 use std::sync::mpsc::Sender;
 
 use crate::app::ReceivedPacket;
-use crate::xbee::{RxPacket, XbeePacket};
+use crate::constants::CONTAINER_ADDR;
+use crate::xbee::{RxFrame, RxPacket, XbeePacket};
 use anyhow::{bail, Result};
 
-/// Telem
+/// Receives telemetry lines over TCP.
+///
+/// The lines don't say which radio sent them, so they are all received as if from the container
+/// (`CONTAINER_ADDR`) and shown in its telemetry stream.
 pub struct TelemetryListener {
     tx: Sender<ReceivedPacket>,
 }
@@ -41,12 +45,12 @@ impl TelemetryListener {
                             data: vec![],
                             checksum: 0,
                         },
-                        frame: RxPacket {
-                            src_addr: 0xFFFF,
+                        frame: RxFrame::Rx16(RxPacket {
+                            src_addr: CONTAINER_ADDR,
                             rssi: 0,
                             options: 0,
                             data: vec![],
-                        },
+                        }),
                         telem,
                     };
                     if let Err(e) = self.tx.send(packet) {
@@ -5,10 +5,14 @@ use std::thread;
 use std::time::Duration;
 
 use crate::app::ReceivedPacket;
-use crate::xbee::{RxPacket, XbeePacket};
+use crate::constants::CONTAINER_ADDR;
+use crate::xbee::{RxFrame, RxPacket, XbeePacket};
 use anyhow::Result;
 
-/// Telem
+/// Plays back the telemetry lines in `test_data/test_data.txt`, one a second.
+///
+/// The lines don't say which radio sent them, so they are all received as if from the container
+/// (`CONTAINER_ADDR`) and shown in its telemetry stream.
 pub struct TelemetryReader {
     tx: Sender<ReceivedPacket>,
 }
@@ -44,12 +48,12 @@ impl TelemetryReader {
                             data: vec![],
                             checksum: 0,
                         },
-                        frame: RxPacket {
-                            src_addr: 0xFFFF,
+                        frame: RxFrame::Rx16(RxPacket {
+                            src_addr: CONTAINER_ADDR,
                             rssi: 0,
                             options: 0,
                             data: vec![],
-                        },
+                        }),
                         telem,
                     };
                     if let Err(e) = self.tx.send(packet) {
@@ -0,0 +1,59 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::Duration;
+
+use crate::app::ReceivedPacket;
+use crate::xbee::{ApiMode, DecodeEvent, FrameDecoder};
+use anyhow::Result;
+
+/// Replays a raw radio data log, e.g. `radio_data.raw`, as if it was coming from the radio
+pub struct RadioReplay {
+    tx: Sender<ReceivedPacket>,
+    path: PathBuf,
+    mode: ApiMode,
+}
+
+impl RadioReplay {
+    pub fn new(tx: Sender<ReceivedPacket>, path: impl Into<PathBuf>, mode: ApiMode) -> Self {
+        Self {
+            tx,
+            path: path.into(),
+            mode,
+        }
+    }
+
+    pub fn run(&mut self) -> Result<()> {
+        // the radio hands us data in small chunks, so replay it the same way
+        const CHUNK_SIZE: usize = 64;
+
+        let mut data = vec![];
+        File::open(&self.path)?.read_to_end(&mut data)?;
+        tracing::info!("Replaying {} bytes from {:?}", data.len(), self.path);
+
+        let mut decoder = FrameDecoder::new(self.mode);
+        let events = data
+            .chunks(CHUNK_SIZE)
+            .flat_map(|chunk| decoder.feed(chunk))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .chain(decoder.flush());
+
+        for event in events {
+            let is_frame = matches!(event, DecodeEvent::Frame(_));
+            if let Err(e) = self.tx.send(event.into()) {
+                tracing::warn!("Encountered error sending replayed packet over the channel: {e:?}");
+                break;
+            }
+
+            // space the frames out so the replay can be watched
+            if is_frame {
+                thread::sleep(Duration::from_millis(100));
+            }
+        }
+
+        Ok(())
+    }
+}
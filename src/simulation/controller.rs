@@ -0,0 +1,287 @@
+use crate::command::{Command, SimMode};
+use std::time::{Duration, Instant};
+
+/// The different states simulation playback can be in
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SimState {
+    /// Loaded but not started
+    Idle,
+    /// `SIM,ENABLE` and `SIM,ACTIVATE` have been sent, waiting for the CanSat to switch modes
+    Enabling,
+    /// Sending a pressure value every period
+    Streaming,
+    /// Stopped part way through, `SIM,DISABLE` has been sent
+    Paused,
+    /// Every pressure value was sent
+    Finished,
+    /// Stopped by the user
+    Cancelled,
+}
+
+/// Plays back simulated pressure values one every `period`.
+///
+/// The controller doesn't send anything itself, every method which changes the state returns the
+/// commands to send, and `poll` is called with the current time to get the next pressure value
+/// once it is due.
+#[derive(Debug)]
+pub struct SimulationController {
+    /// How long between sending each pressure value
+    pub period: Duration,
+
+    pressures: Vec<u32>,
+    state: SimState,
+
+    /// The index of the next pressure value to send
+    position: usize,
+
+    /// When the next state change or pressure value is due
+    next_due: Instant,
+}
+
+impl SimulationController {
+    pub fn new(pressures: Vec<u32>) -> Self {
+        Self {
+            period: Duration::from_secs(1),
+            pressures,
+            state: SimState::Idle,
+            position: 0,
+            next_due: Instant::now(),
+        }
+    }
+
+    pub fn pressures(&self) -> &[u32] {
+        &self.pressures
+    }
+
+    pub fn state(&self) -> SimState {
+        self.state
+    }
+
+    /// The index of the next pressure value to send, which is also how many have been sent
+    /// when playing from the start
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Is playback running, or paused part way through?
+    pub fn is_active(&self) -> bool {
+        matches!(
+            self.state,
+            SimState::Enabling | SimState::Streaming | SimState::Paused
+        )
+    }
+
+    /// Move playback to a different pressure value, clamped to the end of the values
+    pub fn seek(&mut self, position: usize) {
+        self.position = position.min(self.pressures.len());
+    }
+
+    /// Switch the CanSat into simulation mode, waiting a period before streaming
+    fn enable(&mut self, now: Instant) -> Vec<Command> {
+        self.state = SimState::Enabling;
+        self.next_due = now + self.period;
+        vec![
+            Command::SimulationMode(SimMode::Enable),
+            Command::SimulationMode(SimMode::Activate),
+        ]
+    }
+
+    /// Start playback from the current position, or from the beginning if every value was sent
+    pub fn start(&mut self, now: Instant) -> Vec<Command> {
+        if self.is_active() {
+            return vec![];
+        }
+        if self.position >= self.pressures.len() {
+            self.position = 0;
+        }
+
+        tracing::info!("Starting simulation playback at {}", self.position);
+        self.enable(now)
+    }
+
+    pub fn pause(&mut self) -> Vec<Command> {
+        if !matches!(self.state, SimState::Enabling | SimState::Streaming) {
+            return vec![];
+        }
+
+        tracing::info!("Pausing simulation playback at {}", self.position);
+        self.state = SimState::Paused;
+        vec![Command::SimulationMode(SimMode::Disable)]
+    }
+
+    pub fn resume(&mut self, now: Instant) -> Vec<Command> {
+        if self.state != SimState::Paused {
+            return vec![];
+        }
+
+        tracing::info!("Resuming simulation playback at {}", self.position);
+        self.enable(now)
+    }
+
+    /// Stop playback, taking the CanSat out of simulation mode if it is still in it
+    pub fn cancel(&mut self) -> Vec<Command> {
+        let cmds = match self.state {
+            SimState::Enabling | SimState::Streaming => {
+                vec![Command::SimulationMode(SimMode::Disable)]
+            }
+            SimState::Paused => vec![],
+            _ => return vec![],
+        };
+
+        tracing::info!("Cancelling simulation playback at {}", self.position);
+        self.state = SimState::Cancelled;
+        cmds
+    }
+
+    /// Returns the commands which are due at `now`, at most one pressure value is sent per call.
+    /// Once every value is sent, `SIM,DISABLE` is sent a period after the last.
+    pub fn poll(&mut self, now: Instant) -> Vec<Command> {
+        if now < self.next_due {
+            return vec![];
+        }
+
+        match self.state {
+            SimState::Enabling => {
+                self.state = SimState::Streaming;
+                self.poll(now)
+            }
+            SimState::Streaming => {
+                let Some(pressure) = self.pressures.get(self.position) else {
+                    tracing::info!("Finished simulation playback");
+                    self.state = SimState::Finished;
+                    return vec![Command::SimulationMode(SimMode::Disable)];
+                };
+                self.position += 1;
+
+                // if polling stalled for over a period, don't send a burst to catch up
+                self.next_due = if now >= self.next_due + self.period {
+                    now + self.period
+                } else {
+                    self.next_due + self.period
+                };
+                vec![Command::SimulatedPressure(*pressure)]
+            }
+            _ => vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+    const ENABLE: Command = Command::SimulationMode(SimMode::Enable);
+    const ACTIVATE: Command = Command::SimulationMode(SimMode::Activate);
+    const DISABLE: Command = Command::SimulationMode(SimMode::Disable);
+
+    #[test]
+    fn test_playback() {
+        let mut sim = SimulationController::new(vec![101325, 101300, 101250]);
+        let start = Instant::now();
+        assert_eq!(sim.poll(start), vec![]);
+
+        assert_eq!(sim.start(start), vec![ENABLE, ACTIVATE]);
+        assert_eq!(sim.state(), SimState::Enabling);
+        assert_eq!(sim.start(start), vec![]);
+
+        // the first value is sent a period after enabling
+        assert_eq!(sim.poll(start + SECOND / 2), vec![]);
+        assert_eq!(
+            sim.poll(start + SECOND),
+            vec![Command::SimulatedPressure(101325)]
+        );
+        assert_eq!(sim.state(), SimState::Streaming);
+        assert_eq!(sim.poll(start + SECOND), vec![]);
+
+        // polling late doesn't shift the schedule
+        assert_eq!(
+            sim.poll(start + SECOND * 2 + SECOND / 4),
+            vec![Command::SimulatedPressure(101300)]
+        );
+        assert_eq!(
+            sim.poll(start + SECOND * 3),
+            vec![Command::SimulatedPressure(101250)]
+        );
+        assert_eq!(sim.position(), 3);
+
+        // SIM,DISABLE is sent once the last value has had its period
+        assert_eq!(sim.poll(start + SECOND * 3), vec![]);
+        assert_eq!(sim.poll(start + SECOND * 4), vec![DISABLE]);
+        assert_eq!(sim.state(), SimState::Finished);
+        assert!(!sim.is_active());
+        assert_eq!(sim.poll(start + SECOND * 10), vec![]);
+
+        // starting again plays from the beginning
+        sim.start(start + SECOND * 10);
+        assert_eq!(
+            sim.poll(start + SECOND * 11),
+            vec![Command::SimulatedPressure(101325)]
+        );
+    }
+
+    #[test]
+    fn test_pause_and_resume() {
+        let mut sim = SimulationController::new(vec![1, 2, 3]);
+        sim.period = Duration::from_millis(500);
+        let start = Instant::now();
+
+        sim.start(start);
+        assert_eq!(
+            sim.poll(start + SECOND / 2),
+            vec![Command::SimulatedPressure(1)]
+        );
+        assert_eq!(sim.pause(), vec![DISABLE]);
+        assert_eq!(sim.pause(), vec![]);
+        assert_eq!(sim.poll(start + SECOND * 5), vec![]);
+
+        // resuming re-enables simulation mode and carries on where it left off
+        let now = start + SECOND * 5;
+        assert_eq!(sim.resume(now), vec![ENABLE, ACTIVATE]);
+        assert_eq!(sim.poll(now), vec![]);
+        assert_eq!(
+            sim.poll(now + SECOND / 2),
+            vec![Command::SimulatedPressure(2)]
+        );
+    }
+
+    #[test]
+    fn test_stalled_poll_does_not_burst() {
+        let mut sim = SimulationController::new(vec![1, 2, 3]);
+        let start = Instant::now();
+        sim.start(start);
+
+        sim.poll(start + SECOND);
+        let late = start + SECOND * 10;
+        assert_eq!(sim.poll(late), vec![Command::SimulatedPressure(2)]);
+        assert_eq!(sim.poll(late), vec![]);
+        assert_eq!(sim.poll(late + SECOND), vec![Command::SimulatedPressure(3)]);
+    }
+
+    #[test]
+    fn test_seek_and_cancel() {
+        let mut sim = SimulationController::new(vec![1, 2, 3, 4]);
+        let start = Instant::now();
+
+        sim.seek(10);
+        assert_eq!(sim.position(), 4);
+        sim.seek(2);
+        sim.start(start);
+        assert_eq!(
+            sim.poll(start + SECOND),
+            vec![Command::SimulatedPressure(3)]
+        );
+
+        assert_eq!(sim.cancel(), vec![DISABLE]);
+        assert_eq!(sim.state(), SimState::Cancelled);
+        assert_eq!(sim.cancel(), vec![]);
+        assert_eq!(sim.poll(start + SECOND * 2), vec![]);
+
+        // cancelling while paused doesn't disable simulation mode twice
+        let mut sim = SimulationController::new(vec![1, 2]);
+        sim.start(start);
+        sim.pause();
+        assert_eq!(sim.cancel(), vec![]);
+        assert_eq!(sim.state(), SimState::Cancelled);
+    }
+}
@@ -0,0 +1,224 @@
+use super::{altitude_to_pressure, SimProfile};
+use anyhow::{bail, Result};
+use rand::{distributions::Uniform, prelude::*};
+
+/// The part of the flight the CanSat is in
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum FlightPhase {
+    Ascent,
+    /// Falling before the parachute opens
+    Descent,
+    /// Falling under the parachute
+    ParachuteDescent,
+    Landed,
+}
+
+/// The parameters of a simple flight, used to build SIMP profiles for testing edge cases such
+/// as a low apogee or a parachute which never opens
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlightProfile {
+    /// The altitude of the launch site above sea level, in metres
+    pub launch_altitude: f64,
+
+    /// How fast the rocket climbs, in m/s
+    pub ascent_rate: f64,
+
+    /// The highest altitude reached, in metres above the launch site
+    pub apogee: f64,
+
+    /// How fast the CanSat falls before the parachute opens, in m/s
+    pub descent_rate: f64,
+
+    /// The altitude the parachute opens at, in metres above the launch site
+    pub parachute_altitude: f64,
+
+    /// How fast the CanSat falls under the parachute, in m/s
+    pub parachute_descent_rate: f64,
+
+    /// How long to keep sending values after landing, in seconds
+    pub landing_dwell: f64,
+
+    /// The largest random error added to each altitude, in metres
+    pub noise: f64,
+
+    /// Seeds the noise so the same parameters always build the same profile
+    pub seed: u64,
+}
+
+impl Default for FlightProfile {
+    fn default() -> Self {
+        Self {
+            launch_altitude: 0.0,
+            ascent_rate: 100.0,
+            apogee: 725.0,
+            descent_rate: 15.0,
+            parachute_altitude: 200.0,
+            parachute_descent_rate: 5.0,
+            landing_dwell: 10.0,
+            noise: 0.5,
+            seed: 1047,
+        }
+    }
+}
+
+impl FlightProfile {
+    /// The longest flight a profile can be built for, in seconds, one value is sent per second
+    pub const MAX_DURATION: f64 = 3600.0;
+
+    /// Check the parameters describe a flight which lands
+    pub fn validate(&self) -> Result<()> {
+        if self.ascent_rate <= 0.0 || self.descent_rate <= 0.0 || self.parachute_descent_rate <= 0.0
+        {
+            bail!("Ascent and descent rates must be positive");
+        }
+        if self.apogee <= 0.0 {
+            bail!("Apogee must be above the launch site");
+        }
+        if !(0.0..=self.apogee).contains(&self.parachute_altitude) {
+            bail!("The parachute must open between the apogee and the ground");
+        }
+        if self.landing_dwell < 0.0 || self.noise < 0.0 {
+            bail!("Landing dwell and noise can't be negative");
+        }
+        // also catches rates so slow the flight never ends
+        let duration = self.duration();
+        if duration.is_nan() || duration > Self::MAX_DURATION {
+            bail!(
+                "The flight lasts {duration:.0}s, longer than the limit of {}s",
+                Self::MAX_DURATION
+            );
+        }
+
+        Ok(())
+    }
+
+    /// The time each phase ends, in seconds after launch
+    fn phase_ends(&self) -> [f64; 3] {
+        let apogee = self.apogee / self.ascent_rate;
+        let parachute = apogee + (self.apogee - self.parachute_altitude) / self.descent_rate;
+        let landed = parachute + self.parachute_altitude / self.parachute_descent_rate;
+        [apogee, parachute, landed]
+    }
+
+    /// How long the flight lasts including the landing dwell, in seconds
+    pub fn duration(&self) -> f64 {
+        self.phase_ends()[2] + self.landing_dwell
+    }
+
+    /// The phase of the flight and altitude above the launch site `t` seconds after launch
+    pub fn at(&self, t: f64) -> (FlightPhase, f64) {
+        let [apogee, parachute, landed] = self.phase_ends();
+        if t < apogee {
+            (FlightPhase::Ascent, self.ascent_rate * t)
+        } else if t < parachute {
+            (
+                FlightPhase::Descent,
+                self.apogee - self.descent_rate * (t - apogee),
+            )
+        } else if t < landed {
+            (
+                FlightPhase::ParachuteDescent,
+                self.parachute_altitude - self.parachute_descent_rate * (t - parachute),
+            )
+        } else {
+            (FlightPhase::Landed, 0.0)
+        }
+    }
+
+    /// Build a profile with one pressure value per second of the flight
+    pub fn build(&self) -> Result<SimProfile> {
+        self.validate()?;
+
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let noise = Uniform::new_inclusive(-self.noise, self.noise);
+        let pressures = (0..=self.duration().ceil() as usize)
+            .map(|t| {
+                let (_, altitude) = self.at(t as f64);
+                altitude_to_pressure(self.launch_altitude + altitude + rng.sample(noise))
+            })
+            .collect();
+
+        Ok(SimProfile {
+            pressures,
+            errors: vec![],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::pressure_to_altitude;
+
+    #[test]
+    fn test_flight_phases() {
+        let profile = FlightProfile {
+            apogee: 700.0,
+            parachute_altitude: 200.0,
+            ..Default::default()
+        };
+
+        assert_eq!(profile.at(0.0), (FlightPhase::Ascent, 0.0));
+        assert_eq!(profile.at(7.0), (FlightPhase::Descent, 700.0));
+        assert_eq!(profile.at(9.0), (FlightPhase::Descent, 670.0));
+        assert_eq!(
+            profile.at(7.0 + 500.0 / 15.0 + 10.0).0,
+            FlightPhase::ParachuteDescent
+        );
+        assert_eq!(profile.at(1000.0), (FlightPhase::Landed, 0.0));
+        assert!((profile.duration() - (7.0 + 500.0 / 15.0 + 40.0 + 10.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_build() {
+        // reaching apogee on a whole second so it is sampled
+        let profile = FlightProfile {
+            launch_altitude: 100.0,
+            ascent_rate: 145.0,
+            noise: 0.0,
+            ..Default::default()
+        };
+        let sim = profile.build().unwrap();
+        assert_eq!(sim.pressures.len(), profile.duration().ceil() as usize + 1);
+
+        // the apogee is at the launch site's altitude plus the flight's apogee
+        let (_, apogee) = sim.apogee().unwrap();
+        assert!((apogee - 825.0).abs() < 1.0);
+        assert!((pressure_to_altitude(sim.pressures[0]) - 100.0).abs() < 1.0);
+
+        // noise is repeatable
+        let noisy = FlightProfile {
+            noise: 5.0,
+            ..profile.clone()
+        };
+        assert_eq!(noisy.build().unwrap(), noisy.build().unwrap());
+        assert_ne!(noisy.build().unwrap(), sim);
+    }
+
+    #[test]
+    fn test_invalid_profiles() {
+        let invalid = [
+            FlightProfile {
+                ascent_rate: 0.0,
+                ..Default::default()
+            },
+            FlightProfile {
+                parachute_altitude: 1000.0,
+                ..Default::default()
+            },
+            FlightProfile {
+                noise: -1.0,
+                ..Default::default()
+            },
+            FlightProfile {
+                ascent_rate: 0.01,
+                apogee: 5000.0,
+                ..Default::default()
+            },
+        ];
+
+        for profile in invalid {
+            assert!(profile.build().is_err(), "{profile:?}");
+        }
+    }
+}
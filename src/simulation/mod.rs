@@ -0,0 +1,9 @@
+mod controller;
+mod flight;
+mod profile;
+mod verifier;
+
+pub use controller::{SimState, SimulationController};
+pub use flight::{FlightPhase, FlightProfile};
+pub use profile::{altitude_to_pressure, pressure_to_altitude, ProfileError, SimProfile};
+pub use verifier::{Discrepancy, Sample, SentSimp, SimVerifier};
@@ -0,0 +1,268 @@
+use crate::constants::{SEALEVEL_HPA, TEAM_ID_STR};
+use crate::telemetry::Telemetry;
+use std::fmt;
+use std::time::Duration;
+
+/// Convert a pressure in Pa to an altitude in metres
+pub fn pressure_to_altitude(pressure: u32) -> f64 {
+    // Adapted from readAltitude
+    // Equation taken from BMP180 datasheet (page 16):
+    //  http://www.adafruit.com/datasheets/BST-BMP180-DS000-09.pdf
+
+    // Note that using the equation from wikipedia can give bad results
+    // at high altitude. See this thread for more information:
+    //  http://forums.adafruit.com/viewtopic.php?f=22&t=58064
+    let simp_hpa = (pressure as f64) / 100.0;
+    44330.0 * (1.0 - (simp_hpa / SEALEVEL_HPA).powf(0.1903))
+}
+
+/// Convert an altitude in metres to a pressure in Pa
+pub fn altitude_to_pressure(altitude: f64) -> u32 {
+    // inverted form of pressure_to_altitude
+    let presssure_hpa = SEALEVEL_HPA * (1.0 - altitude / 44330.0).powf(1.0 / 0.1903);
+    (presssure_hpa * 100.0) as u32
+}
+
+/// A line of a profile which couldn't be parsed
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ProfileError {
+    /// The line number, starting from 1
+    pub line: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Line {}: {}", self.line, self.reason)
+    }
+}
+
+/// Which column of a CSV holds the profile
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Column {
+    Pressure(usize),
+    Altitude(usize),
+}
+
+impl Column {
+    /// Find the column to use from a CSV header, pressure is preferred over altitude
+    fn from_header(line: &str) -> Option<Self> {
+        let names: Vec<String> = line
+            .split(',')
+            .map(|name| name.trim().to_ascii_uppercase())
+            .collect();
+        let find = |prefix: &str| names.iter().position(|name| name.starts_with(prefix));
+
+        find("PRESSURE")
+            .map(Self::Pressure)
+            .or_else(|| find("ALTITUDE").map(Self::Altitude))
+    }
+
+    fn parse(&self, line: &str) -> Result<u32, String> {
+        let (idx, name) = match self {
+            Column::Pressure(idx) => (*idx, "pressure"),
+            Column::Altitude(idx) => (*idx, "altitude"),
+        };
+        let Some(field) = line.split(',').nth(idx) else {
+            return Err(format!("Missing the {name} column"));
+        };
+        let Ok(value) = field.trim().parse::<f64>() else {
+            return Err(format!("Invalid {name} {:?}", field.trim()));
+        };
+
+        match self {
+            Column::Pressure(_) if value >= 0.0 => Ok(value.round() as u32),
+            Column::Pressure(_) => Err(format!("Negative pressure {value}")),
+            Column::Altitude(_) => Ok(altitude_to_pressure(value)),
+        }
+    }
+}
+
+/// Parse a `CMD,<TEAM_ID>,SIMP,<pressure>` line, the organisers use `$` in place of the team ID
+fn parse_simp_cmd(line: &str) -> Option<Result<u32, String>> {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    let ["CMD", team_id, "SIMP", pressure] = fields.as_slice() else {
+        return None;
+    };
+
+    if *team_id != "$" && *team_id != TEAM_ID_STR {
+        return Some(Err(format!("Command for team {team_id}")));
+    }
+    Some(
+        pressure
+            .parse()
+            .map_err(|_| format!("Invalid pressure {pressure:?}")),
+    )
+}
+
+/// A simulated pressure profile, sent to the CanSat one value at a time.
+///
+/// Profiles can be written in any of:
+/// - the official competition format, `CMD,$,SIMP,101325` with `#` comments
+/// - one pressure in Pa per line
+/// - telemetry, taking the pressure from the altitude
+/// - a CSV with a header naming a `PRESSURE` or `ALTITUDE` column
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SimProfile {
+    /// The pressures to send in Pa
+    pub pressures: Vec<u32>,
+
+    /// Every line which couldn't be parsed
+    pub errors: Vec<ProfileError>,
+}
+
+impl SimProfile {
+    /// Parse a profile, any lines which fail to parse are skipped and recorded in `errors`
+    pub fn parse(src: &str) -> Self {
+        let mut profile = Self::default();
+        let mut column: Option<Column> = None;
+
+        for (i, line) in src.lines().enumerate() {
+            let line_no = i + 1;
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let pressure = if let Some(column) = column {
+                column.parse(line)
+            } else if let Some(pressure) = parse_simp_cmd(line) {
+                pressure
+            } else if let Ok(pressure) = line.parse::<u32>() {
+                Ok(pressure)
+            } else if let Ok(telem) = line.parse::<Telemetry>() {
+                Ok(altitude_to_pressure(telem.altitude))
+            } else if let Some(header) = Column::from_header(line) {
+                // only the first row can be a header
+                if profile.pressures.is_empty() {
+                    column = Some(header);
+                    continue;
+                }
+                Err("Unexpected CSV header".to_string())
+            } else {
+                Err(format!("Unrecognised line {line:?}"))
+            };
+
+            match pressure {
+                Ok(pressure) => profile.pressures.push(pressure),
+                Err(reason) => {
+                    tracing::warn!("Failed to parse line {line_no} of SIMP profile - {reason}");
+                    profile.errors.push(ProfileError {
+                        line: line_no,
+                        reason,
+                    });
+                }
+            }
+        }
+
+        profile
+    }
+
+    /// Write the profile in the official competition format, which `parse` reads back
+    pub fn to_official_format(&self) -> String {
+        let mut out = format!("# {} simulated pressure values\n", self.pressures.len());
+        for pressure in &self.pressures {
+            out += &format!("CMD,$,SIMP,{pressure}\n");
+        }
+        out
+    }
+
+    /// How long the profile takes to send with a value sent every `period`
+    pub fn duration(&self, period: Duration) -> Duration {
+        period * self.pressures.len() as u32
+    }
+
+    pub fn min_pressure(&self) -> Option<u32> {
+        self.pressures.iter().copied().min()
+    }
+
+    pub fn max_pressure(&self) -> Option<u32> {
+        self.pressures.iter().copied().max()
+    }
+
+    /// The highest altitude in the profile in metres and the index of its pressure value
+    pub fn apogee(&self) -> Option<(usize, f64)> {
+        let (idx, pressure) = self
+            .pressures
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, pressure)| **pressure)?;
+
+        Some((idx, pressure_to_altitude(*pressure)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::TelemetryBuilder;
+
+    #[test]
+    fn test_official_format() {
+        let src = "
+            # SIMP profile for the 2022 competition
+            CMD,$,SIMP,101325
+            CMD,$,SIMP,101300   # launch
+            CMD,1047,SIMP,101200
+            CMD,1000,SIMP,101100
+            CMD,$,SIMP,high
+        ";
+        let profile = SimProfile::parse(src);
+        assert_eq!(profile.pressures, vec![101325, 101300, 101200]);
+
+        let written = SimProfile::parse(&profile.to_official_format());
+        assert_eq!(written.pressures, profile.pressures);
+        assert!(written.errors.is_empty());
+
+        assert_eq!(
+            profile.errors,
+            vec![
+                ProfileError {
+                    line: 6,
+                    reason: "Command for team 1000".to_string(),
+                },
+                ProfileError {
+                    line: 7,
+                    reason: "Invalid pressure \"high\"".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bare_values_and_telemetry() {
+        let telem = TelemetryBuilder::default().altitude(0.0).build();
+        let src = format!("101325\n101000\n{telem}\nnonsense");
+        let profile = SimProfile::parse(&src);
+        assert_eq!(profile.pressures.len(), 3);
+        assert!(profile.pressures[2].abs_diff(101325) <= 1);
+        assert_eq!(profile.errors.len(), 1);
+        assert_eq!(profile.errors[0].line, 4);
+    }
+
+    #[test]
+    fn test_csv_with_header() {
+        let src = "time,Altitude (m)\n0,0\n1,500.0\n2,250\n3,";
+        let profile = SimProfile::parse(src);
+        assert_eq!(profile.pressures.len(), 3);
+        assert_eq!(profile.errors.len(), 1);
+
+        let (idx, apogee) = profile.apogee().unwrap();
+        assert_eq!(idx, 1);
+        assert!((apogee - 500.0).abs() < 1.0);
+        assert_eq!(profile.max_pressure(), Some(profile.pressures[0]));
+        assert_eq!(profile.duration(Duration::from_secs(1)).as_secs(), 3);
+
+        // pressure columns are used over altitude
+        let profile = SimProfile::parse("ALTITUDE,PRESSURE\n10,101325\n20,101200.4");
+        assert_eq!(profile.pressures, vec![101325, 101200]);
+    }
+
+    #[test]
+    fn test_altitude_round_trip() {
+        for altitude in [0.0, 100.0, 725.0] {
+            let pressure = altitude_to_pressure(altitude);
+            assert!((pressure_to_altitude(pressure) - altitude).abs() < 1.0);
+        }
+    }
+}
@@ -0,0 +1,375 @@
+use super::pressure_to_altitude;
+use crate::telemetry::{Mode, Telemetry};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// A way the CanSat's telemetry shows it isn't using the simulated pressure values
+#[derive(Debug, Clone, PartialEq)]
+pub enum Discrepancy {
+    /// Telemetry arrived while streaming without the simulation mode flag
+    NotSimulationMode,
+    /// The reported pressure is too far from the last pressure value the CanSat echoed
+    Diverged { expected: u32, reported: f64 },
+    /// A pressure value was echoed, but later than the lag threshold
+    Lagging { pressure: u32, lag: Duration },
+    /// A pressure value wasn't echoed within the lag threshold
+    NotEchoed { pressure: u32 },
+}
+
+impl fmt::Display for Discrepancy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Discrepancy::NotSimulationMode => write!(f, "CanSat is not in simulation mode"),
+            Discrepancy::Diverged { expected, reported } => write!(
+                f,
+                "Reported pressure {reported:.0} Pa diverged from the simulated {expected} Pa"
+            ),
+            Discrepancy::Lagging { pressure, lag } => write!(
+                f,
+                "SIMP{pressure} was echoed after {:.1}s",
+                lag.as_secs_f64()
+            ),
+            Discrepancy::NotEchoed { pressure } => write!(f, "SIMP{pressure} was never echoed"),
+        }
+    }
+}
+
+/// A pressure value sent to the CanSat
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SentSimp {
+    pub pressure: u32,
+    /// Seconds since verification started
+    pub time: f64,
+    sent_at: Instant,
+    /// How long it took to appear in CMD_ECHO
+    pub lag: Option<Duration>,
+    /// The CanSat has been echoing this value since before it was sent, so it can't be seen
+    /// arriving until the echo changes
+    pub repeats_echo: bool,
+    /// Has it been flagged for taking too long to echo
+    flagged: bool,
+}
+
+/// The CanSat's telemetry compared against the pressure value it should be using
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Sample {
+    /// Seconds since verification started
+    pub time: f64,
+    /// The last pressure value the CanSat echoed, in Pa
+    pub expected: u32,
+    /// The pressure the CanSat reported, in Pa
+    pub reported: f64,
+    /// The altitude the CanSat reported, in metres
+    pub altitude: f64,
+    pub mode: Mode,
+}
+
+impl Sample {
+    /// The altitude the CanSat should have calculated from the expected pressure
+    pub fn expected_altitude(&self) -> f64 {
+        pressure_to_altitude(self.expected)
+    }
+}
+
+/// Correlates the pressure values sent in simulation mode with the telemetry received afterwards,
+/// flagging any sign the CanSat isn't using them.
+///
+/// Discrepancies are only returned when they start, so a CanSat stuck in flight mode is flagged
+/// once rather than for every packet, every discrepancy is kept in `log`.
+#[derive(Debug)]
+pub struct SimVerifier {
+    /// How far the reported pressure can be from the expected pressure in Pa, telemetry only
+    /// reports pressure to 0.1 kPa
+    pub max_pressure_error: f64,
+
+    /// How long a pressure value can take to be echoed, from when it was sent
+    pub max_lag: Duration,
+
+    started: Instant,
+    sent: Vec<SentSimp>,
+    samples: Vec<Sample>,
+
+    /// The index of the last pressure value echoed
+    last_echoed: Option<usize>,
+
+    /// The CMD_ECHO of the last telemetry received
+    last_echo: Option<String>,
+
+    in_sim_mode: bool,
+    diverged: bool,
+
+    log: Vec<(f64, Discrepancy)>,
+}
+
+impl SimVerifier {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            max_pressure_error: 150.0,
+            max_lag: Duration::from_secs(3),
+            started: now,
+            sent: vec![],
+            samples: vec![],
+            last_echoed: None,
+            last_echo: None,
+            in_sim_mode: true,
+            diverged: false,
+            log: vec![],
+        }
+    }
+
+    pub fn sent(&self) -> &[SentSimp] {
+        &self.sent
+    }
+
+    pub fn samples(&self) -> &[Sample] {
+        &self.samples
+    }
+
+    /// Every discrepancy found and when, in seconds since verification started
+    pub fn log(&self) -> &[(f64, Discrepancy)] {
+        &self.log
+    }
+
+    /// The mean time taken for pressure values to be echoed
+    pub fn mean_lag(&self) -> Option<Duration> {
+        let lags: Vec<Duration> = self.sent.iter().filter_map(|s| s.lag).collect();
+        let total: Duration = lags.iter().sum();
+        (!lags.is_empty()).then(|| total / lags.len() as u32)
+    }
+
+    fn secs(&self, now: Instant) -> f64 {
+        now.saturating_duration_since(self.started).as_secs_f64()
+    }
+
+    fn flag(&mut self, now: Instant, discrepancy: Discrepancy) -> Discrepancy {
+        tracing::warn!("Simulation mode discrepancy - {discrepancy}");
+        self.log.push((self.secs(now), discrepancy.clone()));
+        discrepancy
+    }
+
+    /// Record a pressure value being sent to the CanSat
+    pub fn record_sent(&mut self, pressure: u32, now: Instant) {
+        let repeats_echo = self.last_echo.as_deref() == Some(&*format!("SIMP{pressure}"));
+        self.sent.push(SentSimp {
+            pressure,
+            time: self.secs(now),
+            sent_at: now,
+            lag: None,
+            repeats_echo,
+            flagged: false,
+        });
+    }
+
+    /// Compare telemetry received at `now` against the pressure values sent
+    pub fn recv(&mut self, telem: &Telemetry, now: Instant) -> Vec<Discrepancy> {
+        let mut found = vec![];
+
+        // match the echo to the oldest unechoed value, anything older was skipped by the CanSat.
+        // Values the CanSat was already echoing when they were sent can't be matched, as the
+        // echo only shows a value arrived if it changed after the value was sent.
+        self.last_echo = Some(telem.cmd_echo.clone());
+        let from = self.last_echoed.map_or(0, |idx| idx + 1);
+        for s in &mut self.sent[from..] {
+            if s.sent_at <= now && format!("SIMP{}", s.pressure) != telem.cmd_echo {
+                s.repeats_echo = false;
+            }
+        }
+        let echoed = self.sent[from..]
+            .iter()
+            .position(|s| {
+                !s.repeats_echo
+                    && s.sent_at <= now
+                    && format!("SIMP{}", s.pressure) == telem.cmd_echo
+            })
+            .map(|i| from + i);
+        if let Some(idx) = echoed {
+            let lag = now.duration_since(self.sent[idx].sent_at);
+            self.sent[idx].lag = Some(lag);
+            self.last_echoed = Some(idx);
+
+            if lag > self.max_lag && !self.sent[idx].flagged {
+                self.sent[idx].flagged = true;
+                let pressure = self.sent[idx].pressure;
+                found.push(self.flag(now, Discrepancy::Lagging { pressure, lag }));
+            }
+        }
+
+        let in_sim_mode = telem.mode == Mode::Simulation;
+        if self.in_sim_mode && !in_sim_mode {
+            found.push(self.flag(now, Discrepancy::NotSimulationMode));
+        }
+        self.in_sim_mode = in_sim_mode;
+
+        // the CanSat can't be expected to use a value until it has echoed one
+        let Some(last_echoed) = self.last_echoed else {
+            return found;
+        };
+
+        let expected = self.sent[last_echoed].pressure;
+        let reported = telem.pressure * 1000.0;
+        self.samples.push(Sample {
+            time: self.secs(now),
+            expected,
+            reported,
+            altitude: telem.altitude,
+            mode: telem.mode,
+        });
+
+        let diverged = (reported - expected as f64).abs() > self.max_pressure_error;
+        if diverged && !self.diverged {
+            found.push(self.flag(now, Discrepancy::Diverged { expected, reported }));
+        }
+        self.diverged = diverged;
+
+        found
+    }
+
+    /// Flag any pressure values which haven't been echoed within `max_lag`
+    pub fn check_lag(&mut self, now: Instant) -> Vec<Discrepancy> {
+        let from = self.last_echoed.map_or(0, |idx| idx + 1);
+        let late: Vec<u32> = self.sent[from..]
+            .iter_mut()
+            .filter(|s| {
+                !s.flagged && !s.repeats_echo && now.duration_since(s.sent_at) > self.max_lag
+            })
+            .map(|s| {
+                s.flagged = true;
+                s.pressure
+            })
+            .collect();
+
+        late.into_iter()
+            .map(|pressure| self.flag(now, Discrepancy::NotEchoed { pressure }))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::TelemetryBuilder;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    fn telem(mode: Mode, pressure_kpa: f64, echo: &str) -> Telemetry {
+        TelemetryBuilder::default()
+            .mode(mode)
+            .pressure(pressure_kpa)
+            .cmd_echo(echo)
+            .build()
+    }
+
+    #[test]
+    fn test_matching_telemetry() {
+        let start = Instant::now();
+        let mut verifier = SimVerifier::new(start);
+
+        verifier.record_sent(101325, start);
+        // telemetry before the echo isn't compared
+        assert_eq!(
+            verifier.recv(&telem(Mode::Simulation, 98.9, "CXON"), start),
+            vec![]
+        );
+        assert!(verifier.samples().is_empty());
+
+        let now = start + SECOND;
+        verifier.record_sent(100000, now);
+        assert_eq!(
+            verifier.recv(&telem(Mode::Simulation, 101.3, "SIMP101325"), now),
+            vec![]
+        );
+        assert_eq!(
+            verifier.recv(&telem(Mode::Simulation, 100.0, "SIMP100000"), now + SECOND),
+            vec![]
+        );
+
+        assert_eq!(verifier.samples().len(), 2);
+        assert_eq!(verifier.samples()[1].expected, 100000);
+        assert_eq!(verifier.sent()[1].lag, Some(SECOND));
+        assert_eq!(verifier.mean_lag(), Some(SECOND));
+        assert!(verifier.check_lag(now + SECOND * 10).is_empty());
+    }
+
+    #[test]
+    fn test_discrepancies() {
+        let start = Instant::now();
+        let mut verifier = SimVerifier::new(start);
+
+        verifier.record_sent(101325, start);
+        assert_eq!(
+            verifier.recv(&telem(Mode::Flight, 101.3, "SIMP101325"), start),
+            vec![Discrepancy::NotSimulationMode]
+        );
+        // only flagged when it starts
+        assert_eq!(
+            verifier.recv(&telem(Mode::Flight, 101.3, "SIMP101325"), start),
+            vec![]
+        );
+
+        assert_eq!(
+            verifier.recv(&telem(Mode::Simulation, 98.9, "SIMP101325"), start),
+            vec![Discrepancy::Diverged {
+                expected: 101325,
+                reported: 98900.0
+            }]
+        );
+
+        // a value echoed late, and one never echoed
+        verifier.record_sent(101000, start);
+        verifier.record_sent(100900, start);
+        assert_eq!(
+            verifier.recv(
+                &telem(Mode::Simulation, 101.0, "SIMP101000"),
+                start + SECOND * 4
+            ),
+            vec![Discrepancy::Lagging {
+                pressure: 101000,
+                lag: SECOND * 4
+            }]
+        );
+        assert_eq!(
+            verifier.check_lag(start + SECOND * 4),
+            vec![Discrepancy::NotEchoed { pressure: 100900 }]
+        );
+        assert!(verifier.check_lag(start + SECOND * 5).is_empty());
+        assert_eq!(verifier.log().len(), 4);
+    }
+
+    #[test]
+    fn test_repeated_values_need_a_new_echo() {
+        let start = Instant::now();
+        let mut verifier = SimVerifier::new(start);
+
+        verifier.record_sent(101325, start);
+        verifier.recv(
+            &telem(Mode::Simulation, 101.3, "SIMP101325"),
+            start + SECOND,
+        );
+        assert_eq!(verifier.sent()[0].lag, Some(SECOND));
+
+        // the CanSat is still echoing the first value, so the repeat can't be seen arriving
+        verifier.record_sent(101325, start + SECOND);
+        verifier.recv(
+            &telem(Mode::Simulation, 101.3, "SIMP101325"),
+            start + SECOND * 2,
+        );
+        assert!(verifier.sent()[1].repeats_echo);
+        assert_eq!(verifier.sent()[1].lag, None);
+        assert!(verifier.check_lag(start + SECOND * 10).is_empty());
+
+        // it's matched once the echo changes and comes back
+        verifier.record_sent(101300, start + SECOND * 2);
+        verifier.record_sent(101325, start + SECOND * 2);
+        assert!(verifier.sent()[3].repeats_echo);
+        verifier.recv(
+            &telem(Mode::Simulation, 101.3, "SIMP101300"),
+            start + SECOND * 3,
+        );
+        assert!(!verifier.sent()[3].repeats_echo);
+        verifier.recv(
+            &telem(Mode::Simulation, 101.3, "SIMP101325"),
+            start + SECOND * 4,
+        );
+        assert_eq!(verifier.sent()[3].lag, Some(SECOND * 2));
+    }
+}
@@ -0,0 +1,310 @@
+use super::{seeded_rng, Flight};
+use crate::command::{Command, Enabled, ParseCommandError, SetState, SimMode, TimeArg};
+use crate::constants::TEAM_ID;
+use crate::simulation::pressure_to_altitude;
+use crate::telemetry::{GpsTime, MissionTime, Mode, State, Telemetry};
+use chrono::{Timelike, Utc};
+use rand::prelude::*;
+use std::time::{Duration, Instant};
+
+/// The seconds in a day, mission time wraps around at midnight
+const DAY: f64 = 86400.0;
+
+/// A fake CanSat which obeys commands like the real flight software, for rehearsing operations.
+///
+/// It is polled with the current time like the simulation controller, so it can be driven by a
+/// real clock or by tests.
+#[derive(Debug)]
+pub struct FlightSoftware {
+    pub team_id: u16,
+
+    /// How often telemetry is sent while enabled
+    pub period: Duration,
+
+    /// Is telemetry enabled with CX
+    telemetry: bool,
+
+    /// The mission time set by ST, and when it was set
+    clock: (f64, Instant),
+
+    /// The UTC time of day in seconds when the flight software started, and when that was
+    gps_clock: (f64, Instant),
+
+    /// Simulation mode is only activated by SIM,ACTIVATE after SIM,ENABLE
+    sim_mode: SimMode,
+
+    /// The last simulated pressure received, in Pa
+    sim_pressure: Option<u32>,
+
+    /// Subtracted from the measured altitude, set by CAL
+    altitude_offset: f64,
+
+    /// The state forced by SETSTATE
+    forced_state: Option<State>,
+
+    flight: Flight,
+    rng: StdRng,
+
+    /// When the flight model was last stepped
+    last_step: Instant,
+
+    /// When telemetry is next due
+    next_due: Instant,
+
+    packet_count: u32,
+    cmd_echo: String,
+}
+
+impl FlightSoftware {
+    /// Create the flight software with telemetry disabled, a random seed is chosen if none is given
+    pub fn new(seed: Option<u64>, now: Instant) -> Self {
+        let mut rng = seeded_rng(seed);
+
+        Self {
+            team_id: TEAM_ID,
+            period: Duration::from_secs(1),
+            telemetry: false,
+            clock: (0.0, now),
+            gps_clock: (Utc::now().num_seconds_from_midnight() as f64, now),
+            sim_mode: SimMode::Disable,
+            sim_pressure: None,
+            altitude_offset: 0.0,
+            forced_state: None,
+            flight: Flight::new(&mut rng),
+            rng,
+            last_step: now,
+            next_due: now,
+            packet_count: 0,
+            cmd_echo: String::new(),
+        }
+    }
+
+    pub fn telemetry_enabled(&self) -> bool {
+        self.telemetry
+    }
+
+    pub fn mode(&self) -> Mode {
+        if self.sim_mode == SimMode::Activate {
+            Mode::Simulation
+        } else {
+            Mode::Flight
+        }
+    }
+
+    pub fn flight(&self) -> &Flight {
+        &self.flight
+    }
+
+    /// The mission time at `now`
+    pub fn mission_time(&self, now: Instant) -> MissionTime {
+        let (base, set) = self.clock;
+        let elapsed = now.saturating_duration_since(set).as_secs_f64();
+        MissionTime::from_seconds((base + elapsed).rem_euclid(DAY))
+    }
+
+    /// The GPS time at `now`, kept by the UTC clock read when the flight software started.
+    /// Setting the mission time with ST doesn't change it.
+    pub fn gps_time(&self, now: Instant) -> GpsTime {
+        let (base, set) = self.gps_clock;
+        GpsTime::from_seconds(base + now.saturating_duration_since(set).as_secs_f64())
+    }
+
+    /// The altitude measured by the sensors, before calibration
+    fn raw_altitude(&self) -> f64 {
+        match (self.mode(), self.sim_pressure) {
+            (Mode::Simulation, Some(pressure)) => pressure_to_altitude(pressure),
+            _ => self.flight.altitude(),
+        }
+    }
+
+    /// Parse and run a command received from the ground station
+    pub fn handle_line(&mut self, line: &str, now: Instant) -> Result<Command, ParseCommandError> {
+        let cmd = line.parse()?;
+        self.handle(cmd, now);
+        Ok(cmd)
+    }
+
+    /// Run a command, echoing it in the next telemetry packet
+    pub fn handle(&mut self, cmd: Command, now: Instant) {
+        tracing::info!("Running {cmd}");
+
+        match cmd {
+            Command::TelemetryEnable(enabled) => {
+                self.telemetry = enabled == Enabled::On;
+                self.next_due = now;
+            }
+            Command::SetTime(time) => {
+                let time = match time {
+                    TimeArg::Utc(time) => time,
+                    TimeArg::Gps => self.gps_time(now),
+                };
+                let secs = time.h as u32 * 3600 + time.m as u32 * 60 + time.s as u32;
+                self.clock = (secs as f64, now);
+            }
+            Command::SimulationMode(mode) => {
+                self.sim_mode = match (self.sim_mode, mode) {
+                    // activating without enabling first is ignored, as a safety measure
+                    (SimMode::Disable, SimMode::Activate) => SimMode::Disable,
+                    (_, mode) => mode,
+                };
+                if self.sim_mode == SimMode::Disable {
+                    self.sim_pressure = None;
+                }
+            }
+            // only used while simulation mode is active
+            Command::SimulatedPressure(pressure) if self.sim_mode == SimMode::Activate => {
+                self.sim_pressure = Some(pressure);
+            }
+            Command::Calibrate => {
+                self.altitude_offset = self.raw_altitude();
+                self.packet_count = 0;
+            }
+            Command::SetState(state) => {
+                let name = match state {
+                    SetState::Container(state) => state.to_string(),
+                    SetState::Payload(state) => state.to_string(),
+                };
+                let Ok(state) = name.parse::<State>();
+                self.forced_state = Some(state);
+            }
+            // the other commands control hardware we don't simulate
+            _ => (),
+        }
+
+        self.cmd_echo = cmd.echo();
+    }
+
+    /// Advance the flight, returning a telemetry packet if one is due
+    pub fn poll(&mut self, now: Instant) -> Option<Telemetry> {
+        // the sensors are replaced by the simulated pressure in simulation mode
+        let dt = now.saturating_duration_since(self.last_step).as_secs_f64();
+        self.last_step = now;
+        if self.mode() == Mode::Flight && dt > 0.0 {
+            self.flight.step(dt, &mut self.rng);
+        }
+
+        if !self.telemetry || now < self.next_due {
+            return None;
+        }
+        self.next_due = now + self.period;
+
+        let mission_time = self.mission_time(now);
+        let gps_time = self.gps_time(now);
+        let mut telem = self.flight.telemetry(
+            &mut self.rng,
+            self.team_id,
+            mission_time,
+            gps_time,
+            self.packet_count,
+            &self.cmd_echo,
+        );
+        self.packet_count += 1;
+
+        telem.mode = self.mode();
+        if let (Mode::Simulation, Some(pressure)) = (telem.mode, self.sim_pressure) {
+            telem.altitude = pressure_to_altitude(pressure);
+            telem.pressure = pressure as f64 / 1000.0;
+        }
+        telem.altitude -= self.altitude_offset;
+        if let Some(state) = &self.forced_state {
+            telem.state = state.clone();
+        }
+
+        Some(telem)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::altitude_to_pressure;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    fn run(fsw: &mut FlightSoftware, now: Instant, line: &str) {
+        fsw.handle_line(&format!("CMD,{TEAM_ID},{line}"), now)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_telemetry_enable() {
+        let start = Instant::now();
+        let mut fsw = FlightSoftware::new(Some(1), start);
+        assert!(fsw.poll(start).is_none());
+
+        run(&mut fsw, start, "CX,ON");
+        let telem = fsw.poll(start).unwrap();
+        assert_eq!(telem.cmd_echo, "CXON");
+        assert_eq!(telem.packet_count, 0);
+        assert_eq!(telem.mode, Mode::Flight);
+
+        // one packet per period
+        assert!(fsw.poll(start + SECOND / 2).is_none());
+        assert_eq!(fsw.poll(start + SECOND).unwrap().packet_count, 1);
+
+        run(&mut fsw, start + SECOND, "CX,OFF");
+        assert!(fsw.poll(start + SECOND * 3).is_none());
+    }
+
+    #[test]
+    fn test_set_time_and_calibrate() {
+        let start = Instant::now();
+        let mut fsw = FlightSoftware::new(Some(1), start);
+        run(&mut fsw, start, "CX,ON");
+        fsw.poll(start);
+        fsw.poll(start + SECOND);
+
+        run(&mut fsw, start + SECOND, "ST,13:35:59");
+        let time = fsw.mission_time(start + SECOND * 2);
+        assert_eq!((time.h, time.m, time.s), (13, 36, 0));
+
+        run(&mut fsw, start + SECOND * 2, "CAL");
+        let telem = fsw.poll(start + SECOND * 2).unwrap();
+        assert_eq!(telem.packet_count, 0);
+        assert_eq!(telem.cmd_echo, "CAL");
+        assert!(telem.altitude.abs() < 1.0);
+    }
+
+    #[test]
+    fn test_simulation_mode() {
+        let start = Instant::now();
+        let mut fsw = FlightSoftware::new(Some(1), start);
+        run(&mut fsw, start, "CX,ON");
+
+        // activating and SIMP are ignored until simulation is enabled
+        run(&mut fsw, start, "SIM,ACTIVATE");
+        run(&mut fsw, start, "SIMP,90000");
+        assert_eq!(fsw.poll(start).unwrap().mode, Mode::Flight);
+
+        run(&mut fsw, start, "SIM,ENABLE");
+        run(&mut fsw, start, "SIM,ACTIVATE");
+        run(&mut fsw, start, "SIMP,101325");
+        run(&mut fsw, start, "CAL");
+
+        let pressure = altitude_to_pressure(500.0);
+        run(&mut fsw, start + SECOND, &format!("SIMP,{pressure}"));
+        let telem = fsw.poll(start + SECOND).unwrap();
+        assert_eq!(telem.mode, Mode::Simulation);
+        assert_eq!(telem.cmd_echo, format!("SIMP{pressure}"));
+        assert_eq!(telem.pressure, pressure as f64 / 1000.0);
+        assert!((telem.altitude - 500.0).abs() < 1.0, "{}", telem.altitude);
+
+        run(&mut fsw, start + SECOND, "SIM,DISABLE");
+        assert_eq!(fsw.poll(start + SECOND * 2).unwrap().mode, Mode::Flight);
+    }
+
+    #[test]
+    fn test_set_state() {
+        let start = Instant::now();
+        let mut fsw = FlightSoftware::new(Some(1), start);
+        run(&mut fsw, start, "CX,ON");
+        assert_eq!(fsw.poll(start).unwrap().state.to_string(), "LAUNCH_WAIT");
+
+        run(&mut fsw, start, "OPTIONAL,SETSTATE,C,WAIT_PARA");
+        let telem = fsw.poll(start + SECOND).unwrap();
+        assert_eq!(telem.state.to_string(), "WAIT_PARA");
+        assert_eq!(telem.cmd_echo, "OPTIONALSETSTATECWAIT_PARA");
+
+        assert!(fsw.handle_line("CMD,1000,CX,ON", start).is_err());
+    }
+}
@@ -0,0 +1,322 @@
+use super::seeded_rng;
+use crate::as_str::AsStr;
+use crate::xbee::{ApiMode, XbeePacket};
+use anyhow::anyhow;
+use enum_iterator::{all, Sequence};
+use rand::prelude::*;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::{fmt, io};
+
+/// A radio link problem which can be injected into XBee framed output
+#[derive(Sequence, Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Fault {
+    /// A byte of the frame is changed on the wire
+    Corrupt,
+    /// The end of the frame is lost
+    Truncate,
+    /// The data of the frame and the following frame are sent in a single frame
+    Merge,
+    /// The frame is sent twice
+    Duplicate,
+    /// The frame is sent after the following frame
+    Reorder,
+    /// Random bytes are sent before the frame, these never contain a start delimiter so the
+    /// frame itself is still received
+    Garbage,
+    /// The frame is sent with the wrong checksum
+    Checksum,
+}
+
+impl AsStr for Fault {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Fault::Corrupt => "corrupt",
+            Fault::Truncate => "truncate",
+            Fault::Merge => "merge",
+            Fault::Duplicate => "duplicate",
+            Fault::Reorder => "reorder",
+            Fault::Garbage => "garbage",
+            Fault::Checksum => "checksum",
+        }
+    }
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Fault {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        all::<Fault>()
+            .find(|fault| fault.as_str() == s)
+            .ok_or_else(|| {
+                let names: Vec<&str> = all::<Fault>().map(|fault| fault.as_str()).collect();
+                anyhow!("Unknown fault {s:?}, expected one of {}", names.join(", "))
+            })
+    }
+}
+
+/// Injects faults into XBee frames as they are serialised, so the ground station's handling of
+/// a bad radio link can be tested on demand. The same seed always injects the same faults.
+#[derive(Debug)]
+pub struct FaultInjector {
+    /// The chance of each fault happening to a frame
+    pub rates: BTreeMap<Fault, f64>,
+
+    rng: StdRng,
+
+    /// Data waiting to be merged with the next frame's
+    merging: Option<Vec<u8>>,
+
+    /// A frame waiting to be sent after the next one
+    held: Option<Vec<u8>>,
+
+    /// How many times each fault has been injected
+    counts: BTreeMap<Fault, u32>,
+}
+
+impl FaultInjector {
+    /// The most garbage bytes sent in one burst
+    const MAX_GARBAGE: usize = 32;
+
+    pub fn new(rates: BTreeMap<Fault, f64>, seed: Option<u64>) -> Self {
+        Self {
+            rates,
+            rng: seeded_rng(seed),
+            merging: None,
+            held: None,
+            counts: BTreeMap::new(),
+        }
+    }
+
+    pub fn counts(&self) -> &BTreeMap<Fault, u32> {
+        &self.counts
+    }
+
+    /// Randomly decide whether to inject a fault, counting it if so
+    fn roll(&mut self, fault: Fault) -> bool {
+        let rate = self.rates.get(&fault).copied().unwrap_or(0.0);
+        let inject = rate > 0.0 && self.rng.gen_bool(rate.min(1.0));
+        if inject {
+            tracing::debug!("Injecting fault: {fault}");
+            *self.counts.entry(fault).or_default() += 1;
+        }
+        inject
+    }
+
+    /// A random byte which doesn't change the framing, i.e. isn't escaped or a start delimiter
+    fn random_byte(&mut self, except: Option<u8>) -> u8 {
+        loop {
+            let byte = self.rng.gen();
+            if !ApiMode::needs_escape(byte) && Some(byte) != except {
+                break byte;
+            }
+        }
+    }
+
+    /// Frame some data with `frame` and serialise it, returning the bytes to send in its place.
+    /// These may be empty if the frame is being held back to merge or reorder it.
+    pub fn inject(
+        &mut self,
+        data: Vec<u8>,
+        frame: impl Fn(Vec<u8>) -> io::Result<XbeePacket>,
+        mode: ApiMode,
+    ) -> io::Result<Vec<u8>> {
+        let data = match self.merging.take() {
+            Some(mut first) => {
+                first.extend(data);
+                first
+            }
+            None if self.roll(Fault::Merge) => {
+                self.merging = Some(data);
+                return Ok(vec![]);
+            }
+            None => data,
+        };
+
+        let mut packet = frame(data)?;
+        if self.roll(Fault::Checksum) {
+            packet.checksum = packet.checksum.wrapping_add(1);
+        }
+
+        let mut bytes = packet.serialise(mode)?;
+        if self.roll(Fault::Corrupt) {
+            // anything but the start delimiter
+            let idx = self.rng.gen_range(1..bytes.len());
+            bytes[idx] = self.random_byte(Some(bytes[idx]));
+        }
+        if self.roll(Fault::Truncate) {
+            let len = self.rng.gen_range(1..bytes.len());
+            bytes.truncate(len);
+        }
+
+        let mut out = vec![];
+        if self.roll(Fault::Garbage) {
+            let len = self.rng.gen_range(1..=Self::MAX_GARBAGE);
+            for _ in 0..len {
+                let byte = self.random_byte(None);
+                out.push(byte);
+            }
+        }
+
+        if self.held.is_none() && self.roll(Fault::Reorder) {
+            self.held = Some(bytes);
+            return Ok(out);
+        }
+
+        out.extend_from_slice(&bytes);
+        if self.roll(Fault::Duplicate) {
+            out.extend_from_slice(&bytes);
+        }
+        if let Some(held) = self.held.take() {
+            out.extend(held);
+        }
+
+        Ok(out)
+    }
+
+    /// The bytes of any frames still being held back
+    pub fn flush(
+        &mut self,
+        frame: impl Fn(Vec<u8>) -> io::Result<XbeePacket>,
+        mode: ApiMode,
+    ) -> io::Result<Vec<u8>> {
+        let mut out = match self.merging.take() {
+            Some(data) => frame(data)?.serialise(mode)?,
+            None => vec![],
+        };
+        out.extend(self.held.take().unwrap_or_default());
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xbee::{DecodeEvent, FrameDecoder, TxRequest};
+
+    const MODE: ApiMode = ApiMode::Escaped;
+
+    fn requests() -> Vec<TxRequest> {
+        (0..20)
+            .map(|i| TxRequest::new(i, 0x00_01, format!("1047,packet {i}")))
+            .collect()
+    }
+
+    fn frame(data: Vec<u8>) -> io::Result<XbeePacket> {
+        TxRequest::new(0, 0x00_01, data).try_into()
+    }
+
+    /// Send all the requests with a single fault and decode the output
+    fn decode_with(fault: Fault, rate: f64) -> (FaultInjector, Vec<DecodeEvent>) {
+        let mut injector = FaultInjector::new([(fault, rate)].into(), Some(1047));
+        let mut bytes = vec![];
+        for req in requests() {
+            bytes.extend(injector.inject(req.data, frame, MODE).unwrap());
+        }
+        bytes.extend(injector.flush(frame, MODE).unwrap());
+
+        let mut decoder = FrameDecoder::new(MODE);
+        let mut events = decoder.feed(&bytes);
+        events.extend(decoder.flush());
+        (injector, events)
+    }
+
+    /// The data of each frame which was decoded
+    fn frames(events: &[DecodeEvent]) -> Vec<String> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                DecodeEvent::Frame(packet) => {
+                    let req = TxRequest::try_from(packet.clone()).unwrap();
+                    Some(String::from_utf8(req.data).unwrap())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn count(events: &[DecodeEvent], f: impl Fn(&DecodeEvent) -> bool) -> usize {
+        events.iter().filter(|event| f(event)).count()
+    }
+
+    #[test]
+    fn test_no_faults() {
+        let (injector, events) = decode_with(Fault::Corrupt, 0.0);
+        let expected: Vec<String> = requests()
+            .into_iter()
+            .map(|req| String::from_utf8(req.data).unwrap())
+            .collect();
+        assert_eq!(frames(&events), expected);
+        assert!(injector.counts().is_empty());
+    }
+
+    #[test]
+    fn test_frame_faults() {
+        let originals: Vec<String> = frames(&decode_with(Fault::Corrupt, 0.0).1);
+
+        // every frame is damaged, so none can be received intact
+        for fault in [Fault::Corrupt, Fault::Truncate] {
+            let (injector, events) = decode_with(fault, 1.0);
+            assert_eq!(injector.counts()[&fault], 20);
+            assert!(
+                frames(&events).iter().all(|data| !originals.contains(data)),
+                "{fault}: {events:?}"
+            );
+        }
+
+        let (_, events) = decode_with(Fault::Checksum, 1.0);
+        assert!(frames(&events).is_empty());
+        assert_eq!(
+            count(&events, |e| matches!(e, DecodeEvent::BadChecksum(_))),
+            20
+        );
+
+        let (_, events) = decode_with(Fault::Truncate, 1.0);
+        assert!(count(&events, |e| matches!(e, DecodeEvent::Resync(_))) >= 19);
+    }
+
+    #[test]
+    fn test_stream_faults() {
+        let (_, events) = decode_with(Fault::Duplicate, 1.0);
+        let received = frames(&events);
+        assert_eq!(received.len(), 40);
+        assert_eq!(received[0], received[1]);
+
+        let (_, events) = decode_with(Fault::Reorder, 1.0);
+        let received = frames(&events);
+        assert_eq!(received.len(), 20);
+        assert_eq!(
+            received[..4],
+            [
+                "1047,packet 1",
+                "1047,packet 0",
+                "1047,packet 3",
+                "1047,packet 2"
+            ]
+        );
+
+        let (_, events) = decode_with(Fault::Merge, 1.0);
+        let received = frames(&events);
+        assert_eq!(received.len(), 10);
+        assert_eq!(received[0], "1047,packet 01047,packet 1");
+
+        // the garbage is reported, but every frame survives
+        let (_, events) = decode_with(Fault::Garbage, 1.0);
+        assert_eq!(frames(&events).len(), 20);
+        assert_eq!(count(&events, |e| matches!(e, DecodeEvent::Garbage(_))), 20);
+    }
+
+    #[test]
+    fn test_parse_fault() {
+        for fault in all::<Fault>() {
+            assert_eq!(fault.to_string().parse::<Fault>().unwrap(), fault);
+        }
+        assert!("lightning".parse::<Fault>().is_err());
+    }
+}
@@ -0,0 +1,250 @@
+use crate::simulation::altitude_to_pressure;
+use crate::telemetry::*;
+use rand::{distributions::Uniform, prelude::*};
+
+/// Acceleration due to gravity in m/s^2
+const GRAVITY: f64 = 9.81;
+
+/// The different parts of the flight, in order
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Phase {
+    /// Sitting on the launch pad
+    LaunchWait,
+    /// The motor is burning
+    Boost,
+    /// Coasting up to apogee
+    Coast,
+    /// Released from the rocket, falling under the container's parachute
+    Yeeted,
+    /// The probe has been released with its heat shield
+    HeatShield,
+    /// The probe's parachute is open
+    Parachute,
+    Landed,
+    /// Landed with the flag mast raised
+    MastRaised,
+}
+
+impl Phase {
+    pub fn state(&self) -> State {
+        let name = match self {
+            Phase::LaunchWait => "LAUNCH_WAIT",
+            Phase::Boost | Phase::Coast => "ASCENT",
+            Phase::Yeeted => return State::Yeeted,
+            Phase::HeatShield => "HS_DEPLOYED",
+            Phase::Parachute => "PC_DEPLOYED",
+            Phase::Landed => "LANDED",
+            Phase::MastRaised => "MAST_RAISED",
+        };
+        State::Other(name.to_string())
+    }
+}
+
+/// A time-stepped model of a CanSat flight, so that every telemetry field is consistent with the
+/// altitude and the state progresses as it would on launch day
+#[derive(Debug, Clone)]
+pub struct Flight {
+    phase: Phase,
+
+    /// Seconds since the start of the current phase
+    phase_time: f64,
+
+    /// Metres above the launch site
+    altitude: f64,
+
+    /// Vertical velocity in m/s, positive is up
+    velocity: f64,
+
+    latitude: f64,
+    longitude: f64,
+
+    /// The wind velocity north and east in m/s, drifts the CanSat while it is in the air
+    wind: (f64, f64),
+
+    /// The battery voltage
+    voltage: f64,
+}
+
+impl Flight {
+    /// How long to sit on the pad before launching
+    const LAUNCH_WAIT: f64 = 10.0;
+    /// How long the motor burns for
+    const BURN_TIME: f64 = 1.5;
+    /// The acceleration from the motor, on top of gravity
+    const BURN_ACCEL: f64 = 85.0;
+    /// How fast the container descends under its parachute
+    const CONTAINER_DESCENT_RATE: f64 = 15.0;
+    /// The altitude the probe is released at
+    const PROBE_RELEASE_ALTITUDE: f64 = 400.0;
+    /// The altitude the probe's parachute opens at
+    const PARACHUTE_ALTITUDE: f64 = 200.0;
+    /// How fast the probe descends under its parachute
+    const PARACHUTE_DESCENT_RATE: f64 = 5.0;
+    /// How long after landing the flag mast is raised
+    const MAST_RAISE_DELAY: f64 = 5.0;
+
+    /// The launch site, taken from the competition launch site in Virginia
+    const LAUNCH_LATITUDE: f64 = 37.2244;
+    const LAUNCH_LONGITUDE: f64 = -80.4286;
+    const LAUNCH_SITE_ALTITUDE: f64 = 600.0;
+
+    pub fn new(rng: &mut impl Rng) -> Self {
+        Self {
+            phase: Phase::LaunchWait,
+            phase_time: 0.0,
+            altitude: 0.0,
+            velocity: 0.0,
+            latitude: Self::LAUNCH_LATITUDE,
+            longitude: Self::LAUNCH_LONGITUDE,
+            wind: (rng.gen_range(-5.0..5.0), rng.gen_range(-5.0..5.0)),
+            voltage: 5.6,
+        }
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    /// Metres above the launch site
+    pub fn altitude(&self) -> f64 {
+        self.altitude
+    }
+
+    pub fn in_air(&self) -> bool {
+        !matches!(
+            self.phase,
+            Phase::LaunchWait | Phase::Landed | Phase::MastRaised
+        )
+    }
+
+    /// Move the velocity towards a terminal descent rate, as if slowed by a parachute
+    fn approach_descent_rate(&mut self, rate: f64, dt: f64) {
+        let blend = (dt / 1.5).min(1.0);
+        self.velocity += (-rate - self.velocity) * blend;
+    }
+
+    /// The longest time the flight is integrated over in one go, packets are sent far less often
+    /// than this would need to be accurate through the motor burn
+    const MAX_STEP: f64 = 0.05;
+
+    /// Advance the flight by `dt` seconds
+    pub fn step(&mut self, dt: f64, rng: &mut impl Rng) {
+        let steps = (dt / Self::MAX_STEP).ceil().max(1.0);
+        for _ in 0..steps as usize {
+            self.integrate(dt / steps, rng);
+        }
+    }
+
+    fn integrate(&mut self, dt: f64, rng: &mut impl Rng) {
+        self.phase_time += dt;
+
+        match self.phase {
+            Phase::LaunchWait | Phase::Landed | Phase::MastRaised => (),
+            Phase::Boost => self.velocity += (Self::BURN_ACCEL - GRAVITY) * dt,
+            Phase::Coast => self.velocity -= GRAVITY * dt,
+            Phase::Yeeted | Phase::HeatShield => {
+                self.approach_descent_rate(Self::CONTAINER_DESCENT_RATE, dt)
+            }
+            Phase::Parachute => self.approach_descent_rate(Self::PARACHUTE_DESCENT_RATE, dt),
+        }
+
+        if self.in_air() {
+            self.altitude = (self.altitude + self.velocity * dt).max(0.0);
+
+            // drift with a gusty wind, converting metres to degrees
+            self.wind.0 += rng.gen_range(-0.05..0.05);
+            self.wind.1 += rng.gen_range(-0.05..0.05);
+            self.latitude += self.wind.0 * dt / 111_111.0;
+            self.longitude += self.wind.1 * dt / (111_111.0 * self.latitude.to_radians().cos());
+        }
+
+        // the radio and servos draw more current while flying
+        let drain = if self.in_air() { 0.002 } else { 0.0005 };
+        self.voltage = (self.voltage - drain * dt).max(4.8);
+
+        let next = match self.phase {
+            Phase::LaunchWait if self.phase_time >= Self::LAUNCH_WAIT => Some(Phase::Boost),
+            Phase::Boost if self.phase_time >= Self::BURN_TIME => Some(Phase::Coast),
+            Phase::Coast if self.velocity <= 0.0 => Some(Phase::Yeeted),
+            Phase::Yeeted if self.altitude <= Self::PROBE_RELEASE_ALTITUDE => {
+                Some(Phase::HeatShield)
+            }
+            Phase::HeatShield if self.altitude <= Self::PARACHUTE_ALTITUDE => {
+                Some(Phase::Parachute)
+            }
+            Phase::Parachute if self.altitude <= 0.0 => Some(Phase::Landed),
+            Phase::Landed if self.phase_time >= Self::MAST_RAISE_DELAY => Some(Phase::MastRaised),
+            _ => None,
+        };
+
+        if let Some(next) = next {
+            tracing::info!(
+                "Flight phase {:?} -> {next:?} at {:.1} m",
+                self.phase,
+                self.altitude
+            );
+            if next == Phase::Landed {
+                self.velocity = 0.0;
+            }
+            self.phase = next;
+            self.phase_time = 0.0;
+        }
+    }
+
+    /// The telemetry the CanSat would send right now, `gps_time` is the UTC time it is sent at
+    pub fn telemetry(
+        &self,
+        rng: &mut impl Rng,
+        team_id: u16,
+        mission_time: MissionTime,
+        gps_time: GpsTime,
+        packet_count: u32,
+        cmd_echo: &str,
+    ) -> Telemetry {
+        let noise = Uniform::new_inclusive(-0.5, 0.5);
+        let altitude = self.altitude + rng.sample(noise);
+        let above_sea_level = Self::LAUNCH_SITE_ALTITUDE + altitude;
+
+        // the air cools by 6.5 C every km, and the tilt is worse while flying
+        let temperature = 24.0 - 0.0065 * altitude + rng.sample(noise);
+        let max_tilt = if self.in_air() { 30.0 } else { 2.0 };
+        let tilt = Uniform::new_inclusive(-max_tilt, max_tilt);
+
+        let deployed = |phase: Phase| self.phase as u8 >= phase as u8;
+
+        Telemetry {
+            team_id,
+            mission_time,
+            packet_count,
+            mode: Mode::Flight,
+            state: self.phase.state(),
+            altitude,
+            hs_deployed: if deployed(Phase::HeatShield) {
+                HsDeployed::Deployed
+            } else {
+                HsDeployed::NotDeployed
+            },
+            pc_deployed: if deployed(Phase::Parachute) {
+                PcDeployed::Deployed
+            } else {
+                PcDeployed::NotDeployed
+            },
+            mast_raised: if deployed(Phase::MastRaised) {
+                MastRaised::Raised
+            } else {
+                MastRaised::NotRaised
+            },
+            temperature,
+            voltage: self.voltage + rng.sample(noise) / 10.0,
+            pressure: altitude_to_pressure(above_sea_level) as f64 / 1000.0,
+            gps_time,
+            gps_altitude: above_sea_level + rng.sample(noise) * 4.0,
+            gps_latitude: self.latitude,
+            gps_longitude: self.longitude,
+            gps_sats: rng.gen_range(8..16),
+            tilt_x: rng.sample(tilt),
+            tilt_y: rng.sample(tilt),
+            cmd_echo: cmd_echo.to_string(),
+        }
+    }
+}
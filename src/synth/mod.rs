@@ -0,0 +1,249 @@
+mod cansat;
+mod faults;
+mod flight;
+mod options;
+mod radio;
+mod sink;
+
+pub use cansat::FlightSoftware;
+pub use faults::{Fault, FaultInjector};
+pub use flight::{Flight, Phase};
+pub use options::{parse_addr, parse_api_mode, parse_rate, Limit, SynthOptions, SYNTH_USAGE};
+pub use radio::{RadioLink, RadioPort};
+pub use sink::{connect_tcp, Framing, SinkKind, SinkSpec, TelemetrySink};
+
+use crate::constants::TEAM_ID;
+use crate::telemetry::{GpsTime, MissionTime, Telemetry};
+use anyhow::Result;
+use rand::{
+    distributions::{Open01, Uniform},
+    prelude::*,
+};
+use std::io::ErrorKind;
+use std::{thread, time::Duration};
+
+/// Create a random number generator from `seed`, or a random seed if none is given
+fn seeded_rng(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    }
+}
+
+/// A packet produced by the synthesiser
+#[derive(Debug, Clone, PartialEq)]
+pub struct SynthPacket {
+    /// How long after the previous packet this one is sent
+    pub delay: Duration,
+    pub telem: Telemetry,
+    /// Should the packet be dropped to simulate a lost packet
+    pub dropped: bool,
+}
+
+/// Synthesises telemetry from a simulated flight, for testing the ground station without a
+/// CanSat. The same seed always produces the same packets.
+#[derive(Debug)]
+pub struct TelemetrySynth {
+    pub team_id: u16,
+
+    /// The mean time between packets, in seconds
+    pub interval: f64,
+
+    /// The largest random change to the time between packets, in seconds
+    pub jitter: f64,
+
+    /// The fraction of packets which are dropped
+    pub failure_rate: f64,
+
+    /// The value reported in the CMD_ECHO field
+    pub cmd_echo: String,
+
+    /// The UTC time the flight starts at, in seconds after midnight. The GPS time follows the
+    /// mission time from here, so it stays consistent however fast packets are generated.
+    pub launch_time: f64,
+
+    rng: StdRng,
+    flight: Flight,
+
+    /// Mission time in seconds, kept separate from the real time so the clock can run fast
+    elapsed: f64,
+    packet_count: u32,
+}
+
+impl TelemetrySynth {
+    /// The default launch time, 13:00 UTC
+    pub const LAUNCH_TIME: f64 = 13.0 * 3600.0;
+
+    /// Create a synthesiser, a random seed is chosen if none is given
+    pub fn new(seed: Option<u64>) -> Self {
+        let mut rng = seeded_rng(seed);
+
+        Self {
+            team_id: TEAM_ID,
+            interval: 1.0,
+            jitter: 0.1,
+            failure_rate: 0.001,
+            cmd_echo: "CXON".to_string(),
+            launch_time: Self::LAUNCH_TIME,
+            flight: Flight::new(&mut rng),
+            rng,
+            elapsed: 0.0,
+            packet_count: 0,
+        }
+    }
+
+    pub fn flight(&self) -> &Flight {
+        &self.flight
+    }
+
+    /// Advance the flight to the next packet
+    pub fn next_packet(&mut self) -> SynthPacket {
+        let jitter = self.jitter.min(self.interval);
+        let delay = if jitter > 0.0 {
+            self.interval + self.rng.sample(Uniform::new_inclusive(-jitter, jitter))
+        } else {
+            self.interval
+        };
+
+        self.elapsed += delay;
+        self.flight.step(delay, &mut self.rng);
+
+        let telem = self.flight.telemetry(
+            &mut self.rng,
+            self.team_id,
+            MissionTime::from_seconds(self.elapsed),
+            GpsTime::from_seconds(self.launch_time + self.elapsed),
+            self.packet_count,
+            &self.cmd_echo,
+        );
+        self.packet_count += 1;
+
+        let fail: f64 = self.rng.sample(Open01);
+        SynthPacket {
+            delay: Duration::from_secs_f64(delay),
+            telem,
+            dropped: fail < self.failure_rate,
+        }
+    }
+
+    /// Has the limit been reached
+    fn finished(&self, limit: Limit) -> bool {
+        match limit {
+            Limit::Packets(count) => self.packet_count >= count,
+            Limit::Duration(secs) => self.elapsed >= secs,
+            Limit::Forever => false,
+        }
+    }
+
+    /// Send packets to the sink until the limit is reached or the sink disconnects.
+    ///
+    /// When running in real time packets are sent at the rate they are generated, otherwise as
+    /// fast as possible.
+    pub fn run(&mut self, sink: &mut TelemetrySink, real_time: bool, limit: Limit) -> Result<()> {
+        let mut error_count = 0;
+
+        while !self.finished(limit) {
+            let packet = self.next_packet();
+            tracing::trace!("Generated telem = {}", packet.telem);
+
+            if packet.dropped {
+                tracing::info!("Artificially failed a packet: {}", packet.telem);
+            } else if let Err(e) = sink.send(&packet.telem) {
+                if matches!(e.kind(), ErrorKind::BrokenPipe | ErrorKind::ConnectionReset) {
+                    tracing::info!("Client has disconnected, exiting.");
+                    return Ok(());
+                }
+
+                error_count += 1;
+                tracing::warn!(
+                    "Failed to send telemetry packet: {e} - {error_count} errors so far"
+                );
+            }
+
+            if real_time {
+                thread::sleep(packet.delay);
+            }
+        }
+
+        if let Some(faults) = sink.faults() {
+            tracing::info!("Injected faults: {:?}", faults.counts());
+        }
+        sink.finish()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::{HsDeployed, MastRaised, PcDeployed};
+
+    #[test]
+    fn test_seeded_synth_repeats() {
+        let mut a = TelemetrySynth::new(Some(1047));
+        let mut b = TelemetrySynth::new(Some(1047));
+        for _ in 0..100 {
+            assert_eq!(a.next_packet(), b.next_packet());
+        }
+    }
+
+    #[test]
+    fn test_flight_progression() {
+        let mut synth = TelemetrySynth::new(Some(7));
+        synth.failure_rate = 0.0;
+
+        let mut states = vec![];
+        let mut last_time = 0.0;
+        let mut apogee: f64 = 0.0;
+        for count in 0..400 {
+            let packet = synth.next_packet();
+            let telem = packet.telem;
+            assert!(!packet.dropped);
+            assert_eq!(telem.packet_count, count);
+
+            let time = telem.mission_time.as_seconds();
+            assert!(time > last_time);
+            let gps_time = GpsTime::from_seconds(TelemetrySynth::LAUNCH_TIME + time);
+            assert_eq!(telem.gps_time, gps_time);
+            last_time = time;
+            apogee = apogee.max(telem.altitude);
+
+            let state = telem.state.to_string();
+            if states.last() != Some(&state) {
+                states.push(state);
+            }
+
+            if telem.pc_deployed == PcDeployed::Deployed {
+                assert_eq!(telem.hs_deployed, HsDeployed::Deployed);
+            }
+            if telem.mast_raised == MastRaised::Raised {
+                assert!(telem.altitude.abs() < 1.0);
+            }
+        }
+
+        assert_eq!(
+            states,
+            [
+                "LAUNCH_WAIT",
+                "ASCENT",
+                "YEETED",
+                "HS_DEPLOYED",
+                "PC_DEPLOYED",
+                "LANDED",
+                "MAST_RAISED"
+            ]
+        );
+        assert!((600.0..900.0).contains(&apogee), "{apogee}");
+    }
+
+    #[test]
+    fn test_failure_rate() {
+        let mut synth = TelemetrySynth::new(Some(1));
+        synth.failure_rate = 1.0;
+        assert!(synth.next_packet().dropped);
+
+        synth.failure_rate = 0.0;
+        assert!(!synth.next_packet().dropped);
+    }
+}
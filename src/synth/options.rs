@@ -0,0 +1,339 @@
+use super::{Fault, FaultInjector, Framing, SinkKind, SinkSpec, TelemetrySink, TelemetrySynth};
+use crate::constants::{BROADCAST_ADDR, CONTAINER_ADDR, PROBE_ADDR, TEAM_ID};
+use crate::xbee::ApiMode;
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
+
+/// When to stop generating telemetry
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Limit {
+    /// After this many packets
+    Packets(u32),
+    /// After this many seconds of mission time
+    Duration(f64),
+    Forever,
+}
+
+/// The options shared by the telemetry generating tools
+#[derive(Debug, Clone, PartialEq)]
+pub struct SynthOptions {
+    /// Where to send the telemetry
+    pub sink: SinkSpec,
+    /// The number of packets to send per second of mission time
+    pub rate: f64,
+    pub limit: Limit,
+    /// Send packets at the rate they are generated, rather than as fast as possible
+    pub real_time: bool,
+    /// The fraction of packets which are dropped
+    pub failure_rate: f64,
+    /// The chance of each fault being injected into an XBee frame
+    pub faults: BTreeMap<Fault, f64>,
+    /// The seed for the random number generator, random if not given
+    pub seed: Option<u64>,
+    pub team_id: u16,
+}
+
+/// The options accepted by `SynthOptions::from_args`, an ADDR is container, probe, broadcast or a
+/// hex address
+pub const SYNTH_USAGE: &str = "[SINK] [--addr HOST:PORT] [--serial PORT] [--baud BAUD] \
+    [--stdout] [--file PATH] [--xbee] [--rx] [--raw] [--dst ADDR] [--src ADDR] \
+    [--mode 1|2] [--rate HZ] [--count N] [--duration SECS] [--real-time] [--fast] [--failure-rate FRACTION] \
+    [--fault NAME=RATE]... [--seed N] [--team-id ID]";
+
+impl Default for SynthOptions {
+    fn default() -> Self {
+        Self {
+            sink: SinkSpec {
+                kind: SinkKind::Tcp("127.0.0.1:10470".to_string()),
+                framing: Framing::Lines,
+                mode: ApiMode::Escaped,
+            },
+            rate: 1.0,
+            limit: Limit::Packets(1000),
+            real_time: false,
+            failure_rate: 0.001,
+            faults: BTreeMap::new(),
+            seed: None,
+            team_id: TEAM_ID,
+        }
+    }
+}
+
+/// Parse an XBee address given to `option`
+pub fn parse_addr(option: &str, value: &str) -> Result<u16> {
+    Ok(match value {
+        "container" => CONTAINER_ADDR,
+        "probe" => PROBE_ADDR,
+        "broadcast" => BROADCAST_ADDR,
+        hex => u16::from_str_radix(hex.trim_start_matches("0x"), 16)
+            .with_context(|| format!("Invalid {option}"))?,
+    })
+}
+
+/// Parse an XBee API mode, either its number or name
+pub fn parse_api_mode(value: &str) -> Result<ApiMode> {
+    Ok(match value {
+        "1" | "unescaped" => ApiMode::Unescaped,
+        "2" | "escaped" => ApiMode::Escaped,
+        _ => bail!("Invalid API mode {value:?}, expected 1 or 2"),
+    })
+}
+
+/// Parse a rate given to `option`, which must be positive
+pub fn parse_rate(option: &str, value: &str) -> Result<f64> {
+    let rate: f64 = value.parse().with_context(|| format!("Invalid {option}"))?;
+    if rate <= 0.0 || !rate.is_finite() {
+        bail!("{option} must be positive");
+    }
+    Ok(rate)
+}
+
+impl SynthOptions {
+    /// Override these options from command line arguments.
+    ///
+    /// A bare argument is either a sink, as parsed by `SinkSpec`, or a serial port to send to.
+    pub fn from_args(mut self, mut args: impl Iterator<Item = String>) -> Result<Self> {
+        let mut baud = None;
+        let mut mode = None;
+
+        while let Some(arg) = args.next() {
+            // options without a value
+            match arg.as_str() {
+                "--stdout" => self.sink.kind = SinkKind::Stdout,
+                "--xbee" => {
+                    if self.sink.framing == Framing::Lines {
+                        self.sink.framing = Framing::Xbee {
+                            dst: BROADCAST_ADDR,
+                        };
+                    }
+                }
+                "--rx" => {
+                    if !matches!(self.sink.framing, Framing::XbeeRx { .. }) {
+                        self.sink.framing = Framing::XbeeRx {
+                            src: CONTAINER_ADDR,
+                        };
+                    }
+                }
+                "--raw" => self.sink.framing = Framing::Lines,
+                "--real-time" => self.real_time = true,
+                "--fast" => self.real_time = false,
+                _ if !arg.starts_with("--") => match arg.parse() {
+                    Ok(sink) => self.sink = sink,
+                    Err(_) => {
+                        self.sink.kind = SinkKind::Serial {
+                            path: arg,
+                            baud: SinkSpec::DEFAULT_BAUD,
+                        }
+                    }
+                },
+                _ => {
+                    let Some(value) = args.next() else {
+                        bail!("Missing value for {arg}");
+                    };
+
+                    match arg.as_str() {
+                        "--addr" => self.sink.kind = SinkKind::Tcp(value),
+                        "--serial" => {
+                            self.sink.kind = SinkKind::Serial {
+                                path: value,
+                                baud: SinkSpec::DEFAULT_BAUD,
+                            }
+                        }
+                        "--baud" => baud = Some(value.parse().context("Invalid --baud")?),
+                        "--file" => self.sink.kind = SinkKind::File(value.into()),
+                        "--dst" => {
+                            let dst = parse_addr(&arg, &value)?;
+                            self.sink.framing = Framing::Xbee { dst };
+                        }
+                        "--src" => {
+                            let src = parse_addr(&arg, &value)?;
+                            self.sink.framing = Framing::XbeeRx { src };
+                        }
+                        "--mode" => mode = Some(parse_api_mode(&value)?),
+                        "--rate" => self.rate = parse_rate(&arg, &value)?,
+                        "--count" => {
+                            self.limit = match value.parse().context("Invalid --count")? {
+                                0 => Limit::Forever,
+                                count => Limit::Packets(count),
+                            }
+                        }
+                        "--duration" => {
+                            self.limit =
+                                Limit::Duration(value.parse().context("Invalid --duration")?)
+                        }
+                        "--failure-rate" => {
+                            self.failure_rate = value.parse().context("Invalid --failure-rate")?
+                        }
+                        "--fault" => {
+                            let Some((name, rate)) = value.split_once('=') else {
+                                bail!("Invalid --fault {value:?}, expected NAME=RATE");
+                            };
+                            let rate = rate.parse().context("Invalid --fault rate")?;
+                            self.faults.insert(name.parse()?, rate);
+                        }
+                        "--seed" => self.seed = Some(value.parse().context("Invalid --seed")?),
+                        "--team-id" => self.team_id = value.parse().context("Invalid --team-id")?,
+                        _ => bail!("Unrecognised argument {arg:?}"),
+                    }
+                }
+            }
+        }
+
+        if let Some(baud) = baud {
+            let SinkKind::Serial {
+                baud: port_baud, ..
+            } = &mut self.sink.kind
+            else {
+                bail!("--baud can only be used with a serial port");
+            };
+            *port_baud = baud;
+        }
+        if let Some(mode) = mode {
+            if self.sink.framing == Framing::Lines {
+                bail!("--mode can only be used with XBee frames");
+            }
+            self.sink.mode = mode;
+        }
+        if !(0.0..=1.0).contains(&self.failure_rate) {
+            bail!("--failure-rate must be between 0 and 1");
+        }
+        if self.faults.values().any(|rate| !(0.0..=1.0).contains(rate)) {
+            bail!("--fault rates must be between 0 and 1");
+        }
+        if !self.faults.is_empty() && self.sink.framing == Framing::Lines {
+            bail!("Faults can only be injected into XBee frames");
+        }
+        if matches!(self.limit, Limit::Duration(secs) if secs <= 0.0 || !secs.is_finite()) {
+            bail!("--duration must be positive");
+        }
+
+        Ok(self)
+    }
+
+    /// Create a synthesiser configured by these options
+    pub fn synth(&self) -> TelemetrySynth {
+        let mut synth = TelemetrySynth::new(self.seed);
+        synth.team_id = self.team_id;
+        synth.interval = 1.0 / self.rate;
+        synth.jitter = synth.interval / 10.0;
+        synth.failure_rate = self.failure_rate;
+        synth
+    }
+
+    /// Open the sink, injecting any faults
+    pub fn open_sink(&self) -> Result<TelemetrySink> {
+        let sink = TelemetrySink::open(&self.sink)?;
+        if self.faults.is_empty() {
+            return Ok(sink);
+        }
+
+        // seeded differently so the faults aren't correlated with the telemetry
+        let seed = self.seed.map(|seed| seed.wrapping_add(1));
+        Ok(sink.with_faults(FaultInjector::new(self.faults.clone(), seed)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(defaults: SynthOptions, args: &str) -> Result<SynthOptions> {
+        defaults.from_args(args.split_whitespace().map(str::to_string))
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let options = parse(
+            Default::default(),
+            "--serial /dev/ttyUSB0 --baud 9600 --dst probe --mode 1 --rate 4 --duration 60 --real-time \
+             --failure-rate 0.1 --fault merge=0.5 --fault corrupt=0.01 --seed 42 --team-id 1000",
+        )
+        .unwrap();
+
+        assert_eq!(
+            options,
+            SynthOptions {
+                sink: SinkSpec {
+                    kind: SinkKind::Serial {
+                        path: "/dev/ttyUSB0".to_string(),
+                        baud: 9600
+                    },
+                    framing: Framing::Xbee { dst: PROBE_ADDR },
+                    mode: ApiMode::Unescaped,
+                },
+                rate: 4.0,
+                limit: Limit::Duration(60.0),
+                real_time: true,
+                failure_rate: 0.1,
+                faults: [(Fault::Merge, 0.5), (Fault::Corrupt, 0.01)].into(),
+                seed: Some(42),
+                team_id: 1000,
+            }
+        );
+
+        let synth = options.synth();
+        assert_eq!(synth.interval, 0.25);
+        assert_eq!(synth.team_id, 1000);
+    }
+
+    #[test]
+    fn test_bare_sink() {
+        let xbee = SynthOptions {
+            sink: SinkSpec {
+                kind: SinkKind::Stdout,
+                framing: Framing::Xbee {
+                    dst: BROADCAST_ADDR,
+                },
+                mode: ApiMode::Escaped,
+            },
+            ..Default::default()
+        };
+
+        // a serial port keeps the default framing
+        let options = parse(xbee.clone(), "COM3 --count 0").unwrap();
+        assert_eq!(
+            options.sink.kind,
+            SinkKind::Serial {
+                path: "COM3".to_string(),
+                baud: SinkSpec::DEFAULT_BAUD
+            }
+        );
+        assert_eq!(options.sink.framing, xbee.sink.framing);
+        assert_eq!(options.limit, Limit::Forever);
+
+        let options = parse(xbee, "file:out.txt --addr localhost:1234").unwrap();
+        assert_eq!(
+            options.sink,
+            SinkSpec {
+                kind: SinkKind::Tcp("localhost:1234".to_string()),
+                framing: Framing::Lines,
+                mode: ApiMode::Escaped,
+            }
+        );
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        for args in [
+            "--rate",
+            "--rate 0",
+            "--rate fast",
+            "--failure-rate 2",
+            "--duration -1",
+            "--duration NaN",
+            "--duration inf",
+            "--baud 9600",
+            "--dst 0xGG",
+            "--xbee --fault merge",
+            "--xbee --fault lightning=0.1",
+            "--xbee --fault merge=1.5",
+            "--fault merge=0.1",
+            "--src nowhere",
+            "--mode 1",
+            "--xbee --mode 3",
+            "--verbose",
+        ] {
+            assert!(parse(Default::default(), args).is_err(), "{args:?}");
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use crate::xbee::{
+    ApiMode, DecodeEvent, DeliveryStatus, FrameDecoder, RxPacket, TxFrame, TxStatus, XbeePacket,
+};
+use anyhow::{Context, Result};
+use parking_lot::Mutex;
+use serialport::SerialPort;
+#[cfg(unix)]
+use serialport::TTYPort;
+use std::io::{self, ErrorKind, Read, Write};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Where the CanSat's radio link is opened
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum RadioPort {
+    Serial {
+        path: String,
+        baud: u32,
+    },
+    /// A new pty, which the ground station can open like a radio
+    #[cfg(unix)]
+    Pty,
+}
+
+/// The CanSat's end of an XBee link to the ground station. It sends frames as the ground
+/// station's radio would receive them and reads the TX requests the ground station sends.
+pub struct RadioLink {
+    /// Locked while writing so frames from different threads aren't interleaved
+    port: Mutex<Box<dyn SerialPort>>,
+    mode: ApiMode,
+
+    /// The slave end of the pty, kept open so the master end stays usable when the ground
+    /// station disconnects
+    #[cfg(unix)]
+    slave: Option<TTYPort>,
+}
+
+impl RadioLink {
+    pub fn open(port: &RadioPort, mode: ApiMode) -> Result<Self> {
+        #[cfg(unix)]
+        let mut slave = None;
+        let port: Box<dyn SerialPort> = match port {
+            RadioPort::Serial { path, baud } => serialport::new(path, *baud)
+                .timeout(Duration::from_millis(100))
+                .open()
+                .with_context(|| format!("Failed to open serial port {path}"))?,
+            #[cfg(unix)]
+            RadioPort::Pty => {
+                // the ground station opens the slave end like any other serial port
+                let (master, pty) = TTYPort::pair().context("Failed to create a pty")?;
+                let pty = slave.insert(pty);
+                pty.set_exclusive(false)?;
+                Box::new(master)
+            }
+        };
+
+        Ok(Self {
+            port: Mutex::new(port),
+            mode,
+            #[cfg(unix)]
+            slave,
+        })
+    }
+
+    /// The path the ground station opens, if the link is a pty
+    #[cfg(unix)]
+    pub fn pty_path(&self) -> Option<String> {
+        self.slave.as_ref()?.name()
+    }
+
+    /// Write a frame to the ground station.
+    ///
+    /// The frame is dropped if the port's buffer is full because the ground station isn't
+    /// reading it, like a real radio's buffer overflowing.
+    pub fn send(&self, packet: XbeePacket) -> io::Result<()> {
+        let data = packet.serialise(self.mode)?;
+        match self.port.lock().write_all(&data) {
+            Err(e) if matches!(e.kind(), ErrorKind::TimedOut | ErrorKind::WouldBlock) => {
+                tracing::warn!("Dropped a frame as the ground station isn't reading - {e}");
+                Ok(())
+            }
+            res => res,
+        }
+    }
+
+    /// Send data as if it was received over the air from `src_addr`
+    pub fn send_rx(&self, src_addr: u16, rssi: i8, data: Vec<u8>) -> io::Result<()> {
+        let packet = RxPacket {
+            src_addr,
+            rssi,
+            options: 0,
+            data,
+        };
+        self.send(packet.try_into()?)
+    }
+
+    /// Reply to a TX request with its delivery status
+    pub fn send_status(&self, frame_id: u8, status: DeliveryStatus) -> io::Result<()> {
+        let status = TxStatus { frame_id, status };
+        tracing::debug!("Replying with {status}");
+        self.send(status.into())
+    }
+
+    /// Spawn a thread which passes each TX request the ground station sends to `handle`, until
+    /// it returns an error
+    pub fn spawn_reader(
+        &self,
+        mut handle: impl FnMut(TxFrame) -> io::Result<()> + Send + 'static,
+    ) -> io::Result<JoinHandle<io::Result<()>>> {
+        let mut reader = self.port.lock().try_clone()?;
+        let mut decoder = FrameDecoder::new(self.mode);
+
+        thread::Builder::new()
+            .name("xbee".to_string())
+            .spawn(move || {
+                let mut buf = [0u8; 1024];
+                loop {
+                    let n = match reader.read(&mut buf) {
+                        Ok(n) => n,
+                        Err(e) if e.kind() == ErrorKind::TimedOut => continue,
+                        Err(e) => {
+                            // reading the master end of a pty fails while nothing has it open
+                            tracing::trace!("Failed to read from the radio - {e:?}");
+                            thread::sleep(Duration::from_millis(100));
+                            continue;
+                        }
+                    };
+
+                    for event in decoder.feed(&buf[..n]) {
+                        let DecodeEvent::Frame(packet) = event else {
+                            tracing::warn!("Received invalid data - {event:?}");
+                            continue;
+                        };
+                        let Ok(frame) = TxFrame::try_from(packet.clone()) else {
+                            tracing::warn!("Ignoring unsupported frame - {packet}");
+                            continue;
+                        };
+
+                        tracing::info!("Received {frame}");
+                        handle(frame)?;
+                    }
+                }
+            })
+    }
+}
@@ -0,0 +1,345 @@
+use super::FaultInjector;
+use crate::constants::{BROADCAST_ADDR, CONTAINER_ADDR};
+use crate::telemetry::Telemetry;
+use crate::xbee::{ApiMode, RxPacket, TxRequest, XbeePacket};
+use anyhow::{bail, Context, Result};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::{thread, time::Duration};
+
+/// How each packet is written to the sink
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Framing {
+    /// One packet per line, as the ground station's TCP listener expects
+    Lines,
+    /// Each packet in an XBee TX request to `dst`, as the CanSat's radio would send it
+    Xbee { dst: u16 },
+    /// Each packet in an XBee RX frame from `src`, as the ground station's radio would receive
+    /// it. These can be replayed by the ground station as a raw radio log.
+    XbeeRx { src: u16 },
+}
+
+impl Framing {
+    /// The RSSI reported in RX frames, in -dBm
+    const RSSI: i8 = 40;
+}
+
+/// Where synthesised telemetry is written
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum SinkKind {
+    Tcp(String),
+    Serial { path: String, baud: u32 },
+    Stdout,
+    File(PathBuf),
+}
+
+/// A sink and its framing, parsed from strings such as `tcp:127.0.0.1:10470`,
+/// `serial:/dev/ttyUSB0@230400`, `stdout` or `file:telem.txt`. Prefixing the sink with `xbee+`
+/// frames each packet as an XBee TX request, e.g. `xbee+serial:/dev/ttyUSB0`, and `rx+` as an
+/// XBee RX frame, e.g. `rx+file:radio_data.raw`. XBee frames are escaped unless `mode` is
+/// changed.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SinkSpec {
+    pub kind: SinkKind,
+    pub framing: Framing,
+    /// The API mode XBee frames are written in, this has no effect when writing lines
+    pub mode: ApiMode,
+}
+
+impl SinkSpec {
+    /// The baud rate used if a serial sink doesn't give one
+    pub const DEFAULT_BAUD: u32 = 230400;
+}
+
+impl FromStr for SinkSpec {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (framing, sink) = if let Some(sink) = s.strip_prefix("xbee+") {
+            let dst = BROADCAST_ADDR;
+            (Framing::Xbee { dst }, sink)
+        } else if let Some(sink) = s.strip_prefix("rx+") {
+            let src = CONTAINER_ADDR;
+            (Framing::XbeeRx { src }, sink)
+        } else {
+            (Framing::Lines, s)
+        };
+
+        let kind = match sink.split_once(':') {
+            None if sink == "stdout" => SinkKind::Stdout,
+            Some(("tcp", addr)) if !addr.is_empty() => SinkKind::Tcp(addr.to_string()),
+            Some(("serial", port)) if !port.is_empty() => match port.rsplit_once('@') {
+                Some((path, baud)) => SinkKind::Serial {
+                    path: path.to_string(),
+                    baud: baud
+                        .parse()
+                        .with_context(|| format!("Invalid baud rate {baud:?}"))?,
+                },
+                None => SinkKind::Serial {
+                    path: port.to_string(),
+                    baud: Self::DEFAULT_BAUD,
+                },
+            },
+            Some(("file", path)) if !path.is_empty() => SinkKind::File(path.into()),
+            _ => bail!(
+                "Unknown sink {s:?}, expected tcp:ADDR, serial:PORT[@BAUD], stdout or file:PATH"
+            ),
+        };
+
+        Ok(Self {
+            kind,
+            framing,
+            mode: ApiMode::Escaped,
+        })
+    }
+}
+
+/// Connect to the ground station's telemetry listener, retrying until it is listening
+pub fn connect_tcp(addr: &str) -> TcpStream {
+    loop {
+        match TcpStream::connect(addr) {
+            Ok(s) => return s,
+            Err(e) => {
+                tracing::warn!("Failed to connect to the ground station on {addr} - {e}");
+                thread::sleep(Duration::from_millis(200));
+            }
+        }
+    }
+}
+
+/// Writes telemetry to a sink, framing it as needed
+pub struct TelemetrySink {
+    writer: Box<dyn Write + Send>,
+    framing: Framing,
+    mode: ApiMode,
+    frame_id: u8,
+
+    /// Damages XBee frames before they are written
+    faults: Option<FaultInjector>,
+}
+
+impl TelemetrySink {
+    pub fn new(writer: Box<dyn Write + Send>, framing: Framing, mode: ApiMode) -> Self {
+        Self {
+            writer,
+            framing,
+            mode,
+            frame_id: 0,
+            faults: None,
+        }
+    }
+
+    /// Inject faults into the frames written, this has no effect when writing lines
+    pub fn with_faults(mut self, faults: FaultInjector) -> Self {
+        self.faults = Some(faults);
+        self
+    }
+
+    pub fn faults(&self) -> Option<&FaultInjector> {
+        self.faults.as_ref()
+    }
+
+    /// Open the sink, a TCP sink is retried until the ground station is listening
+    pub fn open(spec: &SinkSpec) -> Result<Self> {
+        let writer: Box<dyn Write + Send> = match &spec.kind {
+            SinkKind::Tcp(addr) => Box::new(connect_tcp(addr)),
+            SinkKind::Serial { path, baud } => Box::new(
+                serialport::new(path, *baud)
+                    .open()
+                    .with_context(|| format!("Failed to open serial port {path}"))?,
+            ),
+            SinkKind::Stdout => Box::new(io::stdout()),
+            SinkKind::File(path) => Box::new(BufWriter::new(
+                File::create(path).with_context(|| format!("Failed to create {path:?}"))?,
+            )),
+        };
+
+        Ok(Self::new(writer, spec.framing, spec.mode))
+    }
+
+    /// Put data in an XBee frame
+    fn frame(framing: Framing, frame_id: u8, data: Vec<u8>) -> io::Result<XbeePacket> {
+        match framing {
+            Framing::Lines => unreachable!("Lines aren't framed"),
+            Framing::Xbee { dst } => TxRequest::new(frame_id, dst, data).try_into(),
+            Framing::XbeeRx { src } => RxPacket {
+                src_addr: src,
+                rssi: Framing::RSSI,
+                options: 0,
+                data,
+            }
+            .try_into(),
+        }
+    }
+
+    /// Write a single packet to the sink
+    pub fn send(&mut self, telem: &Telemetry) -> io::Result<()> {
+        if self.framing == Framing::Lines {
+            writeln!(self.writer, "{telem}")?;
+            return self.writer.flush();
+        }
+
+        let (framing, frame_id) = (self.framing, self.frame_id);
+        let frame = |data| Self::frame(framing, frame_id, data);
+        let data = telem.to_string().into_bytes();
+        let bytes = match &mut self.faults {
+            Some(faults) => faults.inject(data, frame, self.mode)?,
+            None => frame(data)?.serialise(self.mode)?,
+        };
+        self.writer.write_all(&bytes)?;
+        self.frame_id = self.frame_id.wrapping_add(1);
+
+        self.writer.flush()
+    }
+
+    /// Write any frames still held back by the fault injector
+    pub fn finish(&mut self) -> io::Result<()> {
+        if let Some(faults) = &mut self.faults {
+            let (framing, frame_id) = (self.framing, self.frame_id);
+            let bytes = faults.flush(|data| Self::frame(framing, frame_id, data), self.mode)?;
+            self.writer.write_all(&bytes)?;
+        }
+
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synth::{Fault, FaultInjector, TelemetrySynth};
+    use crate::telemetry::TelemetryBuilder;
+    use crate::xbee::{DecodeEvent, FrameDecoder};
+    use enum_iterator::all;
+    use std::sync::{Arc, Mutex};
+
+    /// A writer which can be inspected after being boxed into a sink
+    #[derive(Clone, Default)]
+    struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_parse_spec() {
+        let spec = |s: &str| s.parse::<SinkSpec>().unwrap();
+
+        assert_eq!(
+            spec("tcp:127.0.0.1:10470"),
+            SinkSpec {
+                kind: SinkKind::Tcp("127.0.0.1:10470".to_string()),
+                framing: Framing::Lines,
+                mode: ApiMode::Escaped,
+            }
+        );
+        assert_eq!(
+            spec("xbee+serial:/dev/ttyUSB0@9600"),
+            SinkSpec {
+                kind: SinkKind::Serial {
+                    path: "/dev/ttyUSB0".to_string(),
+                    baud: 9600
+                },
+                framing: Framing::Xbee {
+                    dst: BROADCAST_ADDR
+                },
+                mode: ApiMode::Escaped,
+            }
+        );
+        assert_eq!(
+            spec("serial:COM3").kind,
+            SinkKind::Serial {
+                path: "COM3".to_string(),
+                baud: SinkSpec::DEFAULT_BAUD
+            }
+        );
+        assert_eq!(
+            spec("rx+file:radio_data.raw").framing,
+            Framing::XbeeRx {
+                src: CONTAINER_ADDR
+            }
+        );
+        assert_eq!(spec("stdout").kind, SinkKind::Stdout);
+        assert_eq!(spec("file:out.txt").kind, SinkKind::File("out.txt".into()));
+
+        for invalid in ["", "tcp:", "serial:COM3@fast", "udp:1.2.3.4:5", "xbee+"] {
+            assert!(invalid.parse::<SinkSpec>().is_err(), "{invalid:?}");
+        }
+    }
+
+    #[test]
+    fn test_xbee_framing() {
+        let telem = TelemetryBuilder::default().build();
+        for mode in all::<ApiMode>() {
+            let buf = SharedBuf::default();
+            let framing = Framing::Xbee { dst: 0x00_01 };
+            let mut sink = TelemetrySink::new(Box::new(buf.clone()), framing, mode);
+            sink.send(&telem).unwrap();
+            sink.send(&telem).unwrap();
+
+            let mut decoder = FrameDecoder::new(mode);
+            let events = decoder.feed(&buf.0.lock().unwrap());
+            assert_eq!(events.len(), 2, "{mode:?}");
+            for (frame_id, event) in events.into_iter().enumerate() {
+                let DecodeEvent::Frame(packet) = event else {
+                    panic!("Expected a frame in {mode:?}, got {event:?}");
+                };
+                let req = TxRequest::try_from(packet).unwrap();
+                assert_eq!(req.frame_id, frame_id as u8);
+                assert_eq!(req.dst, 0x00_01);
+                assert_eq!(String::from_utf8(req.data).unwrap(), telem.to_string());
+            }
+        }
+    }
+
+    #[test]
+    fn test_faults_reach_ground_station() {
+        // half the frames are truncated, the ground station should resync and receive the rest
+        let buf = SharedBuf::default();
+        let faults = FaultInjector::new([(Fault::Truncate, 0.5)].into(), Some(1));
+        let mut sink = TelemetrySink::new(
+            Box::new(buf.clone()),
+            Framing::XbeeRx {
+                src: CONTAINER_ADDR,
+            },
+            ApiMode::Escaped,
+        )
+        .with_faults(faults);
+
+        let mut synth = TelemetrySynth::new(Some(1));
+        synth.failure_rate = 0.0;
+        for _ in 0..100 {
+            sink.send(&synth.next_packet().telem).unwrap();
+        }
+        sink.finish().unwrap();
+        let truncated = sink.faults().unwrap().counts()[&Fault::Truncate];
+
+        let mut decoder = FrameDecoder::new(ApiMode::Escaped);
+        let mut events = decoder.feed(&buf.0.lock().unwrap());
+        events.extend(decoder.flush());
+
+        let received: Vec<u32> = events
+            .into_iter()
+            .filter_map(|event| {
+                let DecodeEvent::Frame(packet) = event else {
+                    return None;
+                };
+                let rx = RxPacket::try_from(packet).ok()?;
+                let telem: Telemetry = String::from_utf8(rx.data).ok()?.parse().ok()?;
+                Some(telem.packet_count)
+            })
+            .collect();
+        assert!(truncated > 0);
+        assert_eq!(received.len() + truncated as usize, 100);
+        assert!(received.windows(2).all(|w| w[0] < w[1]));
+    }
+}
@@ -23,6 +23,17 @@ impl GpsTime {
 
         Ok(Self { h, m, s })
     }
+
+    /// The time `sec` seconds after midnight, wrapping around at the end of the day
+    pub fn from_seconds(sec: f64) -> Self {
+        let sec = sec.rem_euclid(24.0 * 3600.0) as u32;
+
+        Self {
+            h: (sec / 3600) as _,
+            m: (sec / 60 % 60) as _,
+            s: (sec % 60) as _,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -44,6 +55,15 @@ mod tests {
         ts.unwrap_err();
     }
 
+    #[test]
+    fn test_gps_time_from_seconds() {
+        assert_eq!(GpsTime::from_seconds(3723.9), GpsTime { h: 1, m: 2, s: 3 });
+        assert_eq!(
+            GpsTime::from_seconds(86400.0 + 59.0),
+            GpsTime { h: 0, m: 0, s: 59 }
+        );
+    }
+
     #[test]
     fn test_misstion_time_display_low_numbers() {
         let gt = GpsTime { h: 1, m: 2, s: 3 };
@@ -178,6 +178,82 @@ impl fmt::Display for TelemetryField {
     }
 }
 
+/// Builds telemetry for tests, with typical values for any fields the test doesn't set
+#[cfg(test)]
+#[derive(Debug, Clone)]
+pub struct TelemetryBuilder(Telemetry);
+
+#[cfg(test)]
+impl Default for TelemetryBuilder {
+    fn default() -> Self {
+        Self(Telemetry {
+            team_id: 1047,
+            mission_time: MissionTime {
+                h: 0,
+                m: 45,
+                s: 8,
+                cs: 9,
+            },
+            packet_count: 0,
+            mode: Mode::Flight,
+            state: State::Yeeted,
+            altitude: 375.5,
+            hs_deployed: HsDeployed::Deployed,
+            pc_deployed: PcDeployed::Deployed,
+            mast_raised: MastRaised::Raised,
+            temperature: 35.8,
+            voltage: 5.0,
+            pressure: 98.9,
+            gps_time: GpsTime { h: 0, m: 45, s: 8 },
+            gps_altitude: 1975.5,
+            gps_latitude: 37.2244,
+            gps_longitude: -80.2286,
+            gps_sats: 16,
+            tilt_x: -25.74,
+            tilt_y: 12.54,
+            cmd_echo: "CXON".to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+impl TelemetryBuilder {
+    pub fn packet_count(mut self, packet_count: u32) -> Self {
+        self.0.packet_count = packet_count;
+        self
+    }
+
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.0.mode = mode;
+        self
+    }
+
+    pub fn state(mut self, state: &str) -> Self {
+        self.0.state = state.parse().unwrap();
+        self
+    }
+
+    pub fn altitude(mut self, altitude: f64) -> Self {
+        self.0.altitude = altitude;
+        self
+    }
+
+    /// The pressure in kPa
+    pub fn pressure(mut self, pressure: f64) -> Self {
+        self.0.pressure = pressure;
+        self
+    }
+
+    pub fn cmd_echo(mut self, cmd_echo: &str) -> Self {
+        self.0.cmd_echo = cmd_echo.to_string();
+        self
+    }
+
+    pub fn build(self) -> Telemetry {
+        self.0
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
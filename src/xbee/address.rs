@@ -0,0 +1,26 @@
+use std::fmt;
+
+/// The 64-bit address used to broadcast to every radio
+pub const BROADCAST_ADDR_64: u64 = 0x0000_0000_0000_FFFF;
+
+/// The 16-bit address which means the 16-bit address is unknown or 64-bit addressing is used
+pub const UNKNOWN_ADDR_16: u16 = 0xFFFE;
+
+/// The address of a radio, either a 16-bit network address or a 64-bit serial number
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Address {
+    /// A 16-bit address, set on the radio with the MY parameter
+    Short(u16),
+
+    /// A 64-bit address, the radio's serial number (SH + SL)
+    Long(u64),
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Address::Short(addr) => write!(f, "{:02X}:{:02X}", addr >> 8, addr & 0xFF),
+            Address::Long(addr) => write!(f, "{:08X}:{:08X}", addr >> 32, addr & 0xFFFF_FFFF),
+        }
+    }
+}
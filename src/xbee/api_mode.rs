@@ -0,0 +1,76 @@
+use crate::as_str::AsStr;
+use enum_iterator::Sequence;
+use std::fmt;
+
+/// The start delimiter of every API frame
+pub const START_DELIMITER: u8 = 0x7E;
+
+/// The escape byte used in API mode 2, the byte following it is XORed with `ESCAPE_XOR`
+pub const ESCAPE: u8 = 0x7D;
+
+/// The value escaped bytes are XORed with
+pub const ESCAPE_XOR: u8 = 0x20;
+
+/// Software flow control characters, these must be escaped in API mode 2
+pub const XON: u8 = 0x11;
+pub const XOFF: u8 = 0x13;
+
+/// The API mode the radio is configured in, set on the radio with the AP parameter
+#[derive(Sequence, Default, Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ApiMode {
+    /// AP=1 - API mode without escaped characters
+    Unescaped,
+
+    /// AP=2 - API mode with escaped characters
+    #[default]
+    Escaped,
+}
+
+impl ApiMode {
+    /// Does this byte need escaping when sent after the start delimiter?
+    pub const fn needs_escape(byte: u8) -> bool {
+        matches!(byte, START_DELIMITER | ESCAPE | XON | XOFF)
+    }
+
+    /// Write a byte to the buffer, escaping it if required by this mode
+    pub(crate) fn push_byte(&self, buf: &mut Vec<u8>, byte: u8) {
+        if *self == ApiMode::Escaped && Self::needs_escape(byte) {
+            buf.push(ESCAPE);
+            buf.push(byte ^ ESCAPE_XOR);
+        } else {
+            buf.push(byte);
+        }
+    }
+
+    /// The value of the AP parameter for this mode
+    pub const fn ap_value(&self) -> u8 {
+        match self {
+            ApiMode::Unescaped => 1,
+            ApiMode::Escaped => 2,
+        }
+    }
+
+    /// The mode set by a value of the AP parameter, `None` for transparent mode (AP=0)
+    pub const fn from_ap_value(ap: u8) -> Option<Self> {
+        match ap {
+            1 => Some(ApiMode::Unescaped),
+            2 => Some(ApiMode::Escaped),
+            _ => None,
+        }
+    }
+}
+
+impl AsStr for ApiMode {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ApiMode::Unescaped => "API (AP=1)",
+            ApiMode::Escaped => "API Escaped (AP=2)",
+        }
+    }
+}
+
+impl fmt::Display for ApiMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
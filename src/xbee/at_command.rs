@@ -0,0 +1,279 @@
+use crate::as_str::AsStr;
+use crate::xbee::ParsePacketError::IncorrectFrameType;
+use crate::xbee::{AtParameter, AtValue, ParsePacketError, XbeePacket};
+use byteorder::{ReadBytesExt, WriteBytesExt};
+use enum_primitive_derive::Primitive;
+use num_traits::FromPrimitive;
+use std::fmt;
+use std::io::{Cursor, Read, Write};
+
+/// A local AT command (frame type 0x08), or a queued AT command (frame type 0x09) which
+/// isn't applied until the changes are applied with AC or written with WR
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AtCommand {
+    /// The frame ID, 0 means no response is sent
+    pub frame_id: u8,
+    /// The two character AT command
+    pub command: [u8; 2],
+    /// The value to set, empty to query the current value
+    pub parameter: Vec<u8>,
+    /// Is the command queued rather than applied immediately?
+    pub queued: bool,
+}
+
+impl AtCommand {
+    pub fn new(frame_id: u8, command: [u8; 2], parameter: impl AsRef<[u8]>) -> Self {
+        Self {
+            frame_id,
+            command,
+            parameter: parameter.as_ref().to_vec(),
+            queued: false,
+        }
+    }
+
+    /// Query the current value of a parameter
+    pub fn query(frame_id: u8, param: AtParameter) -> Self {
+        Self::new(frame_id, param.command(), [])
+    }
+
+    /// Set the value of a parameter
+    pub fn set(frame_id: u8, value: &AtValue) -> Self {
+        Self::new(frame_id, value.parameter().command(), value.encode())
+    }
+
+    /// Queue a new value for a parameter, it isn't applied until AC or WR is sent
+    pub fn queue(frame_id: u8, value: &AtValue) -> Self {
+        Self {
+            queued: true,
+            ..Self::set(frame_id, value)
+        }
+    }
+
+    /// WR - write the current parameter values to non-volatile memory
+    pub fn write(frame_id: u8) -> Self {
+        Self::new(frame_id, *b"WR", [])
+    }
+
+    /// AC - apply any queued changes
+    pub fn apply_changes(frame_id: u8) -> Self {
+        Self::new(frame_id, *b"AC", [])
+    }
+}
+
+impl TryFrom<AtCommand> for XbeePacket {
+    type Error = std::io::Error;
+
+    fn try_from(cmd: AtCommand) -> Result<Self, Self::Error> {
+        let mut buf = vec![];
+
+        // frame ID
+        buf.write_u8(cmd.frame_id)?;
+
+        // AT command
+        buf.write_all(&cmd.command)?;
+
+        // parameter value
+        buf.write_all(&cmd.parameter)?;
+
+        let frame_type = if cmd.queued { 0x09 } else { 0x08 };
+        Ok(XbeePacket::new(frame_type, buf))
+    }
+}
+
+impl TryFrom<XbeePacket> for AtCommand {
+    type Error = ParsePacketError;
+
+    fn try_from(xbp: XbeePacket) -> Result<Self, Self::Error> {
+        let XbeePacket {
+            frame_type,
+            ref data,
+            ..
+        } = xbp;
+
+        // check the frame type
+        if frame_type != 0x08 && frame_type != 0x09 {
+            return Err(IncorrectFrameType);
+        }
+
+        let mut cur = Cursor::new(data.as_slice());
+        let frame_id = cur.read_u8()?;
+        let mut command = [0; 2];
+        cur.read_exact(&mut command)?;
+        let pos = cur.position() as usize;
+
+        Ok(AtCommand {
+            frame_id,
+            command,
+            parameter: data[pos..].to_vec(),
+            queued: frame_type == 0x09,
+        })
+    }
+}
+
+impl fmt::Display for AtCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "AtCommand {{ frame_id: {}, command: {}, parameter: {:02X?} }}",
+            self.frame_id,
+            String::from_utf8_lossy(&self.command),
+            self.parameter,
+        )
+    }
+}
+
+/// The status of an AT command response
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Primitive)]
+pub enum AtCommandStatus {
+    Ok = 0x00,
+    Failure = 0x01,
+    InvalidCommand = 0x02,
+    InvalidParameter = 0x03,
+    TxFailure = 0x04,
+    UNKNOWN = 0xFF,
+}
+
+impl AsStr for AtCommandStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ok => "OK",
+            Self::Failure => "Error",
+            Self::InvalidCommand => "Invalid command",
+            Self::InvalidParameter => "Invalid parameter",
+            Self::TxFailure => "Transmission failure",
+            Self::UNKNOWN => "Unknown",
+        }
+    }
+}
+
+impl fmt::Display for AtCommandStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The response to a local AT command (frame type 0x88)
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AtCommandResponse {
+    pub frame_id: u8,
+    pub command: [u8; 2],
+    pub status: AtCommandStatus,
+    /// The value of the parameter if it was queried
+    pub data: Vec<u8>,
+}
+
+impl AtCommandResponse {
+    /// The parameter this response is for, if it is one we know about
+    pub fn parameter(&self) -> Option<AtParameter> {
+        AtParameter::from_command(self.command)
+    }
+
+    /// The typed value returned by a successful query
+    pub fn value(&self) -> Option<AtValue> {
+        if self.status != AtCommandStatus::Ok || self.data.is_empty() {
+            return None;
+        }
+
+        AtValue::decode(self.parameter()?, &self.data).ok()
+    }
+}
+
+impl TryFrom<XbeePacket> for AtCommandResponse {
+    type Error = ParsePacketError;
+
+    fn try_from(xbp: XbeePacket) -> Result<Self, Self::Error> {
+        let XbeePacket {
+            frame_type,
+            ref data,
+            ..
+        } = xbp;
+
+        // check the frame type
+        if frame_type != 0x88 {
+            return Err(IncorrectFrameType);
+        }
+
+        let mut cur = Cursor::new(data.as_slice());
+        let frame_id = cur.read_u8()?;
+        let mut command = [0; 2];
+        cur.read_exact(&mut command)?;
+        let status = AtCommandStatus::from_u8(cur.read_u8()?).unwrap_or(AtCommandStatus::UNKNOWN);
+        let pos = cur.position() as usize;
+
+        Ok(AtCommandResponse {
+            frame_id,
+            command,
+            status,
+            data: data[pos..].to_vec(),
+        })
+    }
+}
+
+impl fmt::Display for AtCommandResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "AtCommandResponse {{ frame_id: {}, command: {}, status: {}, data: {:02X?} }}",
+            self.frame_id,
+            String::from_utf8_lossy(&self.command),
+            self.status,
+            self.data,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xbee::ApiMode;
+    use hex_literal::hex;
+
+    #[test]
+    fn test_at_command_serialisation() {
+        // example taken from the XBee documentation
+        const NJ: &[u8] = &hex!("7E 00 04 08 52 4E 4A 0D");
+        let packet: XbeePacket = AtCommand::new(0x52, *b"NJ", []).try_into().unwrap();
+        assert_eq!(packet.serialise(ApiMode::Unescaped).unwrap(), NJ);
+
+        const PL: &[u8] = &hex!("7E 00 05 08 01 50 4C 04 56");
+        let cmd = AtCommand::set(1, &AtValue::PowerLevel(4));
+        let packet: XbeePacket = cmd.clone().try_into().unwrap();
+        assert_eq!(packet.clone().serialise(ApiMode::Unescaped).unwrap(), PL);
+        assert_eq!(AtCommand::try_from(packet).unwrap(), cmd);
+
+        // queued values are sent in a different frame type
+        let cmd = AtCommand::queue(1, &AtValue::PowerLevel(4));
+        let packet: XbeePacket = cmd.clone().try_into().unwrap();
+        assert_eq!(packet.frame_type, 0x09);
+        assert_eq!(AtCommand::try_from(packet).unwrap(), cmd);
+    }
+
+    #[test]
+    fn test_at_response_parse() {
+        let raw = hex!("7E 00 06 88 01 43 48 00 0C DF");
+        let xbp = XbeePacket::decode(&raw, ApiMode::Unescaped).unwrap();
+        let resp = AtCommandResponse::try_from(xbp).unwrap();
+
+        assert_eq!(
+            resp,
+            AtCommandResponse {
+                frame_id: 1,
+                command: *b"CH",
+                status: AtCommandStatus::Ok,
+                data: vec![0x0C],
+            }
+        );
+        assert_eq!(resp.value(), Some(AtValue::Channel(0x0C)));
+    }
+
+    #[test]
+    fn test_at_response_parse_fails_invalid_frame_type() {
+        let xbp = XbeePacket {
+            frame_type: 0x08,
+            data: hex!("01 43 48 00 0C").to_vec(),
+            checksum: 1,
+        };
+
+        let _resp = AtCommandResponse::try_from(xbp).unwrap_err();
+    }
+}
@@ -0,0 +1,248 @@
+use crate::as_str::AsStr;
+use crate::constants::BAUD_RATES;
+use crate::xbee::{ApiMode, ParsePacketError};
+use enum_iterator::Sequence;
+use std::fmt;
+
+/// BD values from this up are the baud rate itself rather than an index into `BAUD_RATES`
+pub const BD_LITERAL_MIN: u32 = 0x80;
+
+/// The baud rate a BD value sets, `None` if it isn't a known rate
+pub fn bd_baud_rate(bd: u32) -> Option<u32> {
+    if bd >= BD_LITERAL_MIN {
+        Some(bd)
+    } else {
+        BAUD_RATES.get(bd as usize).copied()
+    }
+}
+
+/// The radio parameters we commonly need to read and change
+#[derive(Sequence, Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum AtParameter {
+    /// CH - the operating channel
+    Channel,
+
+    /// ID - the PAN ID
+    PanId,
+
+    /// MY - the radio's 16-bit source address
+    SourceAddress,
+
+    /// DH - the upper 32 bits of the 64-bit destination address
+    DestinationHigh,
+
+    /// DL - the lower 32 bits of the 64-bit destination address
+    DestinationLow,
+
+    /// PL - the transmit power level
+    PowerLevel,
+
+    /// AP - the API mode
+    ApiMode,
+
+    /// BD - the serial interface baud rate
+    BaudRate,
+
+    /// NI - the node identifier string
+    NodeIdentifier,
+}
+
+impl AtParameter {
+    /// The two character AT command for this parameter
+    pub const fn command(&self) -> [u8; 2] {
+        match self {
+            AtParameter::Channel => *b"CH",
+            AtParameter::PanId => *b"ID",
+            AtParameter::SourceAddress => *b"MY",
+            AtParameter::DestinationHigh => *b"DH",
+            AtParameter::DestinationLow => *b"DL",
+            AtParameter::PowerLevel => *b"PL",
+            AtParameter::ApiMode => *b"AP",
+            AtParameter::BaudRate => *b"BD",
+            AtParameter::NodeIdentifier => *b"NI",
+        }
+    }
+
+    /// Find the parameter for a two character AT command
+    pub fn from_command(command: [u8; 2]) -> Option<Self> {
+        enum_iterator::all::<Self>().find(|p| p.command() == command)
+    }
+
+    /// A zeroed value for this parameter, used as a starting point for editing. The API mode
+    /// starts at the default mode as AP=0 (transparent mode) isn't supported.
+    pub fn default_value(&self) -> AtValue {
+        match self {
+            AtParameter::Channel => AtValue::Channel(0),
+            AtParameter::PanId => AtValue::PanId(0),
+            AtParameter::SourceAddress => AtValue::SourceAddress(0),
+            AtParameter::DestinationHigh => AtValue::DestinationHigh(0),
+            AtParameter::DestinationLow => AtValue::DestinationLow(0),
+            AtParameter::PowerLevel => AtValue::PowerLevel(0),
+            AtParameter::ApiMode => AtValue::ApiMode(ApiMode::default().ap_value()),
+            AtParameter::BaudRate => AtValue::BaudRate(0),
+            AtParameter::NodeIdentifier => AtValue::NodeIdentifier(String::new()),
+        }
+    }
+}
+
+impl AsStr for AtParameter {
+    fn as_str(&self) -> &'static str {
+        match self {
+            AtParameter::Channel => "Channel (CH)",
+            AtParameter::PanId => "PAN ID (ID)",
+            AtParameter::SourceAddress => "Source Address (MY)",
+            AtParameter::DestinationHigh => "Destination High (DH)",
+            AtParameter::DestinationLow => "Destination Low (DL)",
+            AtParameter::PowerLevel => "Power Level (PL)",
+            AtParameter::ApiMode => "API Mode (AP)",
+            AtParameter::BaudRate => "Baud Rate (BD)",
+            AtParameter::NodeIdentifier => "Node Identifier (NI)",
+        }
+    }
+}
+
+impl fmt::Display for AtParameter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A typed value for one of the `AtParameter`s
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum AtValue {
+    Channel(u8),
+    PanId(u16),
+    SourceAddress(u16),
+    DestinationHigh(u32),
+    DestinationLow(u32),
+    PowerLevel(u8),
+    ApiMode(u8),
+    /// An index into `BAUD_RATES`, or the baud rate itself from `BD_LITERAL_MIN` up
+    BaudRate(u32),
+    NodeIdentifier(String),
+}
+
+impl AtValue {
+    /// The parameter this value is for
+    pub const fn parameter(&self) -> AtParameter {
+        match self {
+            AtValue::Channel(_) => AtParameter::Channel,
+            AtValue::PanId(_) => AtParameter::PanId,
+            AtValue::SourceAddress(_) => AtParameter::SourceAddress,
+            AtValue::DestinationHigh(_) => AtParameter::DestinationHigh,
+            AtValue::DestinationLow(_) => AtParameter::DestinationLow,
+            AtValue::PowerLevel(_) => AtParameter::PowerLevel,
+            AtValue::ApiMode(_) => AtParameter::ApiMode,
+            AtValue::BaudRate(_) => AtParameter::BaudRate,
+            AtValue::NodeIdentifier(_) => AtParameter::NodeIdentifier,
+        }
+    }
+
+    /// Encode the value as the parameter bytes of an AT command
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            AtValue::Channel(v) | AtValue::PowerLevel(v) | AtValue::ApiMode(v) => vec![*v],
+            // without the leading zero bytes
+            AtValue::BaudRate(v) => {
+                let bytes = v.to_be_bytes();
+                let start = bytes.iter().position(|b| *b != 0).unwrap_or(3);
+                bytes[start..].to_vec()
+            }
+            AtValue::PanId(v) | AtValue::SourceAddress(v) => v.to_be_bytes().to_vec(),
+            AtValue::DestinationHigh(v) | AtValue::DestinationLow(v) => v.to_be_bytes().to_vec(),
+            AtValue::NodeIdentifier(s) => s.as_bytes().to_vec(),
+        }
+    }
+
+    /// Decode the data of an AT command response for the given parameter.
+    /// Numeric values may be sent with their leading zero bytes removed.
+    pub fn decode(param: AtParameter, data: &[u8]) -> Result<Self, ParsePacketError> {
+        let numeric = |width: usize| -> Result<u32, ParsePacketError> {
+            if data.is_empty() || data.len() > width {
+                return Err(ParsePacketError::InvalidData);
+            }
+            Ok(data.iter().fold(0u32, |acc, b| (acc << 8) | *b as u32))
+        };
+
+        Ok(match param {
+            AtParameter::Channel => AtValue::Channel(numeric(1)? as u8),
+            AtParameter::PanId => AtValue::PanId(numeric(2)? as u16),
+            AtParameter::SourceAddress => AtValue::SourceAddress(numeric(2)? as u16),
+            AtParameter::DestinationHigh => AtValue::DestinationHigh(numeric(4)?),
+            AtParameter::DestinationLow => AtValue::DestinationLow(numeric(4)?),
+            AtParameter::PowerLevel => AtValue::PowerLevel(numeric(1)? as u8),
+            AtParameter::ApiMode => AtValue::ApiMode(numeric(1)? as u8),
+            AtParameter::BaudRate => AtValue::BaudRate(numeric(4)?),
+            AtParameter::NodeIdentifier => {
+                AtValue::NodeIdentifier(String::from_utf8_lossy(data).into_owned())
+            }
+        })
+    }
+}
+
+impl fmt::Display for AtValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AtValue::Channel(v) => write!(f, "0x{v:02X}"),
+            AtValue::PanId(v) | AtValue::SourceAddress(v) => write!(f, "0x{v:04X}"),
+            AtValue::DestinationHigh(v) | AtValue::DestinationLow(v) => write!(f, "0x{v:08X}"),
+            AtValue::PowerLevel(v) | AtValue::ApiMode(v) => write!(f, "{v}"),
+            AtValue::BaudRate(v) => match bd_baud_rate(*v) {
+                Some(baud) => write!(f, "{baud}"),
+                None => write!(f, "non-standard ({v})"),
+            },
+            AtValue::NodeIdentifier(s) => write!(f, "{s:?}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use enum_iterator::all;
+
+    #[test]
+    fn test_at_parameter_command_round_trip() {
+        for param in all::<AtParameter>() {
+            assert_eq!(AtParameter::from_command(param.command()), Some(param));
+        }
+        assert_eq!(AtParameter::from_command(*b"XX"), None);
+    }
+
+    #[test]
+    fn test_at_value_decode() {
+        let values = [
+            AtValue::Channel(0x0C),
+            AtValue::PanId(0x3332),
+            AtValue::DestinationLow(0x4052_2BAA),
+            AtValue::BaudRate(8),
+            AtValue::BaudRate(0x3D090),
+            AtValue::NodeIdentifier("CANSAT".to_string()),
+        ];
+
+        for value in values {
+            let decoded = AtValue::decode(value.parameter(), &value.encode()).unwrap();
+            assert_eq!(decoded, value);
+        }
+
+        // leading zeroes may be removed
+        assert_eq!(
+            AtValue::decode(AtParameter::SourceAddress, &[0x01]).unwrap(),
+            AtValue::SourceAddress(0x0001)
+        );
+        AtValue::decode(AtParameter::PanId, &[0x01, 0x02, 0x03]).unwrap_err();
+    }
+
+    #[test]
+    fn test_at_value_decode_baud_rate() {
+        // a non-standard rate is sent as the rate itself in up to 4 bytes
+        let bd = AtValue::decode(AtParameter::BaudRate, &[0x00, 0x03, 0xD0, 0x90]).unwrap();
+        assert_eq!(bd, AtValue::BaudRate(250_000));
+        assert_eq!(bd.to_string(), "250000");
+
+        // standard rates are an index
+        let bd = AtValue::decode(AtParameter::BaudRate, &[0x00, 0x00, 0x00, 0x07]).unwrap();
+        assert_eq!(bd.to_string(), "115200");
+        assert_eq!(bd_baud_rate(0x10), None);
+    }
+}
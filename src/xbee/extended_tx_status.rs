@@ -0,0 +1,145 @@
+use crate::as_str::AsStr;
+use crate::xbee::ParsePacketError::IncorrectFrameType;
+use crate::xbee::{DeliveryStatus, ParsePacketError, TxStatus, XbeePacket};
+use byteorder::{BigEndian, ReadBytesExt};
+use enum_primitive_derive::Primitive;
+use num_traits::FromPrimitive;
+use std::fmt;
+use std::io::Cursor;
+
+/// The network discovery that was required to deliver a packet
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Primitive)]
+pub enum DiscoveryStatus {
+    NoDiscoveryOverhead = 0x00,
+    AddressDiscovery = 0x01,
+    RouteDiscovery = 0x02,
+    AddressAndRouteDiscovery = 0x03,
+    ExtendedTimeoutDiscovery = 0x40,
+    UNKNOWN = 0xFF,
+}
+
+impl AsStr for DiscoveryStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::NoDiscoveryOverhead => "No discovery overhead",
+            Self::AddressDiscovery => "Address discovery",
+            Self::RouteDiscovery => "Route discovery",
+            Self::AddressAndRouteDiscovery => "Address and route discovery",
+            Self::ExtendedTimeoutDiscovery => "Extended timeout discovery",
+            Self::UNKNOWN => "Unknown",
+        }
+    }
+}
+
+impl fmt::Display for DiscoveryStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The Zigbee / DigiMesh extended transmit status (frame type 0x8B)
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ExtendedTxStatus {
+    pub frame_id: u8,
+    /// The 16-bit address the packet was delivered to
+    pub dst16: u16,
+    /// The number of application retransmissions that occurred
+    pub retry_count: u8,
+    pub status: DeliveryStatus,
+    pub discovery: DiscoveryStatus,
+}
+
+impl TryFrom<XbeePacket> for ExtendedTxStatus {
+    type Error = ParsePacketError;
+
+    fn try_from(xbp: XbeePacket) -> Result<Self, Self::Error> {
+        let XbeePacket {
+            frame_type,
+            ref data,
+            ..
+        } = xbp;
+
+        // check the frame type
+        if frame_type != 0x8B {
+            return Err(IncorrectFrameType);
+        }
+
+        let mut cur = Cursor::new(data.as_slice());
+        let frame_id = cur.read_u8()?;
+        let dst16 = cur.read_u16::<BigEndian>()?;
+        let retry_count = cur.read_u8()?;
+        let status = DeliveryStatus::from_u8(cur.read_u8()?).unwrap_or(DeliveryStatus::UNKNOWN);
+        let discovery =
+            DiscoveryStatus::from_u8(cur.read_u8()?).unwrap_or(DiscoveryStatus::UNKNOWN);
+
+        Ok(ExtendedTxStatus {
+            frame_id,
+            dst16,
+            retry_count,
+            status,
+            discovery,
+        })
+    }
+}
+
+impl From<ExtendedTxStatus> for TxStatus {
+    fn from(status: ExtendedTxStatus) -> Self {
+        TxStatus {
+            frame_id: status.frame_id,
+            status: status.status,
+        }
+    }
+}
+
+impl fmt::Display for ExtendedTxStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ExtendedTxStatus {{ frame_id: {}, dst: {:02X}:{:02X}, retries: {}, status: {}, discovery: {} }}",
+            self.frame_id,
+            self.dst16 >> 8,
+            self.dst16 & 0xFF,
+            self.retry_count,
+            self.status,
+            self.discovery,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xbee::ApiMode;
+    use hex_literal::hex;
+
+    #[test]
+    fn test_extended_tx_status_parse() {
+        // example taken from the XBee documentation
+        let raw = hex!("7E 00 07 8B 01 7D 84 00 00 01 71");
+        let xbp = XbeePacket::decode(&raw, ApiMode::Unescaped).unwrap();
+
+        let packet = ExtendedTxStatus::try_from(xbp).unwrap();
+
+        assert_eq!(
+            packet,
+            ExtendedTxStatus {
+                frame_id: 1,
+                dst16: 0x7D84,
+                retry_count: 0,
+                status: DeliveryStatus::Success,
+                discovery: DiscoveryStatus::AddressDiscovery,
+            }
+        );
+    }
+
+    #[test]
+    fn test_extended_tx_status_parse_fails_invalid_frame_type() {
+        let xbp = XbeePacket {
+            frame_type: 0x89,
+            data: hex!("01 7D 84 00 00 01").to_vec(),
+            checksum: 1,
+        };
+
+        let _packet = ExtendedTxStatus::try_from(xbp).unwrap_err();
+    }
+}
@@ -0,0 +1,329 @@
+use crate::xbee::{ApiMode, XbeePacket, ESCAPE, ESCAPE_XOR, START_DELIMITER};
+use std::mem;
+
+/// The largest frame length the decoder will accept, anything larger is assumed to be a false
+/// start. This is well above the largest frame any of our radios can send.
+pub const MAX_FRAME_LEN: u16 = 0x200;
+
+/// Something that happened while decoding the incoming byte stream
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum DecodeEvent {
+    /// A complete frame with a valid checksum
+    Frame(XbeePacket),
+
+    /// Bytes that were skipped while searching for a start delimiter
+    Garbage(Vec<u8>),
+
+    /// The raw bytes of a complete frame whose checksum didn't match
+    BadChecksum(Vec<u8>),
+
+    /// The raw bytes of a partial frame that was abandoned, either because a new frame started
+    /// part way through it or because its length was invalid
+    Resync(Vec<u8>),
+}
+
+/// The part of the frame the decoder expects next
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum State {
+    /// Searching for a start delimiter
+    Start,
+    /// The high byte of the length
+    LengthMsb,
+    /// The low byte of the length
+    LengthLsb,
+    /// The frame type and frame data
+    Data,
+    /// The checksum
+    Checksum,
+}
+
+/// An incremental decoder for XBee API frames.
+///
+/// Bytes can be fed in arbitrarily sized chunks, frames split across several chunks are
+/// reassembled and any data which isn't part of a valid frame is reported so it isn't lost.
+#[derive(Debug, Clone)]
+pub struct FrameDecoder {
+    /// The API mode of the incoming data
+    mode: ApiMode,
+
+    /// What we expect the next byte to be
+    state: State,
+
+    /// Was the previous byte an escape byte?
+    escaped: bool,
+
+    /// The length of the frame currently being decoded
+    len: u16,
+
+    /// The raw bytes of the current frame, or the garbage bytes while searching for a frame
+    raw: Vec<u8>,
+
+    /// The un-escaped frame type and data of the current frame
+    frame: Vec<u8>,
+}
+
+impl FrameDecoder {
+    pub fn new(mode: ApiMode) -> Self {
+        Self {
+            mode,
+            state: State::Start,
+            escaped: false,
+            len: 0,
+            raw: vec![],
+            frame: vec![],
+        }
+    }
+
+    /// The API mode this decoder expects
+    pub fn mode(&self) -> ApiMode {
+        self.mode
+    }
+
+    /// Is the decoder holding on to any bytes?
+    pub fn is_empty(&self) -> bool {
+        self.raw.is_empty()
+    }
+
+    /// Feed some bytes into the decoder, returning the events they completed
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<DecodeEvent> {
+        let mut events = vec![];
+        for &byte in bytes {
+            self.push(byte, &mut events);
+        }
+
+        // don't let garbage build up forever if no frames are arriving
+        if self.state == State::Start && self.raw.len() >= MAX_FRAME_LEN as usize {
+            events.push(DecodeEvent::Garbage(mem::take(&mut self.raw)));
+        }
+
+        events
+    }
+
+    /// Give up on any partially decoded data, e.g. when the radio is disconnected
+    pub fn flush(&mut self) -> Option<DecodeEvent> {
+        let raw = mem::take(&mut self.raw);
+        let state = mem::replace(&mut self.state, State::Start);
+        self.escaped = false;
+        self.frame.clear();
+
+        match (raw.is_empty(), state) {
+            (true, _) => None,
+            (false, State::Start) => Some(DecodeEvent::Garbage(raw)),
+            (false, _) => Some(DecodeEvent::Resync(raw)),
+        }
+    }
+
+    fn push(&mut self, byte: u8, events: &mut Vec<DecodeEvent>) {
+        // in escaped mode an unescaped start delimiter always begins a new frame
+        if byte == START_DELIMITER && (self.state == State::Start || self.mode == ApiMode::Escaped)
+        {
+            events.extend(self.flush());
+            self.raw.push(byte);
+            self.state = State::LengthMsb;
+            return;
+        }
+
+        self.raw.push(byte);
+        if self.state == State::Start {
+            return;
+        }
+
+        // un-escape the byte if required
+        let byte = if self.mode == ApiMode::Unescaped {
+            byte
+        } else if self.escaped {
+            self.escaped = false;
+            byte ^ ESCAPE_XOR
+        } else if byte == ESCAPE {
+            self.escaped = true;
+            return;
+        } else {
+            byte
+        };
+
+        match self.state {
+            State::Start => unreachable!("handled above"),
+            State::LengthMsb => {
+                self.len = (byte as u16) << 8;
+                self.state = State::LengthLsb;
+            }
+            State::LengthLsb => {
+                self.len |= byte as u16;
+                if self.len == 0 || self.len > MAX_FRAME_LEN {
+                    tracing::debug!("Invalid frame length - len={}", self.len);
+                    self.resync(DecodeEvent::Resync, events);
+                } else {
+                    self.state = State::Data;
+                }
+            }
+            State::Data => {
+                self.frame.push(byte);
+                if self.frame.len() == self.len as usize {
+                    self.state = State::Checksum;
+                }
+            }
+            State::Checksum => {
+                let sum = self.frame.iter().fold(byte, |acc, x| acc.wrapping_add(*x));
+                if sum == 0xFF {
+                    let frame = mem::take(&mut self.frame);
+                    self.raw.clear();
+                    self.state = State::Start;
+                    events.push(DecodeEvent::Frame(XbeePacket {
+                        frame_type: frame[0],
+                        data: frame[1..].to_vec(),
+                        checksum: byte,
+                    }));
+                } else {
+                    tracing::debug!("Frame checksum didn't match - raw={:02X?}", self.raw);
+                    self.resync(DecodeEvent::BadChecksum, events);
+                }
+            }
+        }
+    }
+
+    /// Abandon the current frame, the start delimiter may have been a false start so any
+    /// later start delimiter is searched again for a real frame.
+    fn resync(&mut self, event: fn(Vec<u8>) -> DecodeEvent, events: &mut Vec<DecodeEvent>) {
+        let mut raw = mem::take(&mut self.raw);
+        self.state = State::Start;
+        self.escaped = false;
+        self.frame.clear();
+
+        let replay = match raw[1..].iter().position(|b| *b == START_DELIMITER) {
+            Some(pos) => raw.split_off(pos + 1),
+            None => vec![],
+        };
+        events.push(event(raw));
+
+        for byte in replay {
+            self.push(byte, events);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use enum_iterator::all;
+    use hex_literal::hex;
+
+    fn frames(events: &[DecodeEvent]) -> Vec<XbeePacket> {
+        events
+            .iter()
+            .filter_map(|e| match e {
+                DecodeEvent::Frame(packet) => Some(packet.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_decode_single_frame() {
+        let mut decoder = FrameDecoder::new(ApiMode::Unescaped);
+        let events = decoder.feed(&hex!("7E 00 09 01 01 FF FE 00 41 42 43 44 F6"));
+
+        assert_eq!(
+            events,
+            vec![DecodeEvent::Frame(XbeePacket::new(
+                0x01,
+                hex!("01 FF FE 00 41 42 43 44").to_vec()
+            ))]
+        );
+        assert!(decoder.is_empty());
+    }
+
+    #[test]
+    fn test_decode_frames_split_across_reads() {
+        for mode in all::<ApiMode>() {
+            let packets = [
+                XbeePacket::new(0x81, b"1047,hello".to_vec()),
+                XbeePacket::new(0x89, vec![0x7E, 0x00]),
+                XbeePacket::new(0x81, (0..0x10).collect()),
+            ];
+            let raw: Vec<u8> = packets
+                .iter()
+                .flat_map(|p| p.clone().serialise(mode).unwrap())
+                .collect();
+
+            // feed one byte at a time
+            let mut decoder = FrameDecoder::new(mode);
+            let events: Vec<_> = raw.chunks(1).flat_map(|c| decoder.feed(c)).collect();
+            assert_eq!(frames(&events), packets, "mode={mode:?}");
+            assert_eq!(events.len(), packets.len());
+
+            // and in awkward chunk sizes
+            let mut decoder = FrameDecoder::new(mode);
+            let events: Vec<_> = raw.chunks(7).flat_map(|c| decoder.feed(c)).collect();
+            assert_eq!(frames(&events), packets, "mode={mode:?}");
+        }
+    }
+
+    #[test]
+    fn test_decode_garbage_before_frame() {
+        let mut decoder = FrameDecoder::new(ApiMode::Escaped);
+        let events = decoder.feed(&hex!("41 42 43 7E 00 02 23 7D 31 CB"));
+
+        assert_eq!(
+            events,
+            vec![
+                DecodeEvent::Garbage(b"ABC".to_vec()),
+                DecodeEvent::Frame(XbeePacket::new(0x23, vec![0x11])),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_bad_checksum_then_good_frame() {
+        let mut decoder = FrameDecoder::new(ApiMode::Unescaped);
+        let events = decoder.feed(&hex!("7E 00 02 23 11 00 7E 00 02 23 11 CB"));
+
+        assert_eq!(
+            events,
+            vec![
+                DecodeEvent::BadChecksum(hex!("7E 00 02 23 11 00").to_vec()),
+                DecodeEvent::Frame(XbeePacket::new(0x23, vec![0x11])),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_escaped_resyncs_on_start_delimiter() {
+        // the first frame is cut short by the start of the second
+        let mut decoder = FrameDecoder::new(ApiMode::Escaped);
+        let events = decoder.feed(&hex!("7E 00 05 81 00 7E 00 02 23 7D 31 CB"));
+
+        assert_eq!(
+            events,
+            vec![
+                DecodeEvent::Resync(hex!("7E 00 05 81 00").to_vec()),
+                DecodeEvent::Frame(XbeePacket::new(0x23, vec![0x11])),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_unescaped_false_start() {
+        // a start delimiter with an impossible length shouldn't hide the real frame after it
+        let mut decoder = FrameDecoder::new(ApiMode::Unescaped);
+        let events = decoder.feed(&hex!("7E FF 7E 00 02 23 11 CB"));
+
+        assert_eq!(
+            events,
+            vec![
+                DecodeEvent::Resync(hex!("7E FF").to_vec()),
+                DecodeEvent::Frame(XbeePacket::new(0x23, vec![0x11])),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_flush() {
+        let mut decoder = FrameDecoder::new(ApiMode::Escaped);
+        assert!(decoder.feed(&hex!("7E 00 05 81")).is_empty());
+        assert_eq!(
+            decoder.flush(),
+            Some(DecodeEvent::Resync(hex!("7E 00 05 81").to_vec()))
+        );
+        assert_eq!(decoder.flush(), None);
+    }
+}
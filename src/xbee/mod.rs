@@ -1,14 +1,43 @@
 use anyhow::{bail, ensure};
-use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use std::fmt;
-use std::io::{Cursor, Result, Write};
+use std::io::{self, Result};
 use std::num::Wrapping;
 
+mod address;
+mod api_mode;
+mod at_command;
+mod at_parameter;
+mod extended_tx_status;
+mod frame_decoder;
+mod modem_status;
+mod receive_packet;
+mod remote_at_command;
+mod rx64_packet;
+mod rx_frame;
 mod rx_packet;
+mod transmit_request;
+mod tx64_request;
+mod tx_frame;
 mod tx_request;
 mod tx_status;
 
+pub use address::{Address, BROADCAST_ADDR_64, UNKNOWN_ADDR_16};
+pub use api_mode::{ApiMode, ESCAPE, ESCAPE_XOR, START_DELIMITER, XOFF, XON};
+pub use at_command::{AtCommand, AtCommandResponse, AtCommandStatus};
+pub use at_parameter::{bd_baud_rate, AtParameter, AtValue, BD_LITERAL_MIN};
+pub use extended_tx_status::{DiscoveryStatus, ExtendedTxStatus};
+pub use frame_decoder::{DecodeEvent, FrameDecoder, MAX_FRAME_LEN};
+pub use modem_status::ModemStatus;
+pub use receive_packet::ReceivePacket;
+pub use remote_at_command::{
+    RemoteAtCommand, RemoteAtCommandResponse, REMOTE_ADDR_64_USE_16, REMOTE_APPLY_CHANGES,
+};
+pub use rx64_packet::Rx64Packet;
+pub use rx_frame::RxFrame;
 pub use rx_packet::RxPacket;
+pub use transmit_request::TransmitRequest;
+pub use tx64_request::Tx64Request;
+pub use tx_frame::TxFrame;
 pub use tx_request::TxRequest;
 pub use tx_status::{DeliveryStatus, TxStatus};
 
@@ -30,59 +59,65 @@ impl XbeePacket {
         }
     }
 
-    /// Serialise the packet out to a vec
-    pub fn serialise(self) -> Result<Vec<u8>> {
-        let mut buf = vec![];
+    /// Serialise the packet out to a vec, escaping any bytes required by the API mode
+    pub fn serialise(self, mode: ApiMode) -> Result<Vec<u8>> {
+        // packet length = frame type + data
+        let len = u16::try_from(1 + self.data.len()).map_err(io::Error::other)?;
 
-        // start delimiter
-        buf.write_u8(0x7E)?;
+        // start delimiter, this is the only byte which is never escaped
+        let mut buf = vec![START_DELIMITER];
 
-        // packet length
-        buf.write_u16::<BigEndian>(1u16 + self.data.len() as u16)?;
+        // packet length, frame type, packet data then checksum
+        let frame_bytes = len
+            .to_be_bytes()
+            .into_iter()
+            .chain([self.frame_type])
+            .chain(self.data)
+            .chain([self.checksum]);
 
-        // frame type
-        buf.write_u8(self.frame_type)?;
-
-        // packet data
-        buf.write_all(&self.data)?;
-
-        // checksum
-        buf.write_u8(self.checksum)?;
+        for byte in frame_bytes {
+            mode.push_byte(&mut buf, byte);
+        }
 
         Ok(buf)
     }
 
     /// Attempt to decode a packet from a slice of bytes
-    pub fn decode(bytes: &[u8]) -> anyhow::Result<Self> {
-        let mut cur = Cursor::new(bytes);
+    pub fn decode(bytes: &[u8], mode: ApiMode) -> anyhow::Result<Self> {
+        Self::decode_with_len(bytes, mode).map(|(packet, _)| packet)
+    }
+
+    /// Attempt to decode a packet from the start of a slice of bytes, also returning
+    /// how many of the raw (possibly escaped) bytes the packet took up
+    pub fn decode_with_len(bytes: &[u8], mode: ApiMode) -> anyhow::Result<(Self, usize)> {
+        let mut reader = FrameReader {
+            bytes,
+            pos: 0,
+            mode,
+        };
         let mut checksum = Wrapping(0xFF_u8);
 
-        ensure!(cur.read_u8()? == 0x7E, "Invalid packet start byte");
-
-        let mut len = cur.read_u16::<BigEndian>()?;
-        // this is some weird fucking edge case :(
-        if len == 0x7D {
-            // MATE I DON'T FUCKING KNOW THIS SHIT IS BS
-            let next = cur.read_u8()?;
-            if next == 0x31 {
-                len = 0x11;
-            } else {
-                bail!("Attempted to fix edge case for length of 0x11, found invalid next - next={next:?}");
-            }
-        }
+        ensure!(
+            bytes.first() == Some(&START_DELIMITER),
+            "Invalid packet start byte"
+        );
+        reader.pos = 1;
+
+        let len = u16::from_be_bytes([reader.read_u8()?, reader.read_u8()?]);
+        ensure!(len > 0, "Packet length was zero");
 
-        let frame_type = cur.read_u8()?;
+        let frame_type = reader.read_u8()?;
         checksum -= frame_type;
 
         let mut data = vec![];
         for _ in 0..len - 1 {
-            let byte = cur.read_u8()?;
+            let byte = reader.read_u8()?;
             data.push(byte);
             checksum -= byte;
         }
 
         // check the checksum
-        let sent_checksum = cur.read_u8()?;
+        let sent_checksum = reader.read_u8()?;
         ensure!(checksum.0 == sent_checksum, "Packet checksum didn't match");
 
         let packet = XbeePacket {
@@ -90,7 +125,45 @@ impl XbeePacket {
             data,
             checksum: sent_checksum,
         };
-        Ok(packet)
+        Ok((packet, reader.pos))
+    }
+}
+
+/// Reads the bytes of a frame after the start delimiter, un-escaping them if required
+struct FrameReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    mode: ApiMode,
+}
+
+impl FrameReader<'_> {
+    fn next_raw(&mut self) -> anyhow::Result<u8> {
+        let Some(&byte) = self.bytes.get(self.pos) else {
+            bail!("Unexpected end of packet");
+        };
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn read_u8(&mut self) -> anyhow::Result<u8> {
+        let byte = self.next_raw()?;
+        if self.mode == ApiMode::Unescaped {
+            return Ok(byte);
+        }
+
+        match byte {
+            // an unescaped start delimiter means this packet was cut short
+            START_DELIMITER => bail!("Unexpected start delimiter inside packet"),
+            ESCAPE => {
+                let escaped = self.next_raw()?;
+                ensure!(
+                    escaped != START_DELIMITER,
+                    "Unexpected start delimiter after escape byte"
+                );
+                Ok(escaped ^ ESCAPE_XOR)
+            }
+            _ => Ok(byte),
+        }
     }
 }
 
@@ -98,6 +171,8 @@ impl XbeePacket {
 pub enum ParsePacketError {
     // indicates that the frame type was wrong
     IncorrectFrameType,
+    // the frame data was the wrong length or had invalid values
+    InvalidData,
     // a wrapper around an internal IO error
     IoError(std::io::Error),
 }
@@ -123,6 +198,7 @@ impl fmt::Display for XbeePacket {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use enum_iterator::all;
     use hex_literal::hex;
 
     #[test]
@@ -130,6 +206,78 @@ mod tests {
         const CORRECT: &[u8] = &hex!("7E 00 09 01 01 FF FE 00 41 42 43 44 F6");
         let packet = XbeePacket::new(0x01, hex!("01 FF FE 00 41 42 43 44").to_vec());
 
-        assert_eq!(packet.serialise().unwrap(), CORRECT);
+        assert_eq!(packet.serialise(ApiMode::Unescaped).unwrap(), CORRECT);
+    }
+
+    #[test]
+    fn test_escaped_packet_serialise() {
+        // example taken from the XBee documentation
+        const CORRECT: &[u8] = &hex!("7E 00 02 23 7D 31 CB");
+        let packet = XbeePacket::new(0x23, vec![0x11]);
+
+        assert_eq!(packet.clone().serialise(ApiMode::Escaped).unwrap(), CORRECT);
+        assert_eq!(
+            XbeePacket::decode(CORRECT, ApiMode::Escaped).unwrap(),
+            packet
+        );
+    }
+
+    #[test]
+    fn test_escaped_length_decode() {
+        // a length of 0x11 must be escaped
+        let packet = XbeePacket::new(0x81, (0..0x10).collect());
+        let raw = packet.clone().serialise(ApiMode::Escaped).unwrap();
+        assert_eq!(&raw[..4], &hex!("7E 00 7D 31"));
+
+        let (decoded, len) = XbeePacket::decode_with_len(&raw, ApiMode::Escaped).unwrap();
+        assert_eq!(decoded, packet);
+        assert_eq!(len, raw.len());
+    }
+
+    #[test]
+    fn test_escaped_round_trip_every_escapable_byte() {
+        for mode in all::<ApiMode>() {
+            for byte in [START_DELIMITER, ESCAPE, XON, XOFF] {
+                // escapable byte in the frame type, the data and surrounded by normal data
+                let packets = [
+                    XbeePacket::new(byte, vec![0x01, 0x02]),
+                    XbeePacket::new(0x81, vec![byte]),
+                    XbeePacket::new(0x81, vec![0x41, byte, byte, 0x42]),
+                ];
+
+                for packet in packets {
+                    let raw = packet.clone().serialise(mode).unwrap();
+                    // no special bytes may appear unescaped after the start delimiter
+                    if mode == ApiMode::Escaped {
+                        let mut escaped = false;
+                        for b in &raw[1..] {
+                            assert!(escaped || *b == ESCAPE || !ApiMode::needs_escape(*b));
+                            escaped = !escaped && *b == ESCAPE;
+                        }
+                    }
+
+                    let (decoded, len) = XbeePacket::decode_with_len(&raw, mode).unwrap();
+                    assert_eq!(decoded, packet, "mode={mode:?}, raw={raw:02X?}");
+                    assert_eq!(len, raw.len());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_escaped_round_trip_every_checksum() {
+        // vary the data so that the checksum takes every possible value
+        for value in 0..=u8::MAX {
+            let packet = XbeePacket::new(0x01, vec![value]);
+            let raw = packet.clone().serialise(ApiMode::Escaped).unwrap();
+            assert!(!raw[1..].contains(&START_DELIMITER));
+            assert_eq!(XbeePacket::decode(&raw, ApiMode::Escaped).unwrap(), packet);
+        }
+    }
+
+    #[test]
+    fn test_escaped_decode_fails_on_unexpected_start() {
+        let raw = hex!("7E 00 05 81 00 01 7E 00 02 23 7D 31 CB");
+        XbeePacket::decode(&raw, ApiMode::Escaped).unwrap_err();
     }
 }
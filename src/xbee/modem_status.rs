@@ -0,0 +1,163 @@
+use crate::as_str::AsStr;
+use crate::xbee::ParsePacketError::IncorrectFrameType;
+use crate::xbee::{ParsePacketError, XbeePacket};
+use byteorder::ReadBytesExt;
+use std::fmt;
+use std::io::Cursor;
+
+/// An event reported by the local radio in a modem status frame (frame type 0x8A)
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ModemStatus {
+    HardwareReset,
+    WatchdogReset,
+    Associated,
+    Disassociated,
+    CoordinatorStarted,
+    SecurityKeyUpdated,
+    NetworkWokeUp,
+    NetworkSleep,
+    VoltageExceeded,
+    ConfigChangedDuringJoin,
+    /// A status we don't know about, with the code the radio reported
+    Unknown(u8),
+}
+
+impl ModemStatus {
+    /// The status with this code
+    pub const fn from_code(code: u8) -> Self {
+        match code {
+            0x00 => Self::HardwareReset,
+            0x01 => Self::WatchdogReset,
+            0x02 => Self::Associated,
+            0x03 => Self::Disassociated,
+            0x06 => Self::CoordinatorStarted,
+            0x07 => Self::SecurityKeyUpdated,
+            0x0B => Self::NetworkWokeUp,
+            0x0C => Self::NetworkSleep,
+            0x0D => Self::VoltageExceeded,
+            0x11 => Self::ConfigChangedDuringJoin,
+            code => Self::Unknown(code),
+        }
+    }
+
+    /// The code the radio reports this status with
+    pub const fn code(&self) -> u8 {
+        match self {
+            Self::HardwareReset => 0x00,
+            Self::WatchdogReset => 0x01,
+            Self::Associated => 0x02,
+            Self::Disassociated => 0x03,
+            Self::CoordinatorStarted => 0x06,
+            Self::SecurityKeyUpdated => 0x07,
+            Self::NetworkWokeUp => 0x0B,
+            Self::NetworkSleep => 0x0C,
+            Self::VoltageExceeded => 0x0D,
+            Self::ConfigChangedDuringJoin => 0x11,
+            Self::Unknown(code) => *code,
+        }
+    }
+
+    /// Does this event mean something has gone wrong with the radio?
+    pub const fn is_fault(&self) -> bool {
+        matches!(
+            self,
+            Self::WatchdogReset | Self::Disassociated | Self::VoltageExceeded | Self::Unknown(_)
+        )
+    }
+}
+
+impl AsStr for ModemStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::HardwareReset => "Hardware reset",
+            Self::WatchdogReset => "Watchdog timer reset",
+            Self::Associated => "Joined network",
+            Self::Disassociated => "Left network",
+            Self::CoordinatorStarted => "Coordinator started",
+            Self::SecurityKeyUpdated => "Network security key updated",
+            Self::NetworkWokeUp => "Network woke up",
+            Self::NetworkSleep => "Network went to sleep",
+            Self::VoltageExceeded => "Voltage supply limit exceeded",
+            Self::ConfigChangedDuringJoin => "Configuration changed while joining",
+            Self::Unknown(_) => "Unknown",
+        }
+    }
+}
+
+impl fmt::Display for ModemStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unknown(code) => write!(f, "Unknown (0x{code:02X})"),
+            _ => f.write_str(self.as_str()),
+        }
+    }
+}
+
+impl From<ModemStatus> for XbeePacket {
+    fn from(status: ModemStatus) -> Self {
+        XbeePacket::new(0x8A, vec![status.code()])
+    }
+}
+
+impl TryFrom<XbeePacket> for ModemStatus {
+    type Error = ParsePacketError;
+
+    fn try_from(xbp: XbeePacket) -> Result<Self, Self::Error> {
+        let XbeePacket {
+            frame_type,
+            ref data,
+            ..
+        } = xbp;
+
+        // check the frame type
+        if frame_type != 0x8A {
+            return Err(IncorrectFrameType);
+        }
+
+        let mut cur = Cursor::new(data.as_slice());
+        let status = ModemStatus::from_code(cur.read_u8()?);
+
+        Ok(status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xbee::ApiMode;
+    use hex_literal::hex;
+
+    #[test]
+    fn test_modem_status_parse() {
+        // example taken from the XBee documentation
+        let raw = hex!("7E 00 02 8A 06 6F");
+        let xbp = XbeePacket::decode(&raw, ApiMode::Unescaped).unwrap();
+
+        let status = ModemStatus::try_from(xbp.clone()).unwrap();
+        assert_eq!(status, ModemStatus::CoordinatorStarted);
+        assert!(!status.is_fault());
+        assert_eq!(XbeePacket::from(status), xbp);
+    }
+
+    #[test]
+    fn test_modem_status_parse_keeps_unknown_code() {
+        let raw = hex!("7E 00 02 8A 42 33");
+        let xbp = XbeePacket::decode(&raw, ApiMode::Unescaped).unwrap();
+
+        let status = ModemStatus::try_from(xbp.clone()).unwrap();
+        assert_eq!(status, ModemStatus::Unknown(0x42));
+        assert_eq!(status.to_string(), "Unknown (0x42)");
+        assert_eq!(XbeePacket::from(status), xbp);
+    }
+
+    #[test]
+    fn test_modem_status_parse_fails_invalid_frame_type() {
+        let xbp = XbeePacket {
+            frame_type: 0x89,
+            data: hex!("01").to_vec(),
+            checksum: 1,
+        };
+
+        let _status = ModemStatus::try_from(xbp).unwrap_err();
+    }
+}
@@ -0,0 +1,96 @@
+use crate::xbee::ParsePacketError::IncorrectFrameType;
+use crate::xbee::{ParsePacketError, XbeePacket};
+use byteorder::{BigEndian, ReadBytesExt};
+use core::fmt;
+use std::io::Cursor;
+
+/// A Zigbee / DigiMesh receive packet (frame type 0x90)
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ReceivePacket {
+    pub src64: u64,
+    pub src16: u16,
+    pub options: u8,
+    pub data: Vec<u8>,
+}
+
+impl TryFrom<XbeePacket> for ReceivePacket {
+    type Error = ParsePacketError;
+
+    fn try_from(xbp: XbeePacket) -> Result<Self, Self::Error> {
+        let XbeePacket {
+            frame_type,
+            ref data,
+            ..
+        } = xbp;
+
+        // check the frame type
+        if frame_type != 0x90 {
+            return Err(IncorrectFrameType);
+        }
+
+        let mut cur = Cursor::new(data.as_slice());
+        let src64 = cur.read_u64::<BigEndian>()?;
+        let src16 = cur.read_u16::<BigEndian>()?;
+        let options = cur.read_u8()?;
+        let pos = cur.position() as usize;
+        let inner_data = data[pos..data.len()].to_vec();
+
+        Ok(ReceivePacket {
+            src64,
+            src16,
+            options,
+            data: inner_data,
+        })
+    }
+}
+
+impl fmt::Display for ReceivePacket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ReceivePacket {{ src: {:016X} ({:02X}:{:02X}), options: {:02}, data: {:?} }}",
+            self.src64,
+            self.src16 >> 8,
+            self.src16 & 0xFF,
+            self.options,
+            String::from_utf8_lossy(self.data.as_slice()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xbee::ApiMode;
+    use hex_literal::hex;
+
+    #[test]
+    fn test_receive_packet_parse() {
+        // example taken from the XBee documentation
+        let raw = hex!("7E 00 12 90 00 13 A2 00 40 52 2B AA 7D 84 01 52 78 44 61 74 61 0D");
+        let xbp = XbeePacket::decode(&raw, ApiMode::Unescaped).unwrap();
+
+        let packet = ReceivePacket::try_from(xbp).unwrap();
+
+        assert_eq!(
+            packet,
+            ReceivePacket {
+                src64: 0x0013_A200_4052_2BAA,
+                src16: 0x7D84,
+                options: 1,
+                data: b"RxData".to_vec(),
+            }
+        )
+    }
+
+    #[test]
+    fn test_receive_packet_parse_fails_invalid_frame_type() {
+        let xbp = XbeePacket {
+            frame_type: 0x80,
+            data: hex!("00 13 A2 00 40 52 2B AA 7D 84 01 52").to_vec(),
+            checksum: 1,
+        };
+
+        let _packet = ReceivePacket::try_from(xbp).unwrap_err();
+    }
+}
@@ -0,0 +1,291 @@
+use crate::xbee::ParsePacketError::IncorrectFrameType;
+use crate::xbee::{
+    Address, AtCommandStatus, AtParameter, AtValue, ParsePacketError, XbeePacket, UNKNOWN_ADDR_16,
+};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use num_traits::FromPrimitive;
+use std::fmt;
+use std::io::{Cursor, Read, Write};
+
+/// The 64-bit destination used when a remote radio is addressed by its 16-bit address
+pub const REMOTE_ADDR_64_USE_16: u64 = 0xFFFF_FFFF_FFFF_FFFF;
+
+/// Remote command option - apply the change immediately, otherwise AC or WR must be sent
+pub const REMOTE_APPLY_CHANGES: u8 = 0x02;
+
+/// An AT command sent over the air to another radio (frame type 0x17)
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RemoteAtCommand {
+    /// The frame ID, 0 means no response is sent
+    pub frame_id: u8,
+    /// The 64-bit address of the remote radio
+    pub dst64: u64,
+    /// The 16-bit address of the remote radio, `UNKNOWN_ADDR_16` when using the 64-bit address
+    pub dst16: u16,
+    /// Remote command options
+    pub options: u8,
+    /// The two character AT command
+    pub command: [u8; 2],
+    /// The value to set, empty to query the current value
+    pub parameter: Vec<u8>,
+}
+
+impl RemoteAtCommand {
+    pub fn new(frame_id: u8, dst: Address, command: [u8; 2], parameter: impl AsRef<[u8]>) -> Self {
+        let (dst64, dst16) = match dst {
+            Address::Short(addr) => (REMOTE_ADDR_64_USE_16, addr),
+            Address::Long(addr) => (addr, UNKNOWN_ADDR_16),
+        };
+
+        Self {
+            frame_id,
+            dst64,
+            dst16,
+            options: REMOTE_APPLY_CHANGES,
+            command,
+            parameter: parameter.as_ref().to_vec(),
+        }
+    }
+
+    /// Query the current value of a parameter on the remote radio
+    pub fn query(frame_id: u8, dst: Address, param: AtParameter) -> Self {
+        Self::new(frame_id, dst, param.command(), [])
+    }
+
+    /// Set the value of a parameter on the remote radio, the change is applied immediately
+    pub fn set(frame_id: u8, dst: Address, value: &AtValue) -> Self {
+        Self::new(frame_id, dst, value.parameter().command(), value.encode())
+    }
+
+    /// WR - write the remote radio's parameter values to non-volatile memory
+    pub fn write(frame_id: u8, dst: Address) -> Self {
+        Self::new(frame_id, dst, *b"WR", [])
+    }
+
+    /// The address the command is sent to
+    pub fn dst(&self) -> Address {
+        if self.dst64 == REMOTE_ADDR_64_USE_16 {
+            Address::Short(self.dst16)
+        } else {
+            Address::Long(self.dst64)
+        }
+    }
+}
+
+impl TryFrom<RemoteAtCommand> for XbeePacket {
+    type Error = std::io::Error;
+
+    fn try_from(cmd: RemoteAtCommand) -> Result<Self, Self::Error> {
+        let mut buf = vec![];
+
+        // frame ID
+        buf.write_u8(cmd.frame_id)?;
+
+        // destination addresses
+        buf.write_u64::<BigEndian>(cmd.dst64)?;
+        buf.write_u16::<BigEndian>(cmd.dst16)?;
+
+        // remote command options
+        buf.write_u8(cmd.options)?;
+
+        // AT command
+        buf.write_all(&cmd.command)?;
+
+        // parameter value
+        buf.write_all(&cmd.parameter)?;
+
+        Ok(XbeePacket::new(0x17, buf))
+    }
+}
+
+impl TryFrom<XbeePacket> for RemoteAtCommand {
+    type Error = ParsePacketError;
+
+    fn try_from(xbp: XbeePacket) -> Result<Self, Self::Error> {
+        let XbeePacket {
+            frame_type,
+            ref data,
+            ..
+        } = xbp;
+
+        // check the frame type
+        if frame_type != 0x17 {
+            return Err(IncorrectFrameType);
+        }
+
+        let mut cur = Cursor::new(data.as_slice());
+        let frame_id = cur.read_u8()?;
+        let dst64 = cur.read_u64::<BigEndian>()?;
+        let dst16 = cur.read_u16::<BigEndian>()?;
+        let options = cur.read_u8()?;
+        let mut command = [0; 2];
+        cur.read_exact(&mut command)?;
+        let pos = cur.position() as usize;
+
+        Ok(RemoteAtCommand {
+            frame_id,
+            dst64,
+            dst16,
+            options,
+            command,
+            parameter: data[pos..].to_vec(),
+        })
+    }
+}
+
+impl fmt::Display for RemoteAtCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "RemoteAtCommand {{ frame_id: {}, dst: {}, command: {}, parameter: {:02X?} }}",
+            self.frame_id,
+            self.dst(),
+            String::from_utf8_lossy(&self.command),
+            self.parameter,
+        )
+    }
+}
+
+/// The response to an AT command sent to another radio (frame type 0x97)
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RemoteAtCommandResponse {
+    pub frame_id: u8,
+    pub src64: u64,
+    pub src16: u16,
+    pub command: [u8; 2],
+    pub status: AtCommandStatus,
+    /// The value of the parameter if it was queried
+    pub data: Vec<u8>,
+}
+
+impl RemoteAtCommandResponse {
+    /// The parameter this response is for, if it is one we know about
+    pub fn parameter(&self) -> Option<AtParameter> {
+        AtParameter::from_command(self.command)
+    }
+
+    /// The typed value returned by a successful query
+    pub fn value(&self) -> Option<AtValue> {
+        if self.status != AtCommandStatus::Ok || self.data.is_empty() {
+            return None;
+        }
+
+        AtValue::decode(self.parameter()?, &self.data).ok()
+    }
+}
+
+impl TryFrom<XbeePacket> for RemoteAtCommandResponse {
+    type Error = ParsePacketError;
+
+    fn try_from(xbp: XbeePacket) -> Result<Self, Self::Error> {
+        let XbeePacket {
+            frame_type,
+            ref data,
+            ..
+        } = xbp;
+
+        // check the frame type
+        if frame_type != 0x97 {
+            return Err(IncorrectFrameType);
+        }
+
+        let mut cur = Cursor::new(data.as_slice());
+        let frame_id = cur.read_u8()?;
+        let src64 = cur.read_u64::<BigEndian>()?;
+        let src16 = cur.read_u16::<BigEndian>()?;
+        let mut command = [0; 2];
+        cur.read_exact(&mut command)?;
+        let status = AtCommandStatus::from_u8(cur.read_u8()?).unwrap_or(AtCommandStatus::UNKNOWN);
+        let pos = cur.position() as usize;
+
+        Ok(RemoteAtCommandResponse {
+            frame_id,
+            src64,
+            src16,
+            command,
+            status,
+            data: data[pos..].to_vec(),
+        })
+    }
+}
+
+impl fmt::Display for RemoteAtCommandResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "RemoteAtCommandResponse {{ frame_id: {}, src64: {}, src16: {}, command: {}, status: {}, data: {:02X?} }}",
+            self.frame_id,
+            Address::Long(self.src64),
+            Address::Short(self.src16),
+            String::from_utf8_lossy(&self.command),
+            self.status,
+            self.data,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xbee::ApiMode;
+    use hex_literal::hex;
+
+    #[test]
+    fn test_remote_at_command_serialisation() {
+        // example taken from the XBee documentation
+        const BH: &[u8] = &hex!("7E 00 10 17 01 00 13 A2 00 40 40 11 22 FF FE 02 42 48 01 F5");
+        let cmd = RemoteAtCommand::new(0x01, Address::Long(0x0013_A200_4040_1122), *b"BH", [1]);
+        let packet: XbeePacket = cmd.clone().try_into().unwrap();
+        assert_eq!(packet.clone().serialise(ApiMode::Unescaped).unwrap(), BH);
+        assert_eq!(RemoteAtCommand::try_from(packet).unwrap(), cmd);
+    }
+
+    #[test]
+    fn test_remote_at_command_short_address() {
+        let cmd = RemoteAtCommand::set(0x05, Address::Short(0x0001), &AtValue::PowerLevel(2));
+        assert_eq!(cmd.dst64, REMOTE_ADDR_64_USE_16);
+        assert_eq!(cmd.dst16, 0x0001);
+        assert_eq!(cmd.dst(), Address::Short(0x0001));
+
+        let packet: XbeePacket = cmd.try_into().unwrap();
+        assert_eq!(
+            packet.data,
+            hex!("05 FF FF FF FF FF FF FF FF 00 01 02 50 4C 02").to_vec()
+        );
+    }
+
+    #[test]
+    fn test_remote_at_response_parse() {
+        let xbp = XbeePacket::new(
+            0x97,
+            hex!("27 00 13 A2 00 40 52 2B AA 00 01 50 4C 00 04").to_vec(),
+        );
+        let raw = xbp.serialise(ApiMode::Escaped).unwrap();
+        let xbp = XbeePacket::decode(&raw, ApiMode::Escaped).unwrap();
+        let resp = RemoteAtCommandResponse::try_from(xbp).unwrap();
+
+        assert_eq!(
+            resp,
+            RemoteAtCommandResponse {
+                frame_id: 0x27,
+                src64: 0x0013_A200_4052_2BAA,
+                src16: 0x0001,
+                command: *b"PL",
+                status: AtCommandStatus::Ok,
+                data: vec![0x04],
+            }
+        );
+        assert_eq!(resp.value(), Some(AtValue::PowerLevel(4)));
+    }
+
+    #[test]
+    fn test_remote_at_response_parse_fails_invalid_frame_type() {
+        let xbp = XbeePacket {
+            frame_type: 0x88,
+            data: hex!("27 00 13 A2 00 40 52 2B AA 00 01 50 4C 00 04").to_vec(),
+            checksum: 1,
+        };
+
+        let _resp = RemoteAtCommandResponse::try_from(xbp).unwrap_err();
+    }
+}
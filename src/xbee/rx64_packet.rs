@@ -0,0 +1,94 @@
+use crate::xbee::ParsePacketError::IncorrectFrameType;
+use crate::xbee::{ParsePacketError, XbeePacket};
+use byteorder::{BigEndian, ReadBytesExt};
+use core::fmt;
+use std::io::Cursor;
+
+/// An RX packet from a radio using a 64-bit source address (frame type 0x80)
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Rx64Packet {
+    pub src_addr: u64,
+    pub rssi: i8,
+    pub options: u8,
+    pub data: Vec<u8>,
+}
+
+impl TryFrom<XbeePacket> for Rx64Packet {
+    type Error = ParsePacketError;
+
+    fn try_from(xbp: XbeePacket) -> Result<Self, Self::Error> {
+        let XbeePacket {
+            frame_type,
+            ref data,
+            ..
+        } = xbp;
+
+        // check the frame type
+        if frame_type != 0x80 {
+            return Err(IncorrectFrameType);
+        }
+
+        let mut cur = Cursor::new(data.as_slice());
+        let src_addr = cur.read_u64::<BigEndian>()?;
+        let rssi = cur.read_i8()?;
+        let options = cur.read_u8()?;
+        let pos = cur.position() as usize;
+        let inner_data = data[pos..data.len()].to_vec();
+
+        Ok(Rx64Packet {
+            src_addr,
+            rssi,
+            options,
+            data: inner_data,
+        })
+    }
+}
+
+impl fmt::Display for Rx64Packet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Rx64Packet {{ src: {:016X}, rssi: -{}dBm, options: {:02}, data: {:?} }}",
+            self.src_addr,
+            self.rssi,
+            self.options,
+            String::from_utf8_lossy(self.data.as_slice()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xbee::ApiMode;
+    use hex_literal::hex;
+
+    #[test]
+    fn test_rx64_packet_parse() {
+        let raw = hex!("7E 00 11 80 00 13 A2 00 40 52 2B AA 28 00 52 78 44 61 74 61 F7");
+        let xbp = XbeePacket::decode(&raw, ApiMode::Unescaped).unwrap();
+
+        let packet = Rx64Packet::try_from(xbp).unwrap();
+
+        assert_eq!(
+            packet,
+            Rx64Packet {
+                src_addr: 0x0013_A200_4052_2BAA,
+                rssi: 0x28,
+                options: 0,
+                data: b"RxData".to_vec(),
+            }
+        )
+    }
+
+    #[test]
+    fn test_rx64_packet_parse_fails_invalid_frame_type() {
+        let xbp = XbeePacket {
+            frame_type: 0x81,
+            data: hex!("00 13 A2 00 40 52 2B AA 28 00 52").to_vec(),
+            checksum: 1,
+        };
+
+        let _packet = Rx64Packet::try_from(xbp).unwrap_err();
+    }
+}
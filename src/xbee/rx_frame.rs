@@ -0,0 +1,73 @@
+use crate::xbee::ParsePacketError::IncorrectFrameType;
+use crate::xbee::{
+    Address, ParsePacketError, ReceivePacket, Rx64Packet, RxPacket, XbeePacket, UNKNOWN_ADDR_16,
+};
+use std::fmt;
+
+/// Any of the frames which carry data received over the air
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum RxFrame {
+    /// RX packet with a 16-bit source address (0x81)
+    Rx16(RxPacket),
+
+    /// RX packet with a 64-bit source address (0x80)
+    Rx64(Rx64Packet),
+
+    /// Zigbee / DigiMesh receive packet (0x90)
+    Receive(ReceivePacket),
+}
+
+impl RxFrame {
+    /// The data received over the air
+    pub fn data(&self) -> &[u8] {
+        match self {
+            RxFrame::Rx16(frame) => &frame.data,
+            RxFrame::Rx64(frame) => &frame.data,
+            RxFrame::Receive(frame) => &frame.data,
+        }
+    }
+
+    /// The RSSI of the received packet, Zigbee receive packets don't include one
+    pub fn rssi(&self) -> Option<i8> {
+        match self {
+            RxFrame::Rx16(frame) => Some(frame.rssi),
+            RxFrame::Rx64(frame) => Some(frame.rssi),
+            RxFrame::Receive(_) => None,
+        }
+    }
+
+    /// The address of the sender, preferring the 16-bit address if it is known
+    pub fn src(&self) -> Address {
+        match self {
+            RxFrame::Rx16(frame) => Address::Short(frame.src_addr),
+            RxFrame::Rx64(frame) => Address::Long(frame.src_addr),
+            RxFrame::Receive(frame) if frame.src16 != UNKNOWN_ADDR_16 => {
+                Address::Short(frame.src16)
+            }
+            RxFrame::Receive(frame) => Address::Long(frame.src64),
+        }
+    }
+}
+
+impl TryFrom<XbeePacket> for RxFrame {
+    type Error = ParsePacketError;
+
+    fn try_from(xbp: XbeePacket) -> Result<Self, Self::Error> {
+        match xbp.frame_type {
+            0x80 => Rx64Packet::try_from(xbp).map(RxFrame::Rx64),
+            0x81 => RxPacket::try_from(xbp).map(RxFrame::Rx16),
+            0x90 => ReceivePacket::try_from(xbp).map(RxFrame::Receive),
+            _ => Err(IncorrectFrameType),
+        }
+    }
+}
+
+impl fmt::Display for RxFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RxFrame::Rx16(frame) => frame.fmt(f),
+            RxFrame::Rx64(frame) => frame.fmt(f),
+            RxFrame::Receive(frame) => frame.fmt(f),
+        }
+    }
+}
@@ -1,8 +1,8 @@
 use crate::xbee::ParsePacketError::IncorrectFrameType;
 use crate::xbee::{ParsePacketError, XbeePacket};
-use byteorder::{BigEndian, ReadBytesExt};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use core::fmt;
-use std::io::Cursor;
+use std::io::{Cursor, Write};
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct RxPacket {
@@ -43,6 +43,26 @@ impl TryFrom<XbeePacket> for RxPacket {
     }
 }
 
+impl TryFrom<RxPacket> for XbeePacket {
+    type Error = std::io::Error;
+
+    fn try_from(packet: RxPacket) -> Result<Self, Self::Error> {
+        let mut buf = vec![];
+
+        // src addr
+        buf.write_u16::<BigEndian>(packet.src_addr)?;
+
+        // RSSI and options
+        buf.write_i8(packet.rssi)?;
+        buf.write_u8(packet.options)?;
+
+        // data
+        buf.write_all(&packet.data)?;
+
+        Ok(XbeePacket::new(0x81, buf))
+    }
+}
+
 impl fmt::Display for RxPacket {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -84,6 +104,21 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_rx_packet_serialise() {
+        let packet = RxPacket {
+            src_addr: 0x0001,
+            rssi: 0x28,
+            options: 0,
+            data: b"1047".to_vec(),
+        };
+
+        let xbp = XbeePacket::try_from(packet.clone()).unwrap();
+        assert_eq!(xbp.frame_type, 0x81);
+        assert_eq!(xbp.data, hex!("00 01 28 00 31 30 34 37").to_vec());
+        assert_eq!(RxPacket::try_from(xbp).unwrap(), packet);
+    }
+
     #[test]
     fn test_rx_packet_parse_fails_invalid_frame_type() {
         let xbp = XbeePacket {
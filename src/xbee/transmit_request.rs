@@ -0,0 +1,138 @@
+use crate::xbee::ParsePacketError::IncorrectFrameType;
+use crate::xbee::{ParsePacketError, XbeePacket, UNKNOWN_ADDR_16};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::fmt;
+use std::io::{Cursor, Write};
+
+/// A Zigbee / DigiMesh transmit request (frame type 0x10)
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TransmitRequest {
+    /// The frame ID
+    pub frame_id: u8,
+    /// The 64-bit destination address
+    pub dst64: u64,
+    /// The 16-bit destination address, 0xFFFE if unknown
+    pub dst16: u16,
+    /// The maximum number of hops for a broadcast, 0 for the maximum
+    pub broadcast_radius: u8,
+    /// The transmit options
+    pub options: u8,
+    /// The data to send
+    pub data: Vec<u8>,
+}
+
+impl TransmitRequest {
+    pub fn new(frame_id: u8, dst64: u64, data: impl AsRef<[u8]>) -> Self {
+        Self {
+            frame_id,
+            dst64,
+            dst16: UNKNOWN_ADDR_16,
+            broadcast_radius: 0,
+            options: 0,
+            data: data.as_ref().to_vec(),
+        }
+    }
+}
+
+impl TryFrom<TransmitRequest> for XbeePacket {
+    type Error = std::io::Error;
+
+    fn try_from(req: TransmitRequest) -> Result<Self, Self::Error> {
+        let mut buf = vec![];
+
+        // frame ID
+        buf.write_u8(req.frame_id)?;
+
+        // dst addrs
+        buf.write_u64::<BigEndian>(req.dst64)?;
+        buf.write_u16::<BigEndian>(req.dst16)?;
+
+        // broadcast radius and options
+        buf.write_u8(req.broadcast_radius)?;
+        buf.write_u8(req.options)?;
+
+        // data
+        buf.write_all(&req.data)?;
+
+        Ok(XbeePacket::new(0x10, buf))
+    }
+}
+
+impl TryFrom<XbeePacket> for TransmitRequest {
+    type Error = ParsePacketError;
+
+    fn try_from(xbp: XbeePacket) -> Result<Self, Self::Error> {
+        let XbeePacket {
+            frame_type,
+            ref data,
+            ..
+        } = xbp;
+
+        // check the frame type
+        if frame_type != 0x10 {
+            return Err(IncorrectFrameType);
+        }
+
+        let mut cur = Cursor::new(data.as_slice());
+        let frame_id = cur.read_u8()?;
+        let dst64 = cur.read_u64::<BigEndian>()?;
+        let dst16 = cur.read_u16::<BigEndian>()?;
+        let broadcast_radius = cur.read_u8()?;
+        let options = cur.read_u8()?;
+        let pos = cur.position() as usize;
+
+        Ok(TransmitRequest {
+            frame_id,
+            dst64,
+            dst16,
+            broadcast_radius,
+            options,
+            data: data[pos..].to_vec(),
+        })
+    }
+}
+
+impl fmt::Display for TransmitRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "TransmitRequest {{ frame_id: {}, dst: {:016X} ({:02X}:{:02X}), data: {:?} }}",
+            self.frame_id,
+            self.dst64,
+            self.dst16 >> 8,
+            self.dst16 & 0xFF,
+            String::from_utf8_lossy(self.data.as_slice())
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::xbee::ApiMode;
+    use hex_literal::hex;
+
+    #[test]
+    fn test_transmit_req_serialisation() {
+        // example taken from the XBee documentation
+        const CORRECT: &[u8] =
+            &hex!("7E 00 16 10 01 00 13 A2 00 40 0A 01 27 FF FE 00 00 54 78 44 61 74 61 30 41 13");
+
+        let req = TransmitRequest::new(1, 0x0013_A200_400A_0127, "TxData0A");
+        let packet: XbeePacket = req.clone().try_into().unwrap();
+        assert_eq!(
+            packet.clone().serialise(ApiMode::Unescaped).unwrap(),
+            CORRECT
+        );
+        assert_eq!(TransmitRequest::try_from(packet).unwrap(), req);
+    }
+
+    #[test]
+    fn test_transmit_req_parse_fails_invalid_frame_type() {
+        let xbp = XbeePacket::new(
+            0x00,
+            hex!("01 00 13 A2 00 40 0A 01 27 FF FE 00 00").to_vec(),
+        );
+        let _req = TransmitRequest::try_from(xbp).unwrap_err();
+    }
+}
@@ -0,0 +1,116 @@
+use crate::xbee::ParsePacketError::IncorrectFrameType;
+use crate::xbee::{ParsePacketError, XbeePacket};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::fmt;
+use std::io::{Cursor, Write};
+
+/// A TX request using a 64-bit destination address (frame type 0x00)
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Tx64Request {
+    /// The frame ID
+    pub frame_id: u8,
+    /// The 64-bit destination address
+    pub dst: u64,
+    /// The data to send
+    pub data: Vec<u8>,
+}
+
+impl Tx64Request {
+    pub fn new(frame_id: u8, dst: u64, data: impl AsRef<[u8]>) -> Self {
+        Self {
+            frame_id,
+            dst,
+            data: data.as_ref().to_vec(),
+        }
+    }
+}
+
+impl TryFrom<Tx64Request> for XbeePacket {
+    type Error = std::io::Error;
+
+    fn try_from(req: Tx64Request) -> Result<Self, Self::Error> {
+        let mut buf = vec![];
+
+        // frame ID
+        buf.write_u8(req.frame_id)?;
+
+        // dst addr
+        buf.write_u64::<BigEndian>(req.dst)?;
+
+        // options
+        buf.write_u8(0)?;
+
+        // data
+        buf.write_all(&req.data)?;
+
+        Ok(XbeePacket::new(0x00, buf))
+    }
+}
+
+impl TryFrom<XbeePacket> for Tx64Request {
+    type Error = ParsePacketError;
+
+    fn try_from(xbp: XbeePacket) -> Result<Self, Self::Error> {
+        let XbeePacket {
+            frame_type,
+            ref data,
+            ..
+        } = xbp;
+
+        // check the frame type
+        if frame_type != 0x00 {
+            return Err(IncorrectFrameType);
+        }
+
+        let mut cur = Cursor::new(data.as_slice());
+        let frame_id = cur.read_u8()?;
+        let dst = cur.read_u64::<BigEndian>()?;
+        let _options = cur.read_u8()?;
+        let pos = cur.position() as usize;
+
+        Ok(Tx64Request {
+            frame_id,
+            dst,
+            data: data[pos..].to_vec(),
+        })
+    }
+}
+
+impl fmt::Display for Tx64Request {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Tx64Request {{ frame_id: {}, dst: {:016X}, data: {:?} }}",
+            self.frame_id,
+            self.dst,
+            String::from_utf8_lossy(self.data.as_slice())
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::xbee::ApiMode;
+    use hex_literal::hex;
+
+    #[test]
+    fn test_tx64_req_serialisation() {
+        const CORRECT: &[u8] =
+            &hex!("7E 00 11 00 01 00 13 A2 00 40 0A 01 27 00 54 78 44 61 74 61 91");
+
+        let req = Tx64Request::new(1, 0x0013_A200_400A_0127, "TxData");
+        let packet: XbeePacket = req.clone().try_into().unwrap();
+        assert_eq!(
+            packet.clone().serialise(ApiMode::Unescaped).unwrap(),
+            CORRECT
+        );
+        assert_eq!(Tx64Request::try_from(packet).unwrap(), req);
+    }
+
+    #[test]
+    fn test_tx64_req_parse_fails_invalid_frame_type() {
+        let xbp = XbeePacket::new(0x01, hex!("01 00 13 A2 00 40 0A 01 27 00 54").to_vec());
+        let _req = Tx64Request::try_from(xbp).unwrap_err();
+    }
+}
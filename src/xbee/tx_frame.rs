@@ -0,0 +1,71 @@
+use crate::xbee::ParsePacketError::IncorrectFrameType;
+use crate::xbee::{ParsePacketError, TransmitRequest, Tx64Request, TxRequest, XbeePacket};
+use std::fmt;
+
+/// Any of the frames which send data over the air
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum TxFrame {
+    /// TX request with a 16-bit destination address (0x01)
+    Tx16(TxRequest),
+
+    /// TX request with a 64-bit destination address (0x00)
+    Tx64(Tx64Request),
+
+    /// Zigbee / DigiMesh transmit request (0x10)
+    Transmit(TransmitRequest),
+}
+
+impl TxFrame {
+    /// The frame ID of the request
+    pub fn frame_id(&self) -> u8 {
+        match self {
+            TxFrame::Tx16(req) => req.frame_id,
+            TxFrame::Tx64(req) => req.frame_id,
+            TxFrame::Transmit(req) => req.frame_id,
+        }
+    }
+
+    /// The data to be sent over the air
+    pub fn data(&self) -> &[u8] {
+        match self {
+            TxFrame::Tx16(req) => &req.data,
+            TxFrame::Tx64(req) => &req.data,
+            TxFrame::Transmit(req) => &req.data,
+        }
+    }
+}
+
+impl TryFrom<TxFrame> for XbeePacket {
+    type Error = std::io::Error;
+
+    fn try_from(frame: TxFrame) -> Result<Self, Self::Error> {
+        match frame {
+            TxFrame::Tx16(req) => req.try_into(),
+            TxFrame::Tx64(req) => req.try_into(),
+            TxFrame::Transmit(req) => req.try_into(),
+        }
+    }
+}
+
+impl TryFrom<XbeePacket> for TxFrame {
+    type Error = ParsePacketError;
+
+    fn try_from(xbp: XbeePacket) -> Result<Self, Self::Error> {
+        match xbp.frame_type {
+            0x00 => Tx64Request::try_from(xbp).map(TxFrame::Tx64),
+            0x01 => TxRequest::try_from(xbp).map(TxFrame::Tx16),
+            0x10 => TransmitRequest::try_from(xbp).map(TxFrame::Transmit),
+            _ => Err(IncorrectFrameType),
+        }
+    }
+}
+
+impl fmt::Display for TxFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TxFrame::Tx16(req) => req.fmt(f),
+            TxFrame::Tx64(req) => req.fmt(f),
+            TxFrame::Transmit(req) => req.fmt(f),
+        }
+    }
+}
@@ -1,7 +1,8 @@
-use crate::xbee::XbeePacket;
-use byteorder::{BigEndian, WriteBytesExt};
+use crate::xbee::ParsePacketError::IncorrectFrameType;
+use crate::xbee::{ParsePacketError, XbeePacket};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use std::fmt;
-use std::io::Write;
+use std::io::{Cursor, Write};
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct TxRequest {
@@ -45,6 +46,35 @@ impl TryFrom<TxRequest> for XbeePacket {
     }
 }
 
+impl TryFrom<XbeePacket> for TxRequest {
+    type Error = ParsePacketError;
+
+    fn try_from(xbp: XbeePacket) -> Result<Self, Self::Error> {
+        let XbeePacket {
+            frame_type,
+            ref data,
+            ..
+        } = xbp;
+
+        // check the frame type
+        if frame_type != 0x01 {
+            return Err(IncorrectFrameType);
+        }
+
+        let mut cur = Cursor::new(data.as_slice());
+        let frame_id = cur.read_u8()?;
+        let dst = cur.read_u16::<BigEndian>()?;
+        let _options = cur.read_u8()?;
+        let pos = cur.position() as usize;
+
+        Ok(TxRequest {
+            frame_id,
+            dst,
+            data: data[pos..].to_vec(),
+        })
+    }
+}
+
 impl fmt::Display for TxRequest {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -61,6 +91,7 @@ impl fmt::Display for TxRequest {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::xbee::ApiMode;
     use hex_literal::hex;
 
     #[test]
@@ -70,6 +101,13 @@ mod test {
 
         let req = TxRequest::new(1, 0x00_01, "CMD,1047,ST,GPS");
         let packet: XbeePacket = req.try_into().unwrap();
-        assert_eq!(packet.serialise().unwrap(), CAL);
+        assert_eq!(packet.serialise(ApiMode::Unescaped).unwrap(), CAL);
+    }
+
+    #[test]
+    fn test_tx_req_parse() {
+        let req = TxRequest::new(1, 0x00_01, "CMD,1047,ST,GPS");
+        let packet: XbeePacket = req.clone().try_into().unwrap();
+        assert_eq!(TxRequest::try_from(packet).unwrap(), req);
     }
 }
@@ -6,7 +6,7 @@ use num_traits::FromPrimitive;
 use std::fmt;
 
 // definitely don't need most of these but I thought I might as well implement them
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Primitive)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Primitive)]
 pub enum DeliveryStatus {
     Success = 0x00,
     NoAck = 0x01,
@@ -137,6 +137,12 @@ impl TryFrom<XbeePacket> for TxStatus {
     }
 }
 
+impl From<TxStatus> for XbeePacket {
+    fn from(status: TxStatus) -> Self {
+        XbeePacket::new(0x89, vec![status.frame_id, status.status as u8])
+    }
+}
+
 impl fmt::Display for TxStatus {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -171,6 +177,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_tx_status_serialise() {
+        let status = TxStatus {
+            frame_id: 7,
+            status: DeliveryStatus::NoAck,
+        };
+
+        let xbp = XbeePacket::from(status);
+        assert_eq!(xbp.frame_type, 0x89);
+        assert_eq!(TxStatus::try_from(xbp).unwrap(), status);
+    }
+
     #[test]
     fn test_rx_packet_parse_fails_invalid_frame_type() {
         let xbp = XbeePacket {